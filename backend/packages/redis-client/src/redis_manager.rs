@@ -140,4 +140,45 @@ impl RedisManager {
             .xadd::<(), _, _, _, _>(stream, false, None, "*", fields)
             .await
     }
+
+    pub async fn stream_add_batch(
+        &self,
+        stream: &str,
+        entries: &[Vec<(&str, &str)>],
+    ) -> Result<(), RedisError> {
+        let pipeline = self.client.pipeline();
+        for pairs in entries {
+            let fields: Vec<(String, String)> = pairs
+                .iter()
+                .map(|(field, value)| ((*field).to_owned(), (*value).to_owned()))
+                .collect();
+            pipeline
+                .xadd::<(), _, _, _, _>(stream, false, None, "*", fields)
+                .await?;
+        }
+
+        pipeline.all::<Vec<RedisValue>>().await.map(|_| ())
+    }
+
+    /// Like `stream_add_batch`, but lets Redis trim the stream to roughly `max_len`
+    /// entries. Meant for streams whose readers only follow the tail.
+    pub async fn stream_add_batch_capped(
+        &self,
+        stream: &str,
+        entries: &[Vec<(&str, &str)>],
+        max_len: i64,
+    ) -> Result<(), RedisError> {
+        let pipeline = self.client.pipeline();
+        for pairs in entries {
+            let fields: Vec<(String, String)> = pairs
+                .iter()
+                .map(|(field, value)| ((*field).to_owned(), (*value).to_owned()))
+                .collect();
+            pipeline
+                .xadd::<(), _, _, _, _>(stream, false, ("MAXLEN", "~", max_len), "*", fields)
+                .await?;
+        }
+
+        pipeline.all::<Vec<RedisValue>>().await.map(|_| ())
+    }
 }
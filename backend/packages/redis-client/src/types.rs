@@ -42,6 +42,9 @@ where
     pub success: bool,
     pub message: String,
     pub data: T,
+    /// Machine-readable failure code, set on error responses that have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
 }
 
 impl<T> RedisResponse<T>
@@ -54,6 +57,12 @@ where
             success,
             message: message.into(),
             data,
+            error_code: None,
         }
     }
+
+    pub fn with_error_code(mut self, code: impl Into<String>) -> Self {
+        self.error_code = Some(code.into());
+        self
+    }
 }
@@ -0,0 +1,4 @@
+-- Hidden (non-displayed) limit orders
+
+ALTER TABLE orders
+ADD COLUMN IF NOT EXISTS hidden BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,7 @@
+-- Cash and shares reserved by resting orders, kept apart from the available amounts
+
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS locked_balance BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE positions
+ADD COLUMN IF NOT EXISTS locked_quantity BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Cost of the shares currently held and profit already realized, per user and market
+
+ALTER TABLE positions
+ADD COLUMN IF NOT EXISTS cost_basis BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE positions
+ADD COLUMN IF NOT EXISTS realized_pnl BIGINT NOT NULL DEFAULT 0;
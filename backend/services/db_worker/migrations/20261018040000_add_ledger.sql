@@ -0,0 +1,53 @@
+-- Append-only double-entry ledger. Each transaction explains one balance change and its
+-- entries sum to zero; the available and locked entries of a user sum to the user's
+-- balance and locked_balance.
+
+CREATE TABLE IF NOT EXISTS ledger_transactions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    order_id BIGINT,
+    trade_id TEXT,
+    event_id BIGINT,
+    sequence BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_transactions_user_id ON ledger_transactions (user_id, id);
+
+-- One transaction per engine event, so events read again after a restart are not booked twice
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ledger_transactions_event
+    ON ledger_transactions (sequence, user_id, kind)
+    WHERE sequence > 0;
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id BIGINT NOT NULL REFERENCES ledger_transactions(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    account TEXT NOT NULL,
+    amount BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_transaction_id ON ledger_entries (transaction_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_user_account ON ledger_entries (user_id, account);
+
+-- Open the ledger with whatever users already hold so it agrees with their balances
+WITH opening AS (
+    INSERT INTO ledger_transactions (user_id, kind)
+    SELECT id, 'opening'
+    FROM users
+    WHERE (balance <> 0 OR locked_balance <> 0)
+      AND NOT EXISTS (SELECT 1 FROM ledger_transactions)
+    RETURNING id, user_id
+)
+INSERT INTO ledger_entries (transaction_id, user_id, account, amount)
+SELECT o.id, o.user_id, e.account, e.amount
+FROM opening o
+JOIN users u ON u.id = o.user_id
+CROSS JOIN LATERAL (
+    VALUES
+        ('external', -(u.balance + u.locked_balance)),
+        ('available', u.balance),
+        ('locked', u.locked_balance)
+) AS e(account, amount)
+WHERE e.amount <> 0;
@@ -0,0 +1,15 @@
+-- Deposit intents created with a payment provider; only confirmed ones were credited
+
+CREATE TABLE IF NOT EXISTS deposits (
+    id TEXT PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount BIGINT NOT NULL,
+    provider TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deposits_user_id ON deposits (user_id, created_at DESC);
+
+ALTER TABLE ledger_transactions ADD COLUMN IF NOT EXISTS deposit_id TEXT;
@@ -0,0 +1,33 @@
+-- OHLCV candles per market, kept for the canonical (YES) market that trades execute on
+
+CREATE TABLE IF NOT EXISTS candles (
+    market_id BIGINT NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    interval TEXT NOT NULL,
+    bucket_start TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    open BIGINT NOT NULL,
+    high BIGINT NOT NULL,
+    low BIGINT NOT NULL,
+    close BIGINT NOT NULL,
+    volume BIGINT NOT NULL,
+    trade_count BIGINT NOT NULL,
+    PRIMARY KEY (market_id, interval, bucket_start)
+);
+
+-- Backfill from the trades already stored
+INSERT INTO candles (market_id, interval, bucket_start, open, high, low, close, volume, trade_count)
+SELECT market_id, interval, bucket_start,
+       (ARRAY_AGG(price ORDER BY executed_at, id))[1],
+       MAX(price),
+       MIN(price),
+       (ARRAY_AGG(price ORDER BY executed_at DESC, id DESC))[1],
+       SUM(quantity),
+       COUNT(*)
+FROM (
+    SELECT t.id, t.market_id, t.price, t.quantity, t.executed_at, i.interval,
+           TO_TIMESTAMP(FLOOR(EXTRACT(EPOCH FROM t.executed_at) / i.seconds) * i.seconds)
+               AT TIME ZONE 'UTC' AS bucket_start
+    FROM trades t
+    CROSS JOIN (VALUES ('1m', 60), ('5m', 300), ('1h', 3600), ('1d', 86400)) AS i(interval, seconds)
+) bucketed
+GROUP BY market_id, interval, bucket_start
+ON CONFLICT (market_id, interval, bucket_start) DO NOTHING;
@@ -0,0 +1,2 @@
+-- Tickers aggregate the last 24h of candles across every market at once
+CREATE INDEX IF NOT EXISTS idx_candles_interval_bucket ON candles (interval, bucket_start);
@@ -0,0 +1,23 @@
+-- Leaderboard support: a per-user opt out, and a history of realized PnL so it can be
+-- summed over a time window. positions.realized_pnl only holds the running total.
+
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS hide_from_leaderboards BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS realized_pnl_changes (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    market_id BIGINT NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    amount BIGINT NOT NULL,
+    recorded_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_realized_pnl_changes_recorded_at
+ON realized_pnl_changes (recorded_at);
+
+-- Seed the history with what positions have realized so far, dated at their last update
+INSERT INTO realized_pnl_changes (user_id, market_id, amount, recorded_at)
+SELECT user_id, market_id, realized_pnl, updated_at
+FROM positions
+WHERE realized_pnl <> 0
+  AND NOT EXISTS (SELECT 1 FROM realized_pnl_changes);
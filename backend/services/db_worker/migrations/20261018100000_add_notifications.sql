@@ -0,0 +1,29 @@
+-- In-app notifications written by db_worker as fills, resolutions and payouts come in.
+-- `dedupe_key` keeps a redelivered db event from notifying twice. A missing preference row
+-- means the kind is enabled.
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    message TEXT NOT NULL,
+    market_id BIGINT,
+    event_id BIGINT,
+    order_id BIGINT,
+    amount BIGINT,
+    dedupe_key TEXT NOT NULL,
+    read_at TIMESTAMP WITHOUT TIME ZONE,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, dedupe_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications (user_id, id);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread
+ON notifications (user_id) WHERE read_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (user_id, kind)
+);
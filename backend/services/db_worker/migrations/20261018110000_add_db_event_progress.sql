@@ -0,0 +1,12 @@
+-- Highest engine event sequence db_worker has applied. db_events is re-read from the start
+-- on every restart, so anything at or below it is a redelivery and skipped. The engine
+-- continues its sequence from here when it bootstraps from the database.
+
+CREATE TABLE IF NOT EXISTS db_event_progress (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    last_sequence BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO db_event_progress (id, last_sequence)
+VALUES (TRUE, 0)
+ON CONFLICT (id) DO NOTHING;
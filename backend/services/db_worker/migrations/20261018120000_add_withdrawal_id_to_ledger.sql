@@ -0,0 +1,3 @@
+-- Links withdrawal ledger transactions to the withdrawal they belong to
+
+ALTER TABLE ledger_transactions ADD COLUMN IF NOT EXISTS withdrawal_id BIGINT;
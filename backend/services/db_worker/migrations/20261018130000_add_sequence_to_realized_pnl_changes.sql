@@ -0,0 +1,7 @@
+-- Keys realized PnL changes by the engine event that produced them, so a redelivered
+-- position update is recorded once. Rows seeded from positions keep sequence 0.
+
+ALTER TABLE realized_pnl_changes ADD COLUMN IF NOT EXISTS sequence BIGINT NOT NULL DEFAULT 0;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_realized_pnl_changes_sequence
+ON realized_pnl_changes (sequence) WHERE sequence > 0;
@@ -0,0 +1,48 @@
+-- Orders already resting had their reservation taken out of the available amounts, so it is
+-- moved over to the locked ones. A NO-market order rests on the YES book on the opposite side
+-- at 100 - price, which is where its reservation was taken.
+CREATE TEMP TABLE resting_reservations AS
+SELECT o.user_id,
+       COALESCE(y.id, o.market_id) AS market_id,
+       CASE
+           WHEN m.side = 'NO' AND o.side = 'Bid' THEN 'Ask'
+           WHEN m.side = 'NO' THEN 'Bid'
+           ELSE o.side
+       END AS side,
+       CASE WHEN m.side = 'NO' THEN 100 - o.price ELSE o.price END AS price,
+       o.remaining_qty
+FROM orders o
+JOIN markets m ON m.id = o.market_id
+LEFT JOIN markets y ON m.side = 'NO' AND y.outcome_id = m.outcome_id AND y.side = 'YES'
+WHERE o.status IN ('open', 'partially_filled') AND o.remaining_qty > 0;
+
+UPDATE users u
+SET locked_balance = r.total
+FROM (
+    SELECT user_id, SUM(price * remaining_qty) AS total
+    FROM resting_reservations
+    WHERE side = 'Bid'
+    GROUP BY user_id
+) r
+WHERE u.id = r.user_id;
+
+UPDATE positions p
+SET locked_quantity = r.total
+FROM (
+    SELECT user_id, market_id, SUM(remaining_qty) AS total
+    FROM resting_reservations
+    WHERE side = 'Ask'
+    GROUP BY user_id, market_id
+) r
+WHERE p.user_id = r.user_id AND p.market_id = r.market_id;
+
+INSERT INTO positions (user_id, market_id, quantity, locked_quantity)
+SELECT r.user_id, r.market_id, 0, SUM(r.remaining_qty)
+FROM resting_reservations r
+WHERE r.side = 'Ask'
+  AND NOT EXISTS (
+      SELECT 1 FROM positions p WHERE p.user_id = r.user_id AND p.market_id = r.market_id
+  )
+GROUP BY r.user_id, r.market_id;
+
+DROP TABLE resting_reservations;
@@ -0,0 +1,197 @@
+use super::common::send_read_response;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+
+pub const CANDLE_INTERVALS: [(&str, i64); 4] =
+    [("1m", 60), ("5m", 300), ("1h", 3600), ("1d", 86400)];
+const MAX_CANDLES: i64 = 1000;
+
+fn interval_seconds(interval: &str) -> Option<i64> {
+    CANDLE_INTERVALS
+        .iter()
+        .find(|(name, _)| *name == interval)
+        .map(|(_, seconds)| *seconds)
+}
+
+pub fn bucket_start(at: DateTime<Utc>, seconds: i64) -> NaiveDateTime {
+    let start = at.timestamp().div_euclid(seconds) * seconds;
+    DateTime::from_timestamp(start, 0).unwrap_or(at).naive_utc()
+}
+
+// Folds one trade into every interval's current candle. Trades reach us in execution order
+// per market, so the latest one is always the close.
+pub async fn record_trade_in_candles(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    market_id: i64,
+    price: i64,
+    quantity: i64,
+    executed_at: DateTime<Utc>,
+) -> Result<(), String> {
+    for (interval, seconds) in CANDLE_INTERVALS {
+        sqlx::query!(
+            r#"
+            INSERT INTO candles
+                (market_id, interval, bucket_start, open, high, low, close, volume, trade_count)
+            VALUES ($1, $2, $3, $4, $4, $4, $4, $5, 1)
+            ON CONFLICT (market_id, interval, bucket_start) DO UPDATE
+            SET high = GREATEST(candles.high, EXCLUDED.high),
+                low = LEAST(candles.low, EXCLUDED.low),
+                close = EXCLUDED.close,
+                volume = candles.volume + EXCLUDED.volume,
+                trade_count = candles.trade_count + 1
+            "#,
+            market_id,
+            interval,
+            bucket_start(executed_at, seconds),
+            price,
+            quantity,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to update {} candle: {}", interval, e))?;
+    }
+    Ok(())
+}
+
+// Candles are stored for the YES market only. A NO market reads its YES sibling's candles
+// with prices mirrored around 100, the same way the engine denormalizes NO prices.
+pub async fn handle_get_candles_by_market(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let market_id = data["market_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid market_id".to_string())? as i64;
+    let interval = data["interval"].as_str().unwrap_or("1m");
+    let Some(seconds) = interval_seconds(interval) else {
+        let response = RedisResponse::new(
+            400,
+            false,
+            format!(
+                "Invalid interval {}, expected one of 1m, 5m, 1h, 1d",
+                interval
+            ),
+            serde_json::json!(null),
+        );
+        send_read_response(&request_id, response).await?;
+        return Ok(());
+    };
+
+    let to = data["to"]
+        .as_i64()
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .unwrap_or_else(Utc::now);
+    let from = data["from"]
+        .as_i64()
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .unwrap_or_else(|| to - chrono::Duration::seconds(seconds * MAX_CANDLES));
+
+    let market = match sqlx::query!(
+        r#"
+        SELECT m.side, y.id AS "yes_market_id?"
+        FROM markets m
+        LEFT JOIN markets y ON y.outcome_id = m.outcome_id AND y.side = 'YES'
+        WHERE m.id = $1
+        "#,
+        market_id
+    )
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(market)) => market,
+        Ok(None) => {
+            let response =
+                RedisResponse::new(404, false, "Market not found", serde_json::json!(null));
+            send_read_response(&request_id, response).await?;
+            return Ok(());
+        }
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch market: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch market: {}", e));
+        }
+    };
+
+    let inverted = market.side == "NO";
+    let canonical_market_id = if inverted {
+        market.yes_market_id.unwrap_or(market_id)
+    } else {
+        market_id
+    };
+
+    let candles = match sqlx::query!(
+        r#"
+        SELECT bucket_start, open, high, low, close, volume, trade_count
+        FROM candles
+        WHERE market_id = $1 AND interval = $2 AND bucket_start >= $3 AND bucket_start <= $4
+        ORDER BY bucket_start
+        LIMIT $5
+        "#,
+        canonical_market_id,
+        interval,
+        bucket_start(from, seconds),
+        to.naive_utc(),
+        MAX_CANDLES
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(candles) => candles,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch candles: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch candles: {}", e));
+        }
+    };
+
+    let candles_json: Vec<Value> = candles
+        .iter()
+        .map(|c| {
+            let (open, high, low, close) = if inverted {
+                (100 - c.open, 100 - c.low, 100 - c.high, 100 - c.close)
+            } else {
+                (c.open, c.high, c.low, c.close)
+            };
+            serde_json::json!({
+                "time": c.bucket_start.and_utc().timestamp(),
+                "open": open,
+                "high": high,
+                "low": low,
+                "close": close,
+                "volume": c.volume,
+                "trade_count": c.trade_count
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Candles fetched successfully",
+        "market_id": market_id,
+        "interval": interval,
+        "candles": candles_json
+    });
+
+    let response = RedisResponse::new(200, true, "Candles fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_candles_by_market request: request_id={}, market_id={}, interval={}",
+        request_id, market_id, interval
+    );
+    Ok(())
+}
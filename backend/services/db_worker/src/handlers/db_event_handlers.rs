@@ -1,4 +1,6 @@
-use chrono::Utc;
+use super::candle_handlers::record_trade_in_candles;
+use super::notification_handlers::{notify_event_resolved, notify_order_filled, notify_payout};
+use chrono::{DateTime, Utc};
 use log::{info, warn};
 use redis_client::RedisManager;
 use serde_json::Value;
@@ -23,10 +25,41 @@ pub async fn handle_db_event(event: Value, pool: &PgPool) -> Result<(), String>
         "event_resolved" => handle_event_resolved(event, pool).await,
         "event_updated" => handle_event_updated(event, pool).await,
         "event_deleted" => handle_event_deleted(event, pool).await,
+        "withdrawal_updated" => handle_withdrawal_updated(event, pool).await,
+        "deposit_updated" => handle_deposit_updated(event, pool).await,
         _ => Err(format!("Unknown event type: {}", event_type)),
     }
 }
 
+pub async fn load_last_sequence(pool: &PgPool) -> Result<u64, String> {
+    let last_sequence = sqlx::query_scalar!(
+        r#"
+        SELECT last_sequence
+        FROM db_event_progress
+        "#
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load db event progress: {}", e))?;
+
+    Ok(last_sequence.unwrap_or(0).max(0) as u64)
+}
+
+pub async fn record_sequence(pool: &PgPool, sequence: u64) -> Result<(), String> {
+    sqlx::query!(
+        r#"
+        UPDATE db_event_progress
+        SET last_sequence = GREATEST(last_sequence, $1)
+        "#,
+        sequence as i64
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record db event progress: {}", e))?;
+
+    Ok(())
+}
+
 async fn handle_order_placed(event: Value, pool: &PgPool) -> Result<(), String> {
     let data = &event;
 
@@ -51,11 +84,13 @@ async fn handle_order_placed(event: Value, pool: &PgPool) -> Result<(), String>
     let remaining_qty = data["remaining_qty"]
         .as_u64()
         .ok_or_else(|| "Invalid remaining_qty".to_string())?;
+    let hidden = data["hidden"].as_bool().unwrap_or(false);
+    let client_order_id = data["client_order_id"].as_str();
 
     sqlx::query!(
         r#"
-        INSERT INTO orders (order_id, user_id, market_id, side, price, original_qty, remaining_qty, filled_qty, status)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, 0, 'open')
+        INSERT INTO orders (order_id, user_id, market_id, side, price, original_qty, remaining_qty, filled_qty, status, hidden, client_order_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, 0, 'open', $8, $9)
         ON CONFLICT (order_id) DO NOTHING
         "#,
         order_id as i64,
@@ -65,6 +100,8 @@ async fn handle_order_placed(event: Value, pool: &PgPool) -> Result<(), String>
         price as i64,
         original_qty as i64,
         remaining_qty as i64,
+        hidden,
+        client_order_id,
     )
     .execute(pool)
     .await
@@ -170,6 +207,11 @@ async fn handle_order_filled(event: Value, pool: &PgPool) -> Result<(), String>
     .await
     .map_err(|e| format!("Failed to update order fill: {}", e))?;
 
+    // Notifications are best effort; the fill itself is already stored
+    if let Err(e) = notify_order_filled(pool, order_id as i64).await {
+        warn!("{}", e);
+    }
+
     info!(
         "Order filled: order_id={}, filled_qty={}, status={}",
         order_id, filled_qty, status
@@ -207,8 +249,18 @@ async fn handle_trade_executed(event: Value, pool: &PgPool) -> Result<(), String
     let taker_side = data["taker_side"]
         .as_str()
         .ok_or_else(|| "Invalid taker_side".to_string())?;
+    let executed_at = data["timestamp"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
 
-    sqlx::query!(
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let inserted = sqlx::query!(
         r#"
         INSERT INTO trades (trade_id, market_id, taker_order_id, maker_order_id, taker_user_id, maker_user_id, price, quantity, taker_side)
         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
@@ -224,9 +276,10 @@ async fn handle_trade_executed(event: Value, pool: &PgPool) -> Result<(), String
         quantity as i64,
         taker_side,
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| format!("Failed to insert trade: {}", e))?;
+    .map_err(|e| format!("Failed to insert trade: {}", e))?
+    .rows_affected();
 
     sqlx::query!(
         r#"
@@ -237,10 +290,26 @@ async fn handle_trade_executed(event: Value, pool: &PgPool) -> Result<(), String
         price as i64,
         market_id as i64,
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
     .map_err(|e| format!("Failed to update market last_price: {}", e))?;
 
+    // A redelivered trade is already in its candles
+    if inserted > 0 {
+        record_trade_in_candles(
+            &mut tx,
+            market_id as i64,
+            price as i64,
+            quantity as i64,
+            executed_at,
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     info!(
         "Trade executed: trade_id={}, market_id={}, price={}",
         trade_id, market_id, price
@@ -260,8 +329,29 @@ async fn handle_position_updated(event: Value, pool: &PgPool) -> Result<(), Stri
     let quantity = data["quantity"]
         .as_u64()
         .ok_or_else(|| "Invalid quantity".to_string())?;
+    let locked_quantity = data["locked_quantity"].as_u64().unwrap_or(0);
+    let cost_basis = data["cost_basis"].as_i64().unwrap_or(0);
+    let realized_pnl = data["realized_pnl"].as_i64().unwrap_or(0);
+    let sequence = data["sequence"].as_u64().unwrap_or(0);
+    let recorded_at = data["timestamp"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
 
-    if quantity == 0 {
+    let previous_pnl: Option<i64> = sqlx::query_scalar!(
+        r#"
+        SELECT realized_pnl FROM positions WHERE user_id = $1 AND market_id = $2
+        "#,
+        user_id as i64,
+        market_id as i64,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch position: {}", e))?;
+
+    // Closed positions are kept while they carry realized PnL
+    if quantity == 0 && locked_quantity == 0 && realized_pnl == 0 {
         sqlx::query!(
             r#"
             DELETE FROM positions
@@ -274,26 +364,18 @@ async fn handle_position_updated(event: Value, pool: &PgPool) -> Result<(), Stri
         .await
         .map_err(|e| format!("Failed to delete position: {}", e))?;
     } else {
-        let exists: bool = sqlx::query_scalar!(
-            r#"
-            SELECT EXISTS(SELECT 1 FROM positions WHERE user_id = $1 AND market_id = $2)
-            "#,
-            user_id as i64,
-            market_id as i64,
-        )
-        .fetch_one(pool)
-        .await
-        .map_err(|e| format!("Failed to check position existence: {}", e))?
-        .unwrap_or(false);
-
-        if exists {
+        if previous_pnl.is_some() {
             sqlx::query!(
                 r#"
                 UPDATE positions
-                SET quantity = $1, updated_at = NOW()
-                WHERE user_id = $2 AND market_id = $3
+                SET quantity = $1, locked_quantity = $2, cost_basis = $3, realized_pnl = $4,
+                    updated_at = NOW()
+                WHERE user_id = $5 AND market_id = $6
                 "#,
                 quantity as i64,
+                locked_quantity as i64,
+                cost_basis,
+                realized_pnl,
                 user_id as i64,
                 market_id as i64,
             )
@@ -303,12 +385,17 @@ async fn handle_position_updated(event: Value, pool: &PgPool) -> Result<(), Stri
         } else {
             sqlx::query!(
                 r#"
-                INSERT INTO positions (user_id, market_id, quantity, updated_at)
-                VALUES ($1, $2, $3, NOW())
+                INSERT INTO positions
+                    (user_id, market_id, quantity, locked_quantity, cost_basis, realized_pnl,
+                     updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, NOW())
                 "#,
                 user_id as i64,
                 market_id as i64,
                 quantity as i64,
+                locked_quantity as i64,
+                cost_basis,
+                realized_pnl,
             )
             .execute(pool)
             .await
@@ -316,9 +403,30 @@ async fn handle_position_updated(event: Value, pool: &PgPool) -> Result<(), Stri
         }
     }
 
+    // Leaderboards sum realized PnL over time windows, so keep each change with the time
+    // of the event that made it. A redelivered event hits its sequence and is skipped.
+    let pnl_change = realized_pnl - previous_pnl.unwrap_or(0);
+    if pnl_change != 0 {
+        sqlx::query!(
+            r#"
+            INSERT INTO realized_pnl_changes (user_id, market_id, amount, sequence, recorded_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (sequence) WHERE sequence > 0 DO NOTHING
+            "#,
+            user_id as i64,
+            market_id as i64,
+            pnl_change,
+            sequence as i64,
+            recorded_at.naive_utc(),
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to record realized PnL change: {}", e))?;
+    }
+
     info!(
-        "Position updated: user_id={}, market_id={}, quantity={}",
-        user_id, market_id, quantity
+        "Position updated: user_id={}, market_id={}, quantity={}, locked={}",
+        user_id, market_id, quantity, locked_quantity
     );
     Ok(())
 }
@@ -332,21 +440,216 @@ async fn handle_balance_updated(event: Value, pool: &PgPool) -> Result<(), Strin
     let balance = data["balance"]
         .as_i64()
         .ok_or_else(|| "Invalid balance".to_string())?;
+    let locked_balance = data["locked_balance"].as_i64().unwrap_or(0);
+    let sequence = data["sequence"].as_u64().unwrap_or(0);
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
 
     sqlx::query!(
         r#"
         UPDATE users
-        SET balance = $1
-        WHERE id = $2
+        SET balance = $1, locked_balance = $2
+        WHERE id = $3
         "#,
         balance,
+        locked_balance,
         user_id as i64,
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
     .map_err(|e| format!("Failed to update balance: {}", e))?;
 
-    info!("Balance updated: user_id={}, balance={}", user_id, balance);
+    if let Some(ledger) = data.get("ledger").filter(|l| !l.is_null()) {
+        insert_ledger_transaction(&mut tx, user_id as i64, sequence as i64, ledger).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    if let Some(ledger) = data.get("ledger").filter(|l| l["kind"] == "settlement") {
+        let payout: i64 = ledger["postings"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|p| p["account"] == "available")
+            .filter_map(|p| p["amount"].as_i64())
+            .sum();
+        if payout > 0 {
+            let event_id = ledger["event_id"].as_i64();
+            if let Err(e) = notify_payout(pool, user_id as i64, event_id, payout).await {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    info!(
+        "Balance updated: user_id={}, balance={}, locked={}",
+        user_id, balance, locked_balance
+    );
+    Ok(())
+}
+
+async fn insert_ledger_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i64,
+    sequence: i64,
+    ledger: &Value,
+) -> Result<(), String> {
+    let kind = ledger["kind"]
+        .as_str()
+        .ok_or_else(|| "Invalid ledger kind".to_string())?;
+    let postings = ledger["postings"]
+        .as_array()
+        .ok_or_else(|| "Invalid ledger postings".to_string())?;
+
+    let total: i64 = postings.iter().filter_map(|p| p["amount"].as_i64()).sum();
+    if total != 0 {
+        return Err(format!(
+            "Ledger entry for user {} does not balance: {}",
+            user_id, total
+        ));
+    }
+
+    let transaction_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO ledger_transactions
+            (user_id, kind, order_id, trade_id, event_id, withdrawal_id, deposit_id, sequence)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (sequence, user_id, kind) WHERE sequence > 0 DO NOTHING
+        RETURNING id
+        "#,
+        user_id,
+        kind,
+        ledger["order_id"].as_i64(),
+        ledger["trade_id"].as_str(),
+        ledger["event_id"].as_i64(),
+        ledger["withdrawal_id"].as_i64(),
+        ledger["deposit_id"].as_str(),
+        sequence,
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to insert ledger transaction: {}", e))?;
+
+    // Already booked when this event was first handled
+    let Some(transaction_id) = transaction_id else {
+        return Ok(());
+    };
+
+    for posting in postings {
+        let account = posting["account"]
+            .as_str()
+            .ok_or_else(|| "Invalid ledger account".to_string())?;
+        let amount = posting["amount"]
+            .as_i64()
+            .ok_or_else(|| "Invalid ledger amount".to_string())?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO ledger_entries (transaction_id, user_id, account, amount)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            transaction_id,
+            user_id,
+            account,
+            amount,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to insert ledger entry: {}", e))?;
+    }
+
+    Ok(())
+}
+
+async fn handle_withdrawal_updated(event: Value, pool: &PgPool) -> Result<(), String> {
+    let data = &event;
+
+    let withdrawal_id = data["withdrawal_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid withdrawal_id".to_string())?;
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())?;
+    let amount = data["amount"]
+        .as_i64()
+        .ok_or_else(|| "Invalid amount".to_string())?;
+    let status = data["status"]
+        .as_str()
+        .ok_or_else(|| "Invalid status".to_string())?;
+    let admin_id = data["admin_id"].as_i64();
+    let reason = data["reason"].as_str();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO withdrawals (id, user_id, amount, status, admin_id, reason)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (id) DO UPDATE
+        SET status = EXCLUDED.status, admin_id = EXCLUDED.admin_id, reason = EXCLUDED.reason,
+            updated_at = NOW()
+        "#,
+        withdrawal_id as i64,
+        user_id as i64,
+        amount,
+        status,
+        admin_id,
+        reason,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to upsert withdrawal: {}", e))?;
+
+    info!(
+        "Withdrawal updated: withdrawal_id={}, user_id={}, status={}",
+        withdrawal_id, user_id, status
+    );
+    Ok(())
+}
+
+async fn handle_deposit_updated(event: Value, pool: &PgPool) -> Result<(), String> {
+    let data = &event;
+
+    let deposit_id = data["deposit_id"]
+        .as_str()
+        .ok_or_else(|| "Invalid deposit_id".to_string())?;
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())?;
+    let amount = data["amount"]
+        .as_i64()
+        .ok_or_else(|| "Invalid amount".to_string())?;
+    let provider = data["provider"]
+        .as_str()
+        .ok_or_else(|| "Invalid provider".to_string())?;
+    let status = data["status"]
+        .as_str()
+        .ok_or_else(|| "Invalid status".to_string())?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO deposits (id, user_id, amount, provider, status)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (id) DO UPDATE
+        SET status = EXCLUDED.status, updated_at = NOW()
+        "#,
+        deposit_id,
+        user_id as i64,
+        amount,
+        provider,
+        status,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to upsert deposit: {}", e))?;
+
+    info!(
+        "Deposit updated: deposit_id={}, user_id={}, status={}",
+        deposit_id, user_id, status
+    );
     Ok(())
 }
 
@@ -378,7 +681,12 @@ async fn handle_user_created(event: Value, pool: &PgPool) -> Result<(), String>
         .as_i64()
         .ok_or_else(|| "Invalid balance".to_string())?;
 
-    sqlx::query!(
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let inserted = sqlx::query!(
         r#"
         INSERT INTO users (id, email, name, password, balance)
         VALUES ($1, $2, $3, $4, $5)
@@ -390,9 +698,25 @@ async fn handle_user_created(event: Value, pool: &PgPool) -> Result<(), String>
         password,
         balance,
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| format!("Failed to insert user: {}", e))?;
+    .map_err(|e| format!("Failed to insert user: {}", e))?
+    .rows_affected();
+
+    if inserted > 0 && balance != 0 {
+        let opening = serde_json::json!({
+            "kind": "opening",
+            "postings": [
+                { "account": "external", "amount": -balance },
+                { "account": "available", "amount": balance }
+            ]
+        });
+        insert_ledger_transaction(&mut tx, user_id, 0, &opening).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
     info!("User created: user_id={}, email={}", user_id, email);
     Ok(())
@@ -594,6 +918,10 @@ async fn handle_event_resolved(event: Value, pool: &PgPool) -> Result<(), String
         .await
         .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
+    if let Err(e) = notify_event_resolved(pool, event_id as i64, winning_outcome_id as i64).await {
+        warn!("{}", e);
+    }
+
     if let Some(redis_manager) = RedisManager::global() {
         let cache_key = format!("event:{}", event_id);
         if let Err(e) = redis_manager.delete("events:all").await {
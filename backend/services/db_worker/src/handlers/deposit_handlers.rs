@@ -0,0 +1,82 @@
+use super::common::send_read_response;
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+
+const DEFAULT_DEPOSIT_LIMIT: i64 = 50;
+const MAX_DEPOSIT_LIMIT: i64 = 200;
+
+pub async fn handle_get_deposits_by_user(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+    let limit = data["limit"]
+        .as_i64()
+        .unwrap_or(DEFAULT_DEPOSIT_LIMIT)
+        .clamp(1, MAX_DEPOSIT_LIMIT);
+    let offset = data["offset"].as_i64().unwrap_or(0).max(0);
+
+    let deposits = match sqlx::query!(
+        r#"
+        SELECT id, user_id, amount, provider, status, created_at, updated_at
+        FROM deposits
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(deposits) => deposits,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch deposits: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch deposits: {}", e));
+        }
+    };
+
+    let deposits_json: Vec<Value> = deposits
+        .iter()
+        .map(|w| {
+            serde_json::json!({
+                "deposit_id": w.id,
+                "user_id": w.user_id,
+                "amount": w.amount,
+                "provider": w.provider,
+                "status": w.status,
+                "created_at": w.created_at,
+                "updated_at": w.updated_at
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Deposits fetched successfully",
+        "deposits": deposits_json,
+        "count": deposits.len()
+    });
+
+    let response = RedisResponse::new(200, true, "Deposits fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_deposits_by_user request: request_id={}, user_id={}",
+        request_id, user_id
+    );
+    Ok(())
+}
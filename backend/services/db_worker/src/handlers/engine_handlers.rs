@@ -0,0 +1,243 @@
+use super::common::send_read_response;
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+
+pub async fn handle_get_engine_bootstrap(
+    _data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let response_data = match fetch_engine_bootstrap(pool).await {
+        Ok(data) => data,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch engine bootstrap data: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch engine bootstrap data: {}", e));
+        }
+    };
+
+    let counts = format!(
+        "users={}, markets={}, orders={}",
+        response_data["users"].as_array().map_or(0, |v| v.len()),
+        response_data["markets"].as_array().map_or(0, |v| v.len()),
+        response_data["orders"].as_array().map_or(0, |v| v.len()),
+    );
+
+    let response = RedisResponse::new(
+        200,
+        true,
+        "Engine bootstrap data fetched successfully",
+        response_data,
+    );
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_engine_bootstrap request: request_id={}, {}",
+        request_id, counts
+    );
+    Ok(())
+}
+
+async fn fetch_engine_bootstrap(pool: &PgPool) -> Result<Value, sqlx::Error> {
+    let users = sqlx::query!(
+        r#"
+        SELECT id, name, email, balance, locked_balance
+        FROM users
+        ORDER BY id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let positions = sqlx::query!(
+        r#"
+        SELECT user_id, market_id, quantity, locked_quantity, cost_basis, realized_pnl
+        FROM positions
+        WHERE quantity > 0 OR locked_quantity > 0 OR realized_pnl <> 0
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let markets = sqlx::query!(
+        r#"
+        SELECT o.event_id, o.id AS outcome_id, y.id AS yes_market_id, n.id AS no_market_id
+        FROM outcomes o
+        JOIN events e ON e.id = o.event_id
+        JOIN markets y ON y.outcome_id = o.id AND y.side = 'YES'
+        JOIN markets n ON n.outcome_id = o.id AND n.side = 'NO'
+        WHERE e.winning_outcome_id IS NULL AND e.status <> 'RESOLVED'
+        ORDER BY o.event_id, o.id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Resting orders are returned in the order they reached the book so time priority survives
+    let orders = sqlx::query!(
+        r#"
+        SELECT order_id, user_id, market_id, side, price, original_qty, remaining_qty, hidden,
+               client_order_id
+        FROM orders
+        WHERE status IN ('open', 'partially_filled') AND remaining_qty > 0
+        ORDER BY created_at, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Finished orders keep their client order ids taken, as the unique index on orders does
+    let client_orders = sqlx::query!(
+        r#"
+        SELECT order_id, user_id, client_order_id AS "client_order_id!",
+               CASE WHEN status = 'cancelled' THEN 'cancelled' ELSE 'filled' END AS "status!"
+        FROM orders
+        WHERE client_order_id IS NOT NULL
+          AND NOT (status IN ('open', 'partially_filled') AND remaining_qty > 0)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let withdrawals = sqlx::query!(
+        r#"
+        SELECT id, user_id, amount
+        FROM withdrawals
+        WHERE status = 'pending'
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let deposits = sqlx::query!(
+        r#"
+        SELECT id, user_id, amount, provider, status
+        FROM deposits
+        WHERE status = 'pending'
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let last_sequence = sqlx::query_scalar!(
+        r#"
+        SELECT last_sequence
+        FROM db_event_progress
+        "#
+    )
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or(0);
+
+    let users_json: Vec<Value> = users
+        .iter()
+        .map(|u| {
+            serde_json::json!({
+                "id": u.id,
+                "name": u.name,
+                "email": u.email,
+                "balance": u.balance,
+                "locked_balance": u.locked_balance
+            })
+        })
+        .collect();
+
+    let positions_json: Vec<Value> = positions
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "user_id": p.user_id,
+                "market_id": p.market_id,
+                "quantity": p.quantity,
+                "locked_quantity": p.locked_quantity,
+                "cost_basis": p.cost_basis,
+                "realized_pnl": p.realized_pnl
+            })
+        })
+        .collect();
+
+    let markets_json: Vec<Value> = markets
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "event_id": m.event_id,
+                "outcome_id": m.outcome_id,
+                "yes_market_id": m.yes_market_id,
+                "no_market_id": m.no_market_id
+            })
+        })
+        .collect();
+
+    let orders_json: Vec<Value> = orders
+        .iter()
+        .map(|o| {
+            serde_json::json!({
+                "order_id": o.order_id,
+                "user_id": o.user_id,
+                "market_id": o.market_id,
+                "side": o.side,
+                "price": o.price,
+                "original_qty": o.original_qty,
+                "remaining_qty": o.remaining_qty,
+                "hidden": o.hidden,
+                "client_order_id": o.client_order_id
+            })
+        })
+        .collect();
+
+    let client_orders_json: Vec<Value> = client_orders
+        .iter()
+        .map(|o| {
+            serde_json::json!({
+                "order_id": o.order_id,
+                "user_id": o.user_id,
+                "client_order_id": o.client_order_id,
+                "status": o.status
+            })
+        })
+        .collect();
+
+    let withdrawals_json: Vec<Value> = withdrawals
+        .iter()
+        .map(|w| {
+            serde_json::json!({
+                "withdrawal_id": w.id,
+                "user_id": w.user_id,
+                "amount": w.amount
+            })
+        })
+        .collect();
+
+    let deposits_json: Vec<Value> = deposits
+        .iter()
+        .map(|d| {
+            serde_json::json!({
+                "deposit_id": d.id,
+                "user_id": d.user_id,
+                "amount": d.amount,
+                "provider": d.provider,
+                "status": d.status
+            })
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "status": "success",
+        "message": "Engine bootstrap data fetched successfully",
+        "users": users_json,
+        "positions": positions_json,
+        "markets": markets_json,
+        "orders": orders_json,
+        "client_orders": client_orders_json,
+        "withdrawals": withdrawals_json,
+        "deposits": deposits_json,
+        "last_sequence": last_sequence
+    }))
+}
@@ -0,0 +1,407 @@
+use super::common::send_read_response;
+use chrono::{Duration, NaiveDateTime, Utc};
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+
+const DEFAULT_LEADERBOARD_LIMIT: i64 = 50;
+const MAX_LEADERBOARD_LIMIT: i64 = 100;
+const DEFAULT_MIN_PREDICTIONS: i64 = 3;
+
+fn window_days(window: &str) -> Option<Option<i64>> {
+    match window {
+        "daily" => Some(Some(1)),
+        "weekly" => Some(Some(7)),
+        "monthly" => Some(Some(30)),
+        "all_time" => Some(None),
+        _ => None,
+    }
+}
+
+struct Standing {
+    user_id: i64,
+    name: String,
+    value: Value,
+    details: Option<(i64, i64)>,
+    total: i64,
+}
+
+// Public rankings over trades, realized PnL and resolved events. Users who set
+// `hide_from_leaderboards` are left out of every board.
+pub async fn handle_get_leaderboard(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let metric = data["metric"].as_str().unwrap_or("pnl").to_string();
+    let window = data["window"].as_str().unwrap_or("all_time").to_string();
+    let category = data["category"].as_str().map(str::to_string);
+    let limit = data["limit"]
+        .as_i64()
+        .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+        .clamp(1, MAX_LEADERBOARD_LIMIT);
+    let offset = data["offset"].as_i64().unwrap_or(0).max(0);
+    let min_predictions = data["min_predictions"]
+        .as_i64()
+        .unwrap_or(DEFAULT_MIN_PREDICTIONS)
+        .max(1);
+
+    let Some(days) = window_days(&window) else {
+        let response = RedisResponse::new(
+            400,
+            false,
+            format!(
+                "Invalid window {}, expected one of daily, weekly, monthly, all_time",
+                window
+            ),
+            serde_json::json!(null),
+        );
+        send_read_response(&request_id, response).await?;
+        return Ok(());
+    };
+    let since: Option<NaiveDateTime> = days.map(|d| (Utc::now() - Duration::days(d)).naive_utc());
+
+    let standings = match metric.as_str() {
+        "pnl" => fetch_pnl(pool, since, category.as_deref(), limit, offset).await,
+        "volume" => fetch_volume(pool, since, category.as_deref(), limit, offset).await,
+        "accuracy" => {
+            fetch_accuracy(
+                pool,
+                since,
+                category.as_deref(),
+                min_predictions,
+                limit,
+                offset,
+            )
+            .await
+        }
+        _ => {
+            let response = RedisResponse::new(
+                400,
+                false,
+                format!(
+                    "Invalid metric {}, expected one of pnl, volume, accuracy",
+                    metric
+                ),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, response).await?;
+            return Ok(());
+        }
+    };
+
+    let standings = match standings {
+        Ok(standings) => standings,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch leaderboard: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch leaderboard: {}", e));
+        }
+    };
+
+    let total = standings.first().map_or(0, |s| s.total);
+    let entries: Vec<Value> = standings
+        .into_iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let mut entry = serde_json::json!({
+                "rank": offset + i as i64 + 1,
+                "user_id": s.user_id,
+                "name": s.name,
+                "value": s.value
+            });
+            if let Some((predictions, correct)) = s.details {
+                entry["predictions"] = predictions.into();
+                entry["correct"] = correct.into();
+            }
+            entry
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Leaderboard fetched successfully",
+        "metric": metric,
+        "window": window,
+        "category": category,
+        "entries": entries,
+        "count": entries.len(),
+        "total": total,
+        "limit": limit,
+        "offset": offset
+    });
+
+    let response = RedisResponse::new(200, true, "Leaderboard fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_leaderboard request: request_id={}, metric={}, window={}",
+        request_id, metric, window
+    );
+    Ok(())
+}
+
+async fn fetch_pnl(
+    pool: &PgPool,
+    since: Option<NaiveDateTime>,
+    category: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Standing>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.user_id, u.name,
+               SUM(c.amount)::BIGINT AS "realized_pnl!",
+               COUNT(*) OVER () AS "total!"
+        FROM realized_pnl_changes c
+        JOIN users u ON u.id = c.user_id
+        JOIN markets m ON m.id = c.market_id
+        JOIN outcomes o ON o.id = m.outcome_id
+        JOIN events e ON e.id = o.event_id
+        WHERE NOT u.hide_from_leaderboards
+          AND ($1::TIMESTAMP IS NULL OR c.recorded_at >= $1)
+          AND ($2::TEXT IS NULL OR e.category = $2)
+        GROUP BY c.user_id, u.name
+        ORDER BY 3 DESC, c.user_id
+        LIMIT $3 OFFSET $4
+        "#,
+        since,
+        category,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| Standing {
+            user_id: r.user_id,
+            name: r.name,
+            value: r.realized_pnl.into(),
+            details: None,
+            total: r.total,
+        })
+        .collect())
+}
+
+// Both sides of a trade count its full notional at the price each paid. Trades are stored
+// in YES terms, so a fill on a NO order is credited (100 - price) per contract, and the
+// order's own market decides the category.
+async fn fetch_volume(
+    pool: &PgPool,
+    since: Option<NaiveDateTime>,
+    category: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Standing>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        WITH sides AS (
+            SELECT taker_user_id AS user_id, taker_order_id AS order_id, market_id, price,
+                   quantity
+            FROM trades
+            WHERE $1::TIMESTAMP IS NULL OR executed_at >= $1
+            UNION ALL
+            SELECT maker_user_id, maker_order_id, market_id, price, quantity
+            FROM trades
+            WHERE $1::TIMESTAMP IS NULL OR executed_at >= $1
+        ),
+        fills AS (
+            SELECT s.user_id, COALESCE(om.id, s.market_id) AS market_id,
+                   CASE WHEN om.side = 'NO' THEN 100 - s.price ELSE s.price END
+                       * s.quantity AS notional
+            FROM sides s
+            LEFT JOIN orders o ON o.order_id = s.order_id
+            LEFT JOIN markets om ON om.id = o.market_id
+        )
+        SELECT f.user_id AS "user_id!", u.name,
+               SUM(f.notional)::BIGINT AS "volume!",
+               COUNT(*) OVER () AS "total!"
+        FROM fills f
+        JOIN users u ON u.id = f.user_id
+        JOIN markets m ON m.id = f.market_id
+        JOIN outcomes o ON o.id = m.outcome_id
+        JOIN events e ON e.id = o.event_id
+        WHERE NOT u.hide_from_leaderboards
+          AND ($2::TEXT IS NULL OR e.category = $2)
+        GROUP BY f.user_id, u.name
+        ORDER BY 3 DESC, f.user_id
+        LIMIT $3 OFFSET $4
+        "#,
+        since,
+        category,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| Standing {
+            user_id: r.user_id,
+            name: r.name,
+            value: r.volume.into(),
+            details: None,
+            total: r.total,
+        })
+        .collect())
+}
+
+// A prediction is a user's net position in a market of a resolved event, taken from their
+// trades: net long on an outcome's YES is right if it won, net short (a NO holder, since NO
+// orders execute on the YES book) is right if it lost. The window applies to resolution time.
+async fn fetch_accuracy(
+    pool: &PgPool,
+    since: Option<NaiveDateTime>,
+    category: Option<&str>,
+    min_predictions: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Standing>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        WITH exposure AS (
+            SELECT user_id, market_id, SUM(quantity)::BIGINT AS net
+            FROM (
+                SELECT CASE WHEN taker_side = 'Bid' THEN taker_user_id ELSE maker_user_id END
+                           AS user_id,
+                       market_id, quantity
+                FROM trades
+                UNION ALL
+                SELECT CASE WHEN taker_side = 'Bid' THEN maker_user_id ELSE taker_user_id END,
+                       market_id, -quantity
+                FROM trades
+            ) legs
+            GROUP BY user_id, market_id
+        ),
+        resolved AS (
+            SELECT id, category, winning_outcome_id,
+                   CASE WHEN resolved_at ~ '^\d{4}-\d{2}-\d{2}'
+                        THEN resolved_at::TIMESTAMPTZ AT TIME ZONE 'UTC'
+                   END AS resolved_at
+            FROM events
+            WHERE winning_outcome_id IS NOT NULL
+        ),
+        predictions AS (
+            SELECT x.user_id,
+                   (x.net > 0) = ((o.id = r.winning_outcome_id) = (m.side = 'YES')) AS correct
+            FROM exposure x
+            JOIN markets m ON m.id = x.market_id
+            JOIN outcomes o ON o.id = m.outcome_id
+            JOIN resolved r ON r.id = o.event_id
+            WHERE x.net <> 0
+              AND ($1::TIMESTAMP IS NULL OR r.resolved_at >= $1)
+              AND ($2::TEXT IS NULL OR r.category = $2)
+        )
+        SELECT p.user_id AS "user_id!", u.name,
+               COUNT(*) AS "predictions!",
+               COUNT(*) FILTER (WHERE p.correct) AS "correct!",
+               COUNT(*) OVER () AS "total!"
+        FROM predictions p
+        JOIN users u ON u.id = p.user_id
+        WHERE NOT u.hide_from_leaderboards
+        GROUP BY p.user_id, u.name
+        HAVING COUNT(*) >= $3
+        ORDER BY COUNT(*) FILTER (WHERE p.correct)::FLOAT8 / COUNT(*) DESC,
+                 COUNT(*) DESC, p.user_id
+        LIMIT $4 OFFSET $5
+        "#,
+        since,
+        category,
+        min_predictions,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let accuracy = (r.correct as f64 * 10_000.0 / r.predictions as f64).round() / 100.0;
+            Standing {
+                user_id: r.user_id,
+                name: r.name,
+                value: accuracy.into(),
+                details: Some((r.predictions, r.correct)),
+                total: r.total,
+            }
+        })
+        .collect())
+}
+
+pub async fn handle_update_leaderboard_privacy(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let (Some(user_id), Some(hidden)) = (
+        data["user_id"].as_u64(),
+        data["hide_from_leaderboards"].as_bool(),
+    ) else {
+        let response = RedisResponse::new(
+            400,
+            false,
+            "Invalid user_id or hide_from_leaderboards",
+            serde_json::json!(null),
+        );
+        send_read_response(&request_id, response).await?;
+        return Ok(());
+    };
+    let user_id = user_id as i64;
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE users
+        SET hide_from_leaderboards = $1
+        WHERE id = $2
+        "#,
+        hidden,
+        user_id
+    )
+    .execute(pool)
+    .await;
+
+    let updated = match updated {
+        Ok(updated) => updated,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to update leaderboard privacy: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to update leaderboard privacy: {}", e));
+        }
+    };
+
+    let response = if updated.rows_affected() == 0 {
+        RedisResponse::new(404, false, "User not found", serde_json::json!(null))
+    } else {
+        RedisResponse::new(
+            200,
+            true,
+            "Leaderboard privacy updated",
+            serde_json::json!({
+                "user_id": user_id,
+                "hide_from_leaderboards": hidden
+            }),
+        )
+    };
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed update leaderboard privacy request: request_id={}, user_id={}, hidden={}",
+        request_id, user_id, hidden
+    );
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use super::common::send_read_response;
+use log::{info, warn};
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+const DEFAULT_LEDGER_LIMIT: i64 = 50;
+const MAX_LEDGER_LIMIT: i64 = 200;
+
+pub async fn handle_get_ledger_by_user(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+    let limit = data["limit"]
+        .as_i64()
+        .unwrap_or(DEFAULT_LEDGER_LIMIT)
+        .clamp(1, MAX_LEDGER_LIMIT);
+    let offset = data["offset"].as_i64().unwrap_or(0).max(0);
+
+    let transactions = match sqlx::query!(
+        r#"
+        SELECT id, kind, order_id, trade_id, event_id, withdrawal_id, deposit_id, sequence,
+               created_at
+        FROM ledger_transactions
+        WHERE user_id = $1
+        ORDER BY id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(transactions) => transactions,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch ledger: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch ledger: {}", e));
+        }
+    };
+
+    let transaction_ids: Vec<i64> = transactions.iter().map(|t| t.id).collect();
+    let entries = match sqlx::query!(
+        r#"
+        SELECT transaction_id, account, amount
+        FROM ledger_entries
+        WHERE transaction_id = ANY($1)
+        ORDER BY id
+        "#,
+        &transaction_ids
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(entries) => entries,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch ledger entries: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch ledger entries: {}", e));
+        }
+    };
+
+    let mut entries_by_transaction: HashMap<i64, Vec<Value>> = HashMap::new();
+    for entry in &entries {
+        entries_by_transaction
+            .entry(entry.transaction_id)
+            .or_default()
+            .push(serde_json::json!({
+                "account": entry.account,
+                "amount": entry.amount
+            }));
+    }
+
+    let transactions_json: Vec<Value> = transactions
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "id": t.id,
+                "kind": t.kind,
+                "order_id": t.order_id,
+                "trade_id": t.trade_id,
+                "event_id": t.event_id,
+                "withdrawal_id": t.withdrawal_id,
+                "deposit_id": t.deposit_id,
+                "sequence": t.sequence,
+                "entries": entries_by_transaction.remove(&t.id).unwrap_or_default(),
+                "created_at": t.created_at
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Ledger fetched successfully",
+        "transactions": transactions_json,
+        "count": transactions.len(),
+        "limit": limit,
+        "offset": offset
+    });
+
+    let response = RedisResponse::new(200, true, "Ledger fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_ledger_by_user request: request_id={}, user_id={}",
+        request_id, user_id
+    );
+    Ok(())
+}
+
+// Reports users whose ledger does not add up to their stored balances, and transactions
+// whose entries do not sum to zero.
+pub async fn handle_check_ledger_consistency(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"].as_u64().map(|id| id as i64);
+
+    let mismatched_users = match sqlx::query!(
+        r#"
+        SELECT u.id AS user_id, u.balance, u.locked_balance,
+               COALESCE(SUM(e.amount) FILTER (WHERE e.account = 'available'), 0)::BIGINT
+                   AS "ledger_available!",
+               COALESCE(SUM(e.amount) FILTER (WHERE e.account = 'locked'), 0)::BIGINT
+                   AS "ledger_locked!"
+        FROM users u
+        LEFT JOIN ledger_entries e ON e.user_id = u.id
+        WHERE $1::BIGINT IS NULL OR u.id = $1
+        GROUP BY u.id
+        HAVING u.balance <> COALESCE(SUM(e.amount) FILTER (WHERE e.account = 'available'), 0)
+            OR u.locked_balance <> COALESCE(SUM(e.amount) FILTER (WHERE e.account = 'locked'), 0)
+        ORDER BY u.id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to check ledger: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to check ledger: {}", e));
+        }
+    };
+
+    let unbalanced_transactions = match sqlx::query!(
+        r#"
+        SELECT e.transaction_id, SUM(e.amount)::BIGINT AS "total!"
+        FROM ledger_entries e
+        WHERE $1::BIGINT IS NULL OR e.user_id = $1
+        GROUP BY e.transaction_id
+        HAVING SUM(e.amount) <> 0
+        ORDER BY e.transaction_id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to check ledger: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to check ledger: {}", e));
+        }
+    };
+
+    let consistent = mismatched_users.is_empty() && unbalanced_transactions.is_empty();
+    if !consistent {
+        warn!(
+            "Ledger check found {} mismatched users and {} unbalanced transactions",
+            mismatched_users.len(),
+            unbalanced_transactions.len()
+        );
+    }
+
+    let users_json: Vec<Value> = mismatched_users
+        .iter()
+        .map(|u| {
+            serde_json::json!({
+                "user_id": u.user_id,
+                "balance": u.balance,
+                "ledger_available": u.ledger_available,
+                "locked_balance": u.locked_balance,
+                "ledger_locked": u.ledger_locked
+            })
+        })
+        .collect();
+
+    let transactions_json: Vec<Value> = unbalanced_transactions
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "transaction_id": t.transaction_id,
+                "total": t.total
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "consistent": consistent,
+        "mismatched_users": users_json,
+        "unbalanced_transactions": transactions_json
+    });
+
+    let response = RedisResponse::new(200, true, "Ledger checked successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed check_ledger_consistency request: request_id={}, consistent={}",
+        request_id, consistent
+    );
+    Ok(())
+}
@@ -1,28 +1,47 @@
 pub mod admin_handlers;
 pub mod bookmark_handlers;
+pub mod candle_handlers;
 pub mod common;
 pub mod db_event_handlers;
+pub mod deposit_handlers;
+pub mod engine_handlers;
 pub mod event_handlers;
+pub mod leaderboard_handlers;
+pub mod ledger_handlers;
+pub mod notification_handlers;
 pub mod order_handlers;
 pub mod outcome_handlers;
 pub mod position_handlers;
+pub mod ticker_handlers;
 pub mod trade_handlers;
 pub mod user_handlers;
+pub mod withdrawal_handlers;
 
 pub use admin_handlers::{handle_get_admin_by_email, handle_get_admin_by_id};
 pub use bookmark_handlers::{
     handle_add_market_bookmark, handle_get_for_you_markets, handle_get_market_bookmarks,
     handle_remove_market_bookmark,
 };
+pub use candle_handlers::handle_get_candles_by_market;
 pub use common::send_read_response;
-pub use db_event_handlers::handle_db_event;
+pub use db_event_handlers::{handle_db_event, load_last_sequence, record_sequence};
+pub use deposit_handlers::handle_get_deposits_by_user;
+pub use engine_handlers::handle_get_engine_bootstrap;
 pub use event_handlers::{handle_get_all_events, handle_get_event_by_id, handle_search_events};
+pub use leaderboard_handlers::{handle_get_leaderboard, handle_update_leaderboard_privacy};
+pub use ledger_handlers::{handle_check_ledger_consistency, handle_get_ledger_by_user};
+pub use notification_handlers::{
+    handle_get_notification_preferences, handle_get_notifications,
+    handle_mark_notifications_read, handle_update_notification_preferences,
+};
 pub use order_handlers::{
     handle_get_order_by_id, handle_get_orders_by_market, handle_get_orders_by_user,
 };
 pub use outcome_handlers::handle_get_outcome_by_id;
 pub use position_handlers::{handle_get_position_by_user_and_market, handle_get_positions_by_user};
+pub use ticker_handlers::handle_get_market_tickers;
 pub use trade_handlers::{
     handle_get_trade_by_id, handle_get_trades_by_market, handle_get_trades_by_user,
 };
 pub use user_handlers::{handle_get_all_users, handle_get_user_by_email, handle_get_user_by_id};
+pub use withdrawal_handlers::{handle_get_withdrawals, handle_get_withdrawals_by_user};
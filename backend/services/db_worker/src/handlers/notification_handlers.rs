@@ -0,0 +1,419 @@
+use super::common::send_read_response;
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+
+pub const NOTIFICATION_KINDS: [&str; 3] = ["order_filled", "event_resolved", "payout"];
+
+const DEFAULT_NOTIFICATION_LIMIT: i64 = 50;
+const MAX_NOTIFICATION_LIMIT: i64 = 200;
+
+struct Notification {
+    user_id: i64,
+    kind: &'static str,
+    message: String,
+    market_id: Option<i64>,
+    event_id: Option<i64>,
+    order_id: Option<i64>,
+    amount: Option<i64>,
+    dedupe_key: String,
+}
+
+// Skipped when the user turned the kind off.
+async fn record_notification(pool: &PgPool, notification: Notification) -> Result<(), String> {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications
+            (user_id, kind, message, market_id, event_id, order_id, amount, dedupe_key)
+        SELECT $1, $2, $3, $4, $5, $6, $7, $8
+        WHERE NOT EXISTS (
+            SELECT 1 FROM notification_preferences
+            WHERE user_id = $1 AND kind = $2 AND NOT enabled
+        )
+        ON CONFLICT (user_id, dedupe_key) DO NOTHING
+        "#,
+        notification.user_id,
+        notification.kind,
+        notification.message,
+        notification.market_id,
+        notification.event_id,
+        notification.order_id,
+        notification.amount,
+        notification.dedupe_key,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record {} notification: {}", notification.kind, e))?;
+    Ok(())
+}
+
+pub async fn notify_order_filled(pool: &PgPool, order_id: i64) -> Result<(), String> {
+    let Some(order) = sqlx::query!(
+        r#"
+        SELECT user_id, market_id, side, original_qty, filled_qty, status
+        FROM orders
+        WHERE order_id = $1
+        "#,
+        order_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch filled order: {}", e))?
+    else {
+        return Ok(());
+    };
+
+    let side = if order.side == "Bid" { "buy" } else { "sell" };
+    let message = if order.status == "filled" {
+        format!(
+            "Your {} order {} for {} shares was filled",
+            side, order_id, order.original_qty
+        )
+    } else {
+        format!(
+            "Your {} order {} was partially filled: {} of {} shares",
+            side, order_id, order.filled_qty, order.original_qty
+        )
+    };
+
+    record_notification(
+        pool,
+        Notification {
+            user_id: order.user_id,
+            kind: "order_filled",
+            message,
+            market_id: Some(order.market_id),
+            event_id: None,
+            order_id: Some(order_id),
+            amount: None,
+            dedupe_key: format!("order_filled:{}:{}", order_id, order.filled_qty),
+        },
+    )
+    .await
+}
+
+// Everyone still holding shares in the event's markets. The engine settles positions after
+// the resolution event is published, so they are still open here.
+pub async fn notify_event_resolved(
+    pool: &PgPool,
+    event_id: i64,
+    winning_outcome_id: i64,
+) -> Result<(), String> {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_id, kind, message, event_id, dedupe_key)
+        SELECT DISTINCT p.user_id, 'event_resolved',
+               FORMAT('"%s" resolved: %s won', e.title, COALESCE(w.name, 'no outcome')),
+               e.id, 'event_resolved:' || e.id
+        FROM positions p
+        JOIN markets m ON m.id = p.market_id
+        JOIN outcomes o ON o.id = m.outcome_id
+        JOIN events e ON e.id = o.event_id
+        LEFT JOIN outcomes w ON w.id = $2
+        WHERE e.id = $1
+          AND p.quantity + p.locked_quantity > 0
+          AND NOT EXISTS (
+              SELECT 1 FROM notification_preferences np
+              WHERE np.user_id = p.user_id AND np.kind = 'event_resolved' AND NOT np.enabled
+          )
+        ON CONFLICT (user_id, dedupe_key) DO NOTHING
+        "#,
+        event_id,
+        winning_outcome_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record event_resolved notifications: {}", e))?;
+    Ok(())
+}
+
+pub async fn notify_payout(
+    pool: &PgPool,
+    user_id: i64,
+    event_id: Option<i64>,
+    amount: i64,
+) -> Result<(), String> {
+    let title = match event_id {
+        Some(event_id) => sqlx::query_scalar!("SELECT title FROM events WHERE id = $1", event_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to fetch event: {}", e))?,
+        None => None,
+    };
+    let message = match title {
+        Some(title) => format!("You were paid {} from \"{}\"", amount, title),
+        None => format!("You were paid {} from a resolved event", amount),
+    };
+
+    record_notification(
+        pool,
+        Notification {
+            user_id,
+            kind: "payout",
+            message,
+            market_id: None,
+            event_id,
+            order_id: None,
+            amount: Some(amount),
+            dedupe_key: format!("payout:{}", event_id.unwrap_or(0)),
+        },
+    )
+    .await
+}
+
+pub async fn handle_get_notifications(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+    let unread_only = data["unread_only"].as_bool().unwrap_or(false);
+    let limit = data["limit"]
+        .as_i64()
+        .unwrap_or(DEFAULT_NOTIFICATION_LIMIT)
+        .clamp(1, MAX_NOTIFICATION_LIMIT);
+    let offset = data["offset"].as_i64().unwrap_or(0).max(0);
+
+    let notifications = match sqlx::query!(
+        r#"
+        SELECT id, kind, message, market_id, event_id, order_id, amount, read_at, created_at
+        FROM notifications
+        WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+        ORDER BY id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+        user_id,
+        unread_only,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(notifications) => notifications,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch notifications: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch notifications: {}", e));
+        }
+    };
+
+    let unread_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM notifications
+        WHERE user_id = $1 AND read_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count unread notifications: {}", e))?;
+
+    let notifications_json: Vec<Value> = notifications
+        .iter()
+        .map(|n| {
+            serde_json::json!({
+                "id": n.id,
+                "kind": n.kind,
+                "message": n.message,
+                "market_id": n.market_id,
+                "event_id": n.event_id,
+                "order_id": n.order_id,
+                "amount": n.amount,
+                "read": n.read_at.is_some(),
+                "read_at": n.read_at,
+                "created_at": n.created_at
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Notifications fetched successfully",
+        "notifications": notifications_json,
+        "count": notifications.len(),
+        "unread_count": unread_count
+    });
+
+    let response = RedisResponse::new(
+        200,
+        true,
+        "Notifications fetched successfully",
+        response_data,
+    );
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_notifications request: request_id={}, user_id={}",
+        request_id, user_id
+    );
+    Ok(())
+}
+
+// Marks the given ids read, or every unread notification when `ids` is absent.
+pub async fn handle_mark_notifications_read(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+    let ids: Option<Vec<i64>> = data["ids"]
+        .as_array()
+        .map(|ids| ids.iter().filter_map(|id| id.as_i64()).collect());
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET read_at = NOW()
+        WHERE user_id = $1 AND read_at IS NULL AND ($2::BIGINT[] IS NULL OR id = ANY($2))
+        "#,
+        user_id,
+        ids.as_deref()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to mark notifications read: {}", e))?
+    .rows_affected();
+
+    let response = RedisResponse::new(
+        200,
+        true,
+        "Notifications marked read",
+        serde_json::json!({ "updated": updated }),
+    );
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed mark notifications read request: request_id={}, user_id={}, updated={}",
+        request_id, user_id, updated
+    );
+    Ok(())
+}
+
+async fn load_preferences(pool: &PgPool, user_id: i64) -> Result<Map<String, Value>, String> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT kind, enabled
+        FROM notification_preferences
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch notification preferences: {}", e))?;
+
+    Ok(NOTIFICATION_KINDS
+        .iter()
+        .map(|kind| {
+            let enabled = rows
+                .iter()
+                .find(|row| row.kind == *kind)
+                .is_none_or(|row| row.enabled);
+            (kind.to_string(), Value::Bool(enabled))
+        })
+        .collect())
+}
+
+pub async fn handle_get_notification_preferences(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+
+    let preferences = load_preferences(pool, user_id).await?;
+    let response = RedisResponse::new(
+        200,
+        true,
+        "Notification preferences fetched successfully",
+        serde_json::json!({ "preferences": preferences }),
+    );
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get notification preferences request: request_id={}, user_id={}",
+        request_id, user_id
+    );
+    Ok(())
+}
+
+// `preferences` maps kinds to enabled flags; kinds left out keep their current setting.
+pub async fn handle_update_notification_preferences(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+    let changes = data["preferences"].as_object().cloned().unwrap_or_default();
+
+    let mut updates = Vec::with_capacity(changes.len());
+    for (kind, enabled) in &changes {
+        match (
+            NOTIFICATION_KINDS.contains(&kind.as_str()),
+            enabled.as_bool(),
+        ) {
+            (true, Some(enabled)) => updates.push((kind.as_str(), enabled)),
+            _ => {
+                let response = RedisResponse::new(
+                    400,
+                    false,
+                    format!(
+                        "Invalid preference {}, expected a boolean for one of {}",
+                        kind,
+                        NOTIFICATION_KINDS.join(", ")
+                    ),
+                    serde_json::json!(null),
+                );
+                send_read_response(&request_id, response).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    for (kind, enabled) in updates {
+        sqlx::query!(
+            r#"
+            INSERT INTO notification_preferences (user_id, kind, enabled)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, kind) DO UPDATE SET enabled = EXCLUDED.enabled
+            "#,
+            user_id,
+            kind,
+            enabled
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update notification preferences: {}", e))?;
+    }
+
+    let preferences = load_preferences(pool, user_id).await?;
+    let response = RedisResponse::new(
+        200,
+        true,
+        "Notification preferences updated",
+        serde_json::json!({ "preferences": preferences }),
+    );
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed update notification preferences request: request_id={}, user_id={}",
+        request_id, user_id
+    );
+    Ok(())
+}
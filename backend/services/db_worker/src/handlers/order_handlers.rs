@@ -20,7 +20,7 @@ pub async fn handle_get_order_by_id(
     let order = match sqlx::query!(
         r#"
         SELECT order_id, user_id, market_id, side, price, original_qty, remaining_qty, 
-               filled_qty, status, created_at, updated_at, cancelled_at, filled_at
+               filled_qty, status, hidden, client_order_id, created_at, updated_at, cancelled_at, filled_at
         FROM orders
         WHERE order_id = $1
         "#,
@@ -69,6 +69,8 @@ pub async fn handle_get_order_by_id(
             "remaining_qty": order.remaining_qty,
             "filled_qty": order.filled_qty,
             "status": order.status,
+            "hidden": order.hidden,
+            "client_order_id": order.client_order_id,
             "created_at": order.created_at,
             "updated_at": order.updated_at,
             "cancelled_at": order.cancelled_at,
@@ -98,7 +100,7 @@ pub async fn handle_get_orders_by_user(
     let orders = match sqlx::query!(
         r#"
         SELECT order_id, user_id, market_id, side, price, original_qty, remaining_qty, 
-               filled_qty, status, created_at, updated_at, cancelled_at, filled_at
+               filled_qty, status, hidden, client_order_id, created_at, updated_at, cancelled_at, filled_at
         FROM orders
         WHERE user_id = $1
         ORDER BY created_at DESC
@@ -134,6 +136,8 @@ pub async fn handle_get_orders_by_user(
                 "remaining_qty": o.remaining_qty,
                 "filled_qty": o.filled_qty,
                 "status": o.status,
+                "hidden": o.hidden,
+                "client_order_id": o.client_order_id,
                 "created_at": o.created_at,
                 "updated_at": o.updated_at,
                 "cancelled_at": o.cancelled_at,
@@ -171,9 +175,9 @@ pub async fn handle_get_orders_by_market(
     let orders = match sqlx::query!(
         r#"
         SELECT order_id, user_id, market_id, side, price, original_qty, remaining_qty, 
-               filled_qty, status, created_at, updated_at, cancelled_at, filled_at
+               filled_qty, status, hidden, client_order_id, created_at, updated_at, cancelled_at, filled_at
         FROM orders
-        WHERE market_id = $1
+        WHERE market_id = $1 AND hidden = FALSE
         ORDER BY created_at DESC
         "#,
         market_id
@@ -207,6 +211,8 @@ pub async fn handle_get_orders_by_market(
                 "remaining_qty": o.remaining_qty,
                 "filled_qty": o.filled_qty,
                 "status": o.status,
+                "hidden": o.hidden,
+                "client_order_id": o.client_order_id,
                 "created_at": o.created_at,
                 "updated_at": o.updated_at,
                 "cancelled_at": o.cancelled_at,
@@ -3,6 +3,41 @@ use log::info;
 use redis_client::RedisResponse;
 use serde_json::Value;
 use sqlx::PgPool;
+use std::collections::BTreeMap;
+
+struct PositionPnl {
+    avg_entry_price: f64,
+    unrealized_pnl: Option<i64>,
+}
+
+impl PositionPnl {
+    // Open shares are marked the way the engine marks them: the YES book's mid, else its one
+    // quoted side, else its last trade, taken as 100 - price for NO. Without any of those
+    // there is no unrealized figure.
+    fn new(held: i64, cost_basis: i64, mark_price: Option<i64>) -> Self {
+        let avg_entry_price = if held > 0 {
+            cost_basis as f64 / held as f64
+        } else {
+            0.0
+        };
+        let unrealized_pnl = match mark_price {
+            Some(price) if held > 0 => Some(held * price - cost_basis),
+            _ if held == 0 => Some(0),
+            _ => None,
+        };
+        Self {
+            avg_entry_price,
+            unrealized_pnl,
+        }
+    }
+}
+
+#[derive(Default)]
+struct EventPnl {
+    cost_basis: i64,
+    realized_pnl: i64,
+    unrealized_pnl: i64,
+}
 
 pub async fn handle_get_position_by_user_and_market(
     data: Value,
@@ -18,9 +53,31 @@ pub async fn handle_get_position_by_user_and_market(
 
     let position = match sqlx::query!(
         r#"
-        SELECT user_id, market_id, quantity, created_at, updated_at
-        FROM positions
-        WHERE user_id = $1 AND market_id = $2
+        SELECT p.user_id, p.market_id, p.quantity, p.locked_quantity, p.cost_basis,
+               p.realized_pnl, o.event_id, p.created_at, p.updated_at,
+               CASE WHEN m.side = 'NO' THEN 100 - mark.price ELSE mark.price END AS mark_price
+        FROM positions p
+        JOIN markets m ON m.id = p.market_id
+        JOIN outcomes o ON o.id = m.outcome_id
+        JOIN markets y ON y.outcome_id = m.outcome_id AND y.side = 'YES'
+        JOIN markets n ON n.outcome_id = m.outcome_id AND n.side = 'NO'
+        CROSS JOIN LATERAL (
+            SELECT MAX(CASE WHEN r.market_id = y.id THEN r.price ELSE 100 - r.price END)
+                       FILTER (WHERE (r.market_id = y.id) = (r.side = 'Bid')) AS best_bid,
+                   MIN(CASE WHEN r.market_id = y.id THEN r.price ELSE 100 - r.price END)
+                       FILTER (WHERE (r.market_id = y.id) <> (r.side = 'Bid')) AS best_ask
+            FROM orders r
+            WHERE r.market_id IN (y.id, n.id) AND NOT r.hidden AND r.remaining_qty > 0
+              AND r.status IN ('open', 'partially_filled')
+        ) book
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(
+                       (book.best_bid + book.best_ask) / 2, book.best_bid, book.best_ask,
+                       (SELECT t.price FROM trades t WHERE t.market_id = y.id
+                        ORDER BY t.executed_at DESC, t.id DESC LIMIT 1)
+                   ) AS price
+        ) mark
+        WHERE p.user_id = $1 AND p.market_id = $2
         "#,
         user_id,
         market_id
@@ -47,13 +104,24 @@ pub async fn handle_get_position_by_user_and_market(
         }
     };
 
+    let held = position.quantity + position.locked_quantity;
+    let pnl = PositionPnl::new(held, position.cost_basis, position.mark_price);
     let response_data = serde_json::json!({
         "status": "success",
         "message": "Position fetched successfully",
         "position": {
             "user_id": position.user_id,
             "market_id": position.market_id,
-            "quantity": position.quantity,
+            "event_id": position.event_id,
+            "quantity": held,
+            "available": position.quantity,
+            "locked": position.locked_quantity,
+            "total": held,
+            "avg_entry_price": pnl.avg_entry_price,
+            "cost_basis": position.cost_basis,
+            "mark_price": position.mark_price,
+            "realized_pnl": position.realized_pnl,
+            "unrealized_pnl": pnl.unrealized_pnl,
             "created_at": position.created_at,
             "updated_at": position.updated_at
         }
@@ -80,10 +148,32 @@ pub async fn handle_get_positions_by_user(
 
     let positions = match sqlx::query!(
         r#"
-        SELECT user_id, market_id, quantity, created_at, updated_at
-        FROM positions
-        WHERE user_id = $1
-        ORDER BY updated_at DESC
+        SELECT p.user_id, p.market_id, p.quantity, p.locked_quantity, p.cost_basis,
+               p.realized_pnl, o.event_id, p.created_at, p.updated_at,
+               CASE WHEN m.side = 'NO' THEN 100 - mark.price ELSE mark.price END AS mark_price
+        FROM positions p
+        JOIN markets m ON m.id = p.market_id
+        JOIN outcomes o ON o.id = m.outcome_id
+        JOIN markets y ON y.outcome_id = m.outcome_id AND y.side = 'YES'
+        JOIN markets n ON n.outcome_id = m.outcome_id AND n.side = 'NO'
+        CROSS JOIN LATERAL (
+            SELECT MAX(CASE WHEN r.market_id = y.id THEN r.price ELSE 100 - r.price END)
+                       FILTER (WHERE (r.market_id = y.id) = (r.side = 'Bid')) AS best_bid,
+                   MIN(CASE WHEN r.market_id = y.id THEN r.price ELSE 100 - r.price END)
+                       FILTER (WHERE (r.market_id = y.id) <> (r.side = 'Bid')) AS best_ask
+            FROM orders r
+            WHERE r.market_id IN (y.id, n.id) AND NOT r.hidden AND r.remaining_qty > 0
+              AND r.status IN ('open', 'partially_filled')
+        ) book
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(
+                       (book.best_bid + book.best_ask) / 2, book.best_bid, book.best_ask,
+                       (SELECT t.price FROM trades t WHERE t.market_id = y.id
+                        ORDER BY t.executed_at DESC, t.id DESC LIMIT 1)
+                   ) AS price
+        ) mark
+        WHERE p.user_id = $1
+        ORDER BY p.updated_at DESC
         "#,
         user_id
     )
@@ -103,23 +193,55 @@ pub async fn handle_get_positions_by_user(
         }
     };
 
+    let mut events: BTreeMap<i64, EventPnl> = BTreeMap::new();
     let positions_json: Vec<serde_json::Value> = positions
         .iter()
         .map(|p| {
+            let held = p.quantity + p.locked_quantity;
+            let pnl = PositionPnl::new(held, p.cost_basis, p.mark_price);
+
+            let event = events.entry(p.event_id).or_default();
+            event.cost_basis += p.cost_basis;
+            event.realized_pnl += p.realized_pnl;
+            event.unrealized_pnl += pnl.unrealized_pnl.unwrap_or(0);
+
             serde_json::json!({
                 "user_id": p.user_id,
                 "market_id": p.market_id,
-                "quantity": p.quantity,
+                "event_id": p.event_id,
+                "quantity": held,
+                "available": p.quantity,
+                "locked": p.locked_quantity,
+                "total": held,
+                "avg_entry_price": pnl.avg_entry_price,
+                "cost_basis": p.cost_basis,
+                "mark_price": p.mark_price,
+                "realized_pnl": p.realized_pnl,
+                "unrealized_pnl": pnl.unrealized_pnl,
                 "created_at": p.created_at,
                 "updated_at": p.updated_at
             })
         })
         .collect();
 
+    let events_json: Vec<serde_json::Value> = events
+        .into_iter()
+        .map(|(event_id, pnl)| {
+            serde_json::json!({
+                "event_id": event_id,
+                "cost_basis": pnl.cost_basis,
+                "realized_pnl": pnl.realized_pnl,
+                "unrealized_pnl": pnl.unrealized_pnl,
+                "pnl": pnl.realized_pnl + pnl.unrealized_pnl
+            })
+        })
+        .collect();
+
     let response_data = serde_json::json!({
         "status": "success",
         "message": "Positions fetched successfully",
         "positions": positions_json,
+        "events": events_json,
         "count": positions.len()
     });
 
@@ -0,0 +1,134 @@
+use super::candle_handlers::bucket_start;
+use super::common::send_read_response;
+use chrono::{Duration, Utc};
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+
+// Tickers read 5m candles, so the 24h window rolls forward in five-minute steps.
+const TICKER_INTERVAL: &str = "5m";
+const TICKER_INTERVAL_SECONDS: i64 = 300;
+
+// Rolling 24h stats for every market, or one event's markets. Candles only exist for the
+// YES market of each outcome, so a NO market reports its sibling's stats mirrored around 100.
+pub async fn handle_get_market_tickers(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let event_id = data["event_id"].as_u64().map(|id| id as i64);
+    let window_start = bucket_start(Utc::now() - Duration::hours(24), TICKER_INTERVAL_SECONDS);
+
+    let rows = match sqlx::query!(
+        r#"
+        WITH window_stats AS (
+            SELECT market_id,
+                   MAX(high) AS high,
+                   MIN(low) AS low,
+                   (ARRAY_AGG(open ORDER BY bucket_start))[1] AS open,
+                   (ARRAY_AGG(close ORDER BY bucket_start DESC))[1] AS close,
+                   SUM(volume)::BIGINT AS volume,
+                   SUM(trade_count)::BIGINT AS trade_count
+            FROM candles
+            WHERE interval = $1 AND bucket_start >= $2
+            GROUP BY market_id
+        )
+        SELECT m.id AS market_id, m.side, m.outcome_id, o.event_id,
+               y.last_price AS "yes_last_price?",
+               s.high AS "high?", s.low AS "low?", s.open AS "open?", s.close AS "close?",
+               s.volume AS "volume?", s.trade_count AS "trade_count?",
+               (
+                   SELECT c.close
+                   FROM candles c
+                   WHERE c.market_id = y.id AND c.interval = $1 AND c.bucket_start < $2
+                   ORDER BY c.bucket_start DESC
+                   LIMIT 1
+               ) AS "previous_close?"
+        FROM markets m
+        JOIN outcomes o ON o.id = m.outcome_id
+        LEFT JOIN markets y ON y.outcome_id = m.outcome_id AND y.side = 'YES'
+        LEFT JOIN window_stats s ON s.market_id = y.id
+        WHERE $3::BIGINT IS NULL OR o.event_id = $3
+        ORDER BY m.id
+        "#,
+        TICKER_INTERVAL,
+        window_start,
+        event_id
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch tickers: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch tickers: {}", e));
+        }
+    };
+
+    let tickers_json: Vec<Value> = rows
+        .iter()
+        .map(|r| {
+            let mirror = |price: Option<i64>| {
+                if r.side == "NO" {
+                    price.map(|p| 100 - p)
+                } else {
+                    price
+                }
+            };
+            let last_price = mirror(r.close.or(r.yes_last_price));
+            // Change is measured from the last trade before the window, or the first one in it
+            let reference_price = mirror(r.previous_close.or(r.open));
+            let (high, low) = if r.side == "NO" {
+                (mirror(r.low), mirror(r.high))
+            } else {
+                (r.high, r.low)
+            };
+            let price_change = last_price
+                .zip(reference_price)
+                .map(|(last, from)| last - from);
+            let price_change_percent = price_change
+                .zip(reference_price)
+                .filter(|(_, from)| *from != 0)
+                .map(|(change, from)| (change as f64 * 10_000.0 / from as f64).round() / 100.0);
+
+            serde_json::json!({
+                "market_id": r.market_id,
+                "outcome_id": r.outcome_id,
+                "event_id": r.event_id,
+                "side": r.side,
+                "last_price": last_price,
+                "open_price": reference_price,
+                "price_change": price_change,
+                "price_change_percent": price_change_percent,
+                "high": high,
+                "low": low,
+                "volume": r.volume.unwrap_or(0),
+                "trade_count": r.trade_count.unwrap_or(0)
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Tickers fetched successfully",
+        "window_start": window_start.and_utc().timestamp(),
+        "tickers": tickers_json,
+        "count": rows.len()
+    });
+
+    let response = RedisResponse::new(200, true, "Tickers fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_market_tickers request: request_id={}, event_id={:?}",
+        request_id, event_id
+    );
+    Ok(())
+}
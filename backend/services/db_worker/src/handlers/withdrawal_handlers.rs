@@ -0,0 +1,156 @@
+use super::common::send_read_response;
+use log::info;
+use redis_client::RedisResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+
+const DEFAULT_WITHDRAWAL_LIMIT: i64 = 50;
+const MAX_WITHDRAWAL_LIMIT: i64 = 200;
+
+pub async fn handle_get_withdrawals_by_user(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let user_id = data["user_id"]
+        .as_u64()
+        .ok_or_else(|| "Invalid user_id".to_string())? as i64;
+    let limit = data["limit"]
+        .as_i64()
+        .unwrap_or(DEFAULT_WITHDRAWAL_LIMIT)
+        .clamp(1, MAX_WITHDRAWAL_LIMIT);
+    let offset = data["offset"].as_i64().unwrap_or(0).max(0);
+
+    let withdrawals = match sqlx::query!(
+        r#"
+        SELECT id, user_id, amount, status, admin_id, reason, created_at, updated_at
+        FROM withdrawals
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(withdrawals) => withdrawals,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch withdrawals: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch withdrawals: {}", e));
+        }
+    };
+
+    let withdrawals_json: Vec<Value> = withdrawals
+        .iter()
+        .map(|w| {
+            serde_json::json!({
+                "withdrawal_id": w.id,
+                "user_id": w.user_id,
+                "amount": w.amount,
+                "status": w.status,
+                "admin_id": w.admin_id,
+                "reason": w.reason,
+                "created_at": w.created_at,
+                "updated_at": w.updated_at
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Withdrawals fetched successfully",
+        "withdrawals": withdrawals_json,
+        "count": withdrawals.len()
+    });
+
+    let response = RedisResponse::new(200, true, "Withdrawals fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_withdrawals_by_user request: request_id={}, user_id={}",
+        request_id, user_id
+    );
+    Ok(())
+}
+
+pub async fn handle_get_withdrawals(
+    data: Value,
+    pool: &PgPool,
+    request_id: String,
+) -> Result<(), String> {
+    let status = data["status"].as_str();
+    let limit = data["limit"]
+        .as_i64()
+        .unwrap_or(DEFAULT_WITHDRAWAL_LIMIT)
+        .clamp(1, MAX_WITHDRAWAL_LIMIT);
+    let offset = data["offset"].as_i64().unwrap_or(0).max(0);
+
+    let withdrawals = match sqlx::query!(
+        r#"
+        SELECT id, user_id, amount, status, admin_id, reason, created_at, updated_at
+        FROM withdrawals
+        WHERE $1::TEXT IS NULL OR status = $1
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3
+        "#,
+        status,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(withdrawals) => withdrawals,
+        Err(e) => {
+            let error_response = RedisResponse::new(
+                500,
+                false,
+                format!("Failed to fetch withdrawals: {}", e),
+                serde_json::json!(null),
+            );
+            send_read_response(&request_id, error_response).await?;
+            return Err(format!("Failed to fetch withdrawals: {}", e));
+        }
+    };
+
+    let withdrawals_json: Vec<Value> = withdrawals
+        .iter()
+        .map(|w| {
+            serde_json::json!({
+                "withdrawal_id": w.id,
+                "user_id": w.user_id,
+                "amount": w.amount,
+                "status": w.status,
+                "admin_id": w.admin_id,
+                "reason": w.reason,
+                "created_at": w.created_at,
+                "updated_at": w.updated_at
+            })
+        })
+        .collect();
+
+    let response_data = serde_json::json!({
+        "status": "success",
+        "message": "Withdrawals fetched successfully",
+        "withdrawals": withdrawals_json,
+        "count": withdrawals.len()
+    });
+
+    let response = RedisResponse::new(200, true, "Withdrawals fetched successfully", response_data);
+
+    send_read_response(&request_id, response).await?;
+    info!(
+        "Processed get_withdrawals request: request_id={}, status={:?}",
+        request_id, status
+    );
+    Ok(())
+}
@@ -210,6 +210,46 @@ async fn process_messages(
             "get_positions_by_user" => {
                 handlers::handle_get_positions_by_user(data, pool, request_id.clone()).await
             }
+            "get_ledger_by_user" => {
+                handlers::handle_get_ledger_by_user(data, pool, request_id.clone()).await
+            }
+            "check_ledger_consistency" => {
+                handlers::handle_check_ledger_consistency(data, pool, request_id.clone()).await
+            }
+            "get_candles_by_market" => {
+                handlers::handle_get_candles_by_market(data, pool, request_id.clone()).await
+            }
+            "get_market_tickers" => {
+                handlers::handle_get_market_tickers(data, pool, request_id.clone()).await
+            }
+            "get_leaderboard" => {
+                handlers::handle_get_leaderboard(data, pool, request_id.clone()).await
+            }
+            "update_leaderboard_privacy" => {
+                handlers::handle_update_leaderboard_privacy(data, pool, request_id.clone()).await
+            }
+            "get_notifications" => {
+                handlers::handle_get_notifications(data, pool, request_id.clone()).await
+            }
+            "mark_notifications_read" => {
+                handlers::handle_mark_notifications_read(data, pool, request_id.clone()).await
+            }
+            "get_notification_preferences" => {
+                handlers::handle_get_notification_preferences(data, pool, request_id.clone()).await
+            }
+            "update_notification_preferences" => {
+                handlers::handle_update_notification_preferences(data, pool, request_id.clone())
+                    .await
+            }
+            "get_withdrawals_by_user" => {
+                handlers::handle_get_withdrawals_by_user(data, pool, request_id.clone()).await
+            }
+            "get_deposits_by_user" => {
+                handlers::handle_get_deposits_by_user(data, pool, request_id.clone()).await
+            }
+            "get_withdrawals" => {
+                handlers::handle_get_withdrawals(data, pool, request_id.clone()).await
+            }
             "add_market_bookmark" => {
                 handlers::handle_add_market_bookmark(data, pool, request_id.clone()).await
             }
@@ -222,6 +262,9 @@ async fn process_messages(
             "get_for_you_markets" => {
                 handlers::handle_get_for_you_markets(data, pool, request_id.clone()).await
             }
+            "get_engine_bootstrap" => {
+                handlers::handle_get_engine_bootstrap(data, pool, request_id.clone()).await
+            }
             _ => {
                 error!("Unknown read action: {}", action);
                 let error_response = redis_client::RedisResponse::new(
@@ -25,12 +25,21 @@ pub async fn start_db_event_consumer(pool: PgPool) {
         DB_EVENTS_STREAM
     );
     let mut last_id = "0".to_string();
+    let mut last_sequence = loop {
+        match handlers::load_last_sequence(&pool).await {
+            Ok(sequence) => break sequence,
+            Err(e) => {
+                error!("{}", e);
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            }
+        }
+    };
 
     loop {
         match read_stream_messages(&client, DB_EVENTS_STREAM, &mut last_id).await {
             Ok(messages) => {
                 if !messages.is_empty() {
-                    if let Err(e) = process_messages(messages, &pool).await {
+                    if let Err(e) = process_messages(messages, &pool, &mut last_sequence).await {
                         error!("Error processing messages: {}", e);
                     }
                 }
@@ -116,9 +125,12 @@ async fn read_stream_messages(
     Ok(result)
 }
 
+// Engine events carry a sequence and are skipped once applied; events from the server have
+// none and are always handled.
 async fn process_messages(
     messages: Vec<(String, HashMap<String, String>)>,
     pool: &PgPool,
+    last_sequence: &mut u64,
 ) -> Result<(), String> {
     for (msg_id, fields) in messages {
         let data_str = fields
@@ -128,6 +140,15 @@ async fn process_messages(
         let event: Value = serde_json::from_str(data_str)
             .map_err(|e| format!("Failed to parse event JSON: {}", e))?;
 
+        let sequence = event["sequence"].as_u64().unwrap_or(0);
+        if sequence != 0 && sequence <= *last_sequence {
+            info!(
+                "Skipping already applied event {} (sequence {})",
+                msg_id, sequence
+            );
+            continue;
+        }
+
         let event_clone = event.clone();
         match handlers::handle_db_event(event, pool).await {
             Ok(_) => {
@@ -140,6 +161,11 @@ async fn process_messages(
                 }
             }
         }
+
+        if sequence != 0 {
+            handlers::record_sequence(pool, sequence).await?;
+            *last_sequence = sequence;
+        }
     }
 
     Ok(())
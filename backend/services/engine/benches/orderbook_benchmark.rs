@@ -1,13 +1,14 @@
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use rand::Rng;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Import engine modules
+use engine::store::context::EngineContext;
 use engine::store::market::MarketStore;
 use engine::store::orderbook::spawn_orderbook_actor;
 use engine::types::market_types::MarketMeta;
-use engine::types::orderbook_types::{Order, OrderSide, OrderType};
+use engine::types::orderbook_types::{Order, OrderSide, OrderType, SnapshotOptions};
 use engine::types::user_types::User;
 
 // ==================== TEST DATA GENERATION UTILITIES ====================
@@ -28,6 +29,11 @@ fn generate_users(count: u64) -> Vec<User> {
             email: format!("user{}@test.com", i),
             balance: 100_000_000, // 100M units for high-volume testing
             positions: HashMap::new(),
+            locked_balance: 0,
+            locked_positions: HashMap::new(),
+            costs: HashMap::new(),
+            pending_withdrawals: HashMap::new(),
+            deposits: HashMap::new(),
         });
     }
     users
@@ -36,7 +42,7 @@ fn generate_users(count: u64) -> Vec<User> {
 /// Setup benchmark context with users and market
 async fn setup_benchmark_context(user_count: u64) -> BenchmarkContext {
     let market_store = MarketStore::new();
-    let orderbook = spawn_orderbook_actor(market_store.clone());
+    let orderbook = spawn_orderbook_actor(market_store.clone(), None, EngineContext::system());
 
     // Create and add users
     let users = generate_users(user_count);
@@ -101,6 +107,8 @@ fn generate_matching_orders(
             remaining_qty: rng.gen_range(1..100),
             side: side.clone(),
             order_type: OrderType::Limit,
+            hidden: false,
+            client_order_id: None,
         });
     }
     orders
@@ -132,6 +140,8 @@ fn generate_varied_price_orders(
             remaining_qty: rng.gen_range(10..200),
             side: side.clone(),
             order_type: OrderType::Limit,
+            hidden: false,
+            client_order_id: None,
         });
     }
     orders
@@ -304,6 +314,8 @@ fn bench_concurrent_users_max_capacity(c: &mut Criterion) {
                                     remaining_qty: rng.gen_range(10..100),
                                     side,
                                     order_type: OrderType::Limit,
+                                    hidden: false,
+                                    client_order_id: None,
                                 };
 
                                 orderbook.place_order(order).await.ok();
@@ -350,6 +362,8 @@ fn bench_single_order_latency(c: &mut Criterion) {
                 remaining_qty: 100,
                 side: OrderSide::Ask,
                 order_type: OrderType::Limit,
+                hidden: false,
+                client_order_id: None,
             };
             ctx.orderbook.place_order(maker).await.ok();
 
@@ -363,6 +377,8 @@ fn bench_single_order_latency(c: &mut Criterion) {
                 remaining_qty: 50,
                 side: OrderSide::Bid,
                 order_type: OrderType::Limit,
+                hidden: false,
+                client_order_id: None,
             };
 
             black_box(ctx.orderbook.place_order(taker).await.ok())
@@ -384,6 +400,8 @@ fn bench_single_order_latency(c: &mut Criterion) {
                 remaining_qty: 100,
                 side: OrderSide::Bid,
                 order_type: OrderType::Limit,
+                hidden: false,
+                client_order_id: None,
             };
 
             black_box(ctx.orderbook.place_order(order).await.ok())
@@ -414,6 +432,8 @@ fn bench_order_cancellation(c: &mut Criterion) {
                 remaining_qty: 100,
                 side: OrderSide::Bid,
                 order_type: OrderType::Limit,
+                hidden: false,
+                client_order_id: None,
             };
 
             let placed = ctx.orderbook.place_order(order).await.unwrap();
@@ -432,6 +452,106 @@ fn bench_order_cancellation(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark: Cancelling and sweeping a single deep price level
+/// Every resting order sits at the same price, so per-level queue cost dominates.
+/// Only the cancel/sweep phase is timed; populating the level is excluded.
+fn bench_deep_price_level(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deep_price_level");
+    group.measurement_time(Duration::from_secs(15));
+    group.sample_size(10);
+
+    for depth in [1_000, 10_000, 50_000].iter() {
+        group.throughput(Throughput::Elements(*depth as u64));
+
+        group.bench_with_input(BenchmarkId::new("cancel_all", depth), depth, |b, &depth| {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+
+            b.to_async(&runtime).iter_custom(|iters| async move {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let ctx = setup_benchmark_context(100).await;
+
+                    let orders = generate_matching_orders(
+                        ctx.user_count,
+                        ctx.market_id,
+                        depth,
+                        OrderSide::Ask,
+                        55,
+                    );
+                    let mut order_ids = Vec::with_capacity(depth);
+                    for order in orders {
+                        if let Ok(placed) = ctx.orderbook.place_order(order).await {
+                            order_ids.extend(placed.order_id);
+                        }
+                    }
+
+                    // Cancel from the back of the level, the worst case for a Vec-backed queue
+                    let start = Instant::now();
+                    for order_id in order_ids.into_iter().rev() {
+                        black_box(
+                            ctx.orderbook
+                                .cancel_order(ctx.market_id, order_id)
+                                .await
+                                .ok(),
+                        );
+                    }
+                    total += start.elapsed();
+                }
+                total
+            });
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("sweep_level", depth),
+            depth,
+            |b, &depth| {
+                let runtime = tokio::runtime::Runtime::new().unwrap();
+
+                b.to_async(&runtime).iter_custom(|iters| async move {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        let ctx = setup_benchmark_context(100).await;
+
+                        let orders = generate_matching_orders(
+                            ctx.user_count,
+                            ctx.market_id,
+                            depth,
+                            OrderSide::Ask,
+                            55,
+                        );
+                        let mut resting_qty = 0;
+                        for order in orders {
+                            if let Ok(placed) = ctx.orderbook.place_order(order).await {
+                                resting_qty += placed.remaining_qty;
+                            }
+                        }
+
+                        let sweep = Order {
+                            order_id: None,
+                            market_id: ctx.market_id,
+                            user_id: 1,
+                            price: 55,
+                            original_qty: resting_qty,
+                            remaining_qty: resting_qty,
+                            side: OrderSide::Bid,
+                            order_type: OrderType::Limit,
+                            hidden: false,
+                            client_order_id: None,
+                        };
+
+                        let start = Instant::now();
+                        black_box(ctx.orderbook.place_order(sweep).await.ok());
+                        total += start.elapsed();
+                    }
+                    total
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 /// Benchmark: Orderbook query operations
 fn bench_orderbook_queries(c: &mut Criterion) {
     let mut group = c.benchmark_group("orderbook_queries");
@@ -471,7 +591,14 @@ fn bench_orderbook_queries(c: &mut Criterion) {
 
     group.bench_function("get_full_orderbook", |b| {
         b.to_async(&runtime)
-            .iter(|| async { black_box(ctx.orderbook.get_orderbook(ctx.market_id).await.ok()) });
+            .iter(|| async {
+                black_box(
+                    ctx.orderbook
+                        .get_orderbook(ctx.market_id, SnapshotOptions::default())
+                        .await
+                        .ok(),
+                )
+            });
     });
 
     group.bench_function("get_user_orders", |b| {
@@ -496,6 +623,7 @@ criterion_group!(
         bench_concurrent_users_max_capacity,
         bench_single_order_latency,
         bench_order_cancellation,
+        bench_deep_price_level,
         bench_orderbook_queries
 );
 
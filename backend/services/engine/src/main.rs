@@ -4,9 +4,15 @@ mod types;
 
 use dotenvy::dotenv;
 use redis_client::RedisManager;
+use services::bootstrap::bootstrap_from_db;
+use services::db_event_publisher::start_db_event_publisher;
+use services::market_data_publisher::start_market_data_publisher;
+use services::replay::run_replay;
 use services::request_consumer::start_request_consumer;
 use std::env;
+use store::context::EngineContext;
 use store::market;
+use store::order_feed::init_public_order_ids;
 use store::orderbook;
 
 #[tokio::main]
@@ -14,6 +20,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
     env_logger::init();
 
+    init_public_order_ids()?;
+
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("replay") {
+        run_replay(&args[2..]).await?;
+        return Ok(());
+    }
+
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
 
     let _redis_manager =
@@ -26,8 +40,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Connected to Redis");
 
+    start_db_event_publisher()?;
+    start_market_data_publisher();
+
+    let persistence =
+        orderbook::Persistence::from_env().expect("Failed to initialize engine persistence");
+
+    let has_saved_state = persistence.has_saved_state();
+
     let market_store = market::MarketStore::new();
-    let orderbook = orderbook::spawn_orderbook_actor(market_store);
+    let orderbook =
+        orderbook::spawn_orderbook_actor(market_store, Some(persistence), EngineContext::system());
+
+    if !has_saved_state {
+        println!("No local engine state found, loading from database");
+        bootstrap_from_db(&orderbook).await?;
+    }
 
     start_request_consumer(orderbook).await;
 
@@ -0,0 +1,322 @@
+use crate::services::request_consumer::{latest_stream_id, read_stream_messages};
+use crate::store::orderbook::{ClosedClientOrder, ClosedStatus, Orderbook};
+use crate::types::bootstrap_types::BootstrapData;
+use crate::types::deposit_types::Deposit;
+use crate::types::orderbook_types::{Order, OrderSide, OrderType};
+use crate::types::user_types::{PositionCost, User};
+use fred::prelude::*;
+use log::{info, warn};
+use redis_client::{RedisManager, RedisRequest, RedisResponse};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+const DB_EVENTS_STREAM: &str = "db_events";
+const DB_READ_REQUESTS_STREAM: &str = "db_read_requests";
+const DB_READ_RESPONSES_STREAM: &str = "db_read_responses";
+const BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const SEQUENCE_SCAN_PAGE: u64 = 100;
+
+// Rebuilds users, markets and resting orders from Postgres. Must run before the request
+// consumer starts so no new order can jump ahead of the restored ones.
+pub async fn bootstrap_from_db(orderbook: &Orderbook) -> Result<(), String> {
+    let data = fetch_bootstrap_data().await?;
+    // db_worker's progress lags behind events still waiting in db_events. Handing those
+    // sequences out again would make db_worker skip the new events as already applied.
+    let published = last_published_sequence().await?;
+    orderbook
+        .restore_sequence(data.last_sequence.max(published))
+        .await;
+
+    let mut positions: HashMap<u64, HashMap<u64, u64>> = HashMap::new();
+    let mut locked_positions: HashMap<u64, HashMap<u64, u64>> = HashMap::new();
+    let mut costs: HashMap<u64, HashMap<u64, PositionCost>> = HashMap::new();
+    for position in &data.positions {
+        if position.quantity > 0 {
+            positions
+                .entry(position.user_id)
+                .or_default()
+                .insert(position.market_id, position.quantity);
+        }
+        if position.locked_quantity > 0 {
+            locked_positions
+                .entry(position.user_id)
+                .or_default()
+                .insert(position.market_id, position.locked_quantity);
+        }
+        if position.cost_basis != 0 || position.realized_pnl != 0 {
+            costs.entry(position.user_id).or_default().insert(
+                position.market_id,
+                PositionCost {
+                    cost_basis: position.cost_basis,
+                    realized_pnl: position.realized_pnl,
+                },
+            );
+        }
+    }
+
+    let mut pending_withdrawals: HashMap<u64, HashMap<u64, i64>> = HashMap::new();
+    for withdrawal in &data.withdrawals {
+        pending_withdrawals
+            .entry(withdrawal.user_id)
+            .or_default()
+            .insert(withdrawal.withdrawal_id, withdrawal.amount);
+    }
+
+    let mut deposits: HashMap<u64, HashMap<String, Deposit>> = HashMap::new();
+    for deposit in &data.deposits {
+        deposits
+            .entry(deposit.user_id)
+            .or_default()
+            .insert(deposit.deposit_id.clone(), deposit.clone());
+    }
+
+    for user in &data.users {
+        orderbook
+            .add_user(User {
+                id: user.id,
+                name: user.name.clone(),
+                email: user.email.clone(),
+                balance: user.balance,
+                positions: positions.remove(&user.id).unwrap_or_default(),
+                locked_balance: user.locked_balance,
+                locked_positions: locked_positions.remove(&user.id).unwrap_or_default(),
+                costs: costs.remove(&user.id).unwrap_or_default(),
+                pending_withdrawals: pending_withdrawals.remove(&user.id).unwrap_or_default(),
+                deposits: deposits.remove(&user.id).unwrap_or_default(),
+            })
+            .await;
+    }
+
+    if !data.markets.is_empty() {
+        orderbook
+            .init_markets(data.markets.clone())
+            .await
+            .map_err(|e| format!("Failed to restore markets: {}", e))?;
+    }
+
+    let mut restored = 0;
+    for stored in &data.orders {
+        let side = match stored.side.as_str() {
+            "Bid" => OrderSide::Bid,
+            "Ask" => OrderSide::Ask,
+            other => {
+                warn!(
+                    "Skipping order {} with unknown side {}",
+                    stored.order_id, other
+                );
+                continue;
+            }
+        };
+
+        let order = Order {
+            order_id: Some(stored.order_id as u64),
+            market_id: stored.market_id,
+            user_id: stored.user_id,
+            price: stored.price,
+            original_qty: stored.original_qty,
+            remaining_qty: stored.remaining_qty,
+            side,
+            order_type: OrderType::Limit,
+            hidden: stored.hidden,
+            client_order_id: stored.client_order_id.clone(),
+        };
+
+        match orderbook.restore_order(order).await {
+            Ok(_) => restored += 1,
+            Err(e) => warn!("Skipping order {}: {}", stored.order_id, e),
+        }
+    }
+
+    for stored in &data.client_orders {
+        let status = match stored.status.as_str() {
+            "filled" => ClosedStatus::Filled,
+            "cancelled" => ClosedStatus::Cancelled,
+            other => {
+                warn!(
+                    "Skipping client order {} with unknown status {}",
+                    stored.order_id, other
+                );
+                continue;
+            }
+        };
+        orderbook
+            .restore_client_order(
+                stored.user_id,
+                stored.client_order_id.clone(),
+                ClosedClientOrder {
+                    order_id: stored.order_id as u64,
+                    status,
+                },
+            )
+            .await;
+    }
+
+    info!(
+        "Bootstrapped engine from database: {} users, {} markets, {} of {} open orders",
+        data.users.len(),
+        data.markets.len(),
+        restored,
+        data.orders.len()
+    );
+    Ok(())
+}
+
+// Walks db_events back from the tail until it finds an engine event, since events from the
+// server carry no sequence.
+async fn last_published_sequence() -> Result<u64, String> {
+    let redis_manager =
+        RedisManager::global().ok_or_else(|| "Redis manager not initialized".to_string())?;
+    let client = redis_manager.client();
+
+    let mut end = "+".to_string();
+    loop {
+        let entries = client
+            .xrevrange_values::<String, String, String, _, _, _>(
+                DB_EVENTS_STREAM,
+                end.as_str(),
+                "-",
+                Some(SEQUENCE_SCAN_PAGE),
+            )
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", DB_EVENTS_STREAM, e))?;
+
+        if let Some(sequence) = highest_sequence(&entries) {
+            return Ok(sequence);
+        }
+        match entries.last() {
+            Some((id, _)) if entries.len() as u64 == SEQUENCE_SCAN_PAGE => {
+                end = format!("({}", id);
+            }
+            _ => return Ok(0),
+        }
+    }
+}
+
+fn highest_sequence(entries: &[(String, HashMap<String, String>)]) -> Option<u64> {
+    entries
+        .iter()
+        .filter_map(|(_, fields)| {
+            let event: Value = serde_json::from_str(fields.get("data")?).ok()?;
+            event["sequence"].as_u64()
+        })
+        .filter(|sequence| *sequence > 0)
+        .max()
+}
+
+async fn fetch_bootstrap_data() -> Result<BootstrapData, String> {
+    let redis_manager =
+        RedisManager::global().ok_or_else(|| "Redis manager not initialized".to_string())?;
+    let client = redis_manager.client();
+
+    // Only responses written after the request can be ours, so start reading from the current tail
+    let mut last_id = latest_stream_id(&client, DB_READ_RESPONSES_STREAM)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", DB_READ_RESPONSES_STREAM, e))?
+        .unwrap_or_else(|| "0".to_string());
+
+    let request_id = Uuid::new_v4().to_string();
+    let request = RedisRequest::new(
+        "db_worker",
+        "get_engine_bootstrap",
+        "Load engine state",
+        serde_json::json!({}),
+    );
+    let request_json = serde_json::to_string(&request)
+        .map_err(|e| format!("Failed to serialize request: {}", e))?;
+
+    redis_manager
+        .stream_add(
+            DB_READ_REQUESTS_STREAM,
+            &[("request_id", &request_id), ("data", &request_json)],
+        )
+        .await
+        .map_err(|e| format!("Failed to send bootstrap request: {}", e))?;
+
+    let deadline = Instant::now() + BOOTSTRAP_TIMEOUT;
+    while Instant::now() < deadline {
+        let streams = read_stream_messages(&client, DB_READ_RESPONSES_STREAM, &mut last_id)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", DB_READ_RESPONSES_STREAM, e))?;
+
+        for (_stream_name, messages) in streams {
+            for (_msg_id, fields) in messages {
+                let is_ours = fields
+                    .iter()
+                    .any(|(key, value)| key == "request_id" && *value == request_id);
+                if !is_ours {
+                    continue;
+                }
+
+                let data_str = fields
+                    .iter()
+                    .find(|(key, _)| key == "data")
+                    .map(|(_, value)| value.as_str())
+                    .ok_or_else(|| "Missing data in bootstrap response".to_string())?;
+
+                let response: RedisResponse<Value> = serde_json::from_str(data_str)
+                    .map_err(|e| format!("Failed to parse bootstrap response: {}", e))?;
+                if !response.success {
+                    return Err(response.message);
+                }
+
+                return serde_json::from_value(response.data)
+                    .map_err(|e| format!("Failed to parse bootstrap data: {}", e));
+            }
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    Err("Timed out waiting for bootstrap data from db_worker".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, data: Value) -> (String, HashMap<String, String>) {
+        let fields = HashMap::from([("data".to_string(), data.to_string())]);
+        (id.to_string(), fields)
+    }
+
+    #[test]
+    fn resumes_after_events_db_worker_has_not_applied_yet() {
+        // Newest first, as XREVRANGE returns them; the newest entry came from the server
+        let entries = vec![
+            entry(
+                "5-0",
+                serde_json::json!({"event_type": "user_created", "user_id": 7}),
+            ),
+            entry(
+                "4-0",
+                serde_json::json!({"event_type": "order_placed", "sequence": 42}),
+            ),
+            entry(
+                "3-0",
+                serde_json::json!({"event_type": "order_filled", "sequence": 41}),
+            ),
+        ];
+
+        // db_worker has only got to 40, so the engine has to continue after 42
+        assert_eq!(highest_sequence(&entries), Some(42));
+    }
+
+    #[test]
+    fn server_events_alone_give_no_sequence() {
+        let entries = vec![
+            entry(
+                "2-0",
+                serde_json::json!({"event_type": "user_created", "sequence": 0}),
+            ),
+            entry("1-0", serde_json::json!({"event_type": "event_created"})),
+            ("0-1".to_string(), HashMap::new()),
+        ];
+
+        assert_eq!(highest_sequence(&entries), None);
+        assert_eq!(highest_sequence(&[]), None);
+    }
+}
@@ -2,34 +2,116 @@ use crate::types::db_event_types::DbEvent;
 use log::{error, warn};
 use redis_client::RedisManager;
 use serde_json;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 const DB_EVENTS_STREAM: &str = "db_events";
+const OUTBOX_CAPACITY: usize = 65_536;
+const PUBLISH_BATCH_SIZE: usize = 512;
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+static OUTBOX: OnceLock<mpsc::Sender<DbEvent>> = OnceLock::new();
+static PUBLISHING_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// The outbox and the publishing switch are process-wide, so tests that drive the engine take
+// turns instead of leaking events into each other.
+#[cfg(test)]
+pub(crate) static ENGINE_TEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// Events are queued in-process and written to Redis by a single publisher task,
+// so callers only wait when the outbox is full. Redis has to be set up first: the publisher
+// never drops events, so it cannot start without somewhere to send them.
+pub fn start_db_event_publisher() -> Result<JoinHandle<()>, String> {
+    let redis_manager = RedisManager::global().ok_or_else(|| {
+        "Redis manager must be initialized before the DB event publisher".to_string()
+    })?;
+
+    let (tx, rx) = mpsc::channel(OUTBOX_CAPACITY);
+    if OUTBOX.set(tx).is_err() {
+        warn!("DB event publisher already started");
+    }
+
+    Ok(tokio::spawn(run_publisher(rx, redis_manager)))
+}
+
+// Hands DB events to the caller instead of Redis, for tools that run the engine offline.
+pub fn capture_db_events() -> mpsc::Receiver<DbEvent> {
+    let (tx, rx) = mpsc::channel(OUTBOX_CAPACITY);
+    if OUTBOX.set(tx).is_err() {
+        warn!("DB event outbox already initialized");
+    }
+
+    rx
+}
+
+// Used while replaying the engine journal, whose events were already published before the restart.
+pub fn set_db_event_publishing(enabled: bool) {
+    PUBLISHING_ENABLED.store(enabled, Ordering::SeqCst);
+}
 
 pub async fn publish_db_event(event: DbEvent) -> Result<(), String> {
-    let redis_manager = match RedisManager::global() {
-        Some(rm) => rm,
-        None => {
-            warn!("Redis manager not initialized, cannot publish DB event");
-            return Err("Redis manager not initialized".into());
-        }
-    };
+    if !PUBLISHING_ENABLED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
 
-    let event_json = match serde_json::to_string(&event) {
-        Ok(json) => json,
-        Err(e) => {
-            error!("Failed to serialize DB event: {}", e);
-            return Err(format!("Failed to serialize event: {}", e));
-        }
+    let Some(outbox) = OUTBOX.get() else {
+        warn!("DB event outbox not initialized, cannot publish DB event");
+        return Err("DB event outbox not initialized".into());
     };
 
-    match redis_manager
-        .stream_add(DB_EVENTS_STREAM, &[("data", &event_json)])
+    outbox
+        .send(event)
         .await
-    {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            error!("Failed to publish DB event to stream: {}", e);
-            Err(format!("Failed to publish event: {}", e))
+        .map_err(|_| "DB event outbox closed".to_string())
+}
+
+async fn run_publisher(mut rx: mpsc::Receiver<DbEvent>, redis_manager: &'static RedisManager) {
+    let mut batch = Vec::with_capacity(PUBLISH_BATCH_SIZE);
+
+    while rx.recv_many(&mut batch, PUBLISH_BATCH_SIZE).await > 0 {
+        let payloads: Vec<String> = batch
+            .drain(..)
+            .filter_map(|event| match serde_json::to_string(&event) {
+                Ok(json) => Some(json),
+                Err(e) => {
+                    error!("Failed to serialize DB event: {}", e);
+                    None
+                }
+            })
+            .collect();
+
+        if !payloads.is_empty() {
+            publish_batch(&payloads, redis_manager).await;
+        }
+    }
+}
+
+async fn publish_batch(payloads: &[String], redis_manager: &RedisManager) {
+    let entries: Vec<Vec<(&str, &str)>> = payloads
+        .iter()
+        .map(|json| vec![("data", json.as_str())])
+        .collect();
+
+    let mut backoff = Duration::from_millis(50);
+    loop {
+        // Retry the whole batch rather than skipping ahead so db_events stays in order
+        match redis_manager
+            .stream_add_batch(DB_EVENTS_STREAM, &entries)
+            .await
+        {
+            Ok(()) => return,
+            Err(e) => {
+                error!(
+                    "Failed to publish {} DB events to stream, retrying: {}",
+                    payloads.len(),
+                    e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
         }
     }
 }
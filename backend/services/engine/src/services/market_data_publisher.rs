@@ -0,0 +1,95 @@
+use crate::types::market_data_types::MarketDataEvent;
+use log::{error, warn};
+use redis_client::RedisManager;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const MARKET_DATA_STREAM: &str = "market_data";
+const MARKET_DATA_STREAM_MAX_LEN: i64 = 100_000;
+const OUTBOX_CAPACITY: usize = 65_536;
+const PUBLISH_BATCH_SIZE: usize = 512;
+
+static OUTBOX: OnceLock<mpsc::Sender<MarketDataEvent>> = OnceLock::new();
+static PUBLISHING_ENABLED: AtomicBool = AtomicBool::new(true);
+static DROPPED_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+// Unlike DB events, market data is only useful while it is fresh: a batch that fails to
+// publish is dropped instead of retried, and subscribers recover from the next snapshot.
+pub fn start_market_data_publisher() -> JoinHandle<()> {
+    let (tx, rx) = mpsc::channel(OUTBOX_CAPACITY);
+    if OUTBOX.set(tx).is_err() {
+        warn!("Market data publisher already started");
+    }
+
+    tokio::spawn(run_publisher(rx))
+}
+
+pub fn set_market_data_publishing(enabled: bool) {
+    PUBLISHING_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+// Tools that run the engine offline never start the publisher, so a missing outbox is not
+// an error here. The engine never waits on a full outbox: the event is dropped and counted,
+// and the publisher reports the count.
+pub async fn publish_market_data(event: MarketDataEvent) {
+    if !PUBLISHING_ENABLED.load(Ordering::SeqCst) {
+        return;
+    }
+
+    if let Some(outbox) = OUTBOX.get()
+        && let Err(mpsc::error::TrySendError::Full(_)) = outbox.try_send(event)
+    {
+        DROPPED_EVENTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+async fn run_publisher(mut rx: mpsc::Receiver<MarketDataEvent>) {
+    let mut batch = Vec::with_capacity(PUBLISH_BATCH_SIZE);
+
+    while rx.recv_many(&mut batch, PUBLISH_BATCH_SIZE).await > 0 {
+        let dropped = DROPPED_EVENTS.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!("Market data outbox was full, dropped {} events", dropped);
+        }
+
+        let payloads: Vec<String> = batch
+            .drain(..)
+            .filter_map(|event| match serde_json::to_string(&event) {
+                Ok(json) => Some(json),
+                Err(e) => {
+                    error!("Failed to serialize market data event: {}", e);
+                    None
+                }
+            })
+            .collect();
+
+        if payloads.is_empty() {
+            continue;
+        }
+
+        let Some(redis_manager) = RedisManager::global() else {
+            warn!(
+                "Redis manager not initialized, dropping {} market data events",
+                payloads.len()
+            );
+            continue;
+        };
+
+        let entries: Vec<Vec<(&str, &str)>> = payloads
+            .iter()
+            .map(|json| vec![("data", json.as_str())])
+            .collect();
+        if let Err(e) = redis_manager
+            .stream_add_batch_capped(MARKET_DATA_STREAM, &entries, MARKET_DATA_STREAM_MAX_LEN)
+            .await
+        {
+            error!(
+                "Failed to publish {} market data events, dropping them: {}",
+                payloads.len(),
+                e
+            );
+        }
+    }
+}
@@ -1,2 +1,5 @@
+pub mod bootstrap;
 pub mod db_event_publisher;
+pub mod market_data_publisher;
+pub mod replay;
 pub mod request_consumer;
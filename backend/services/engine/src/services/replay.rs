@@ -0,0 +1,297 @@
+use crate::services::db_event_publisher::capture_db_events;
+use crate::services::request_consumer::handle_request;
+use crate::store::context::{EngineContext, ManualClock, SequentialIdGenerator};
+use crate::store::market::MarketStore;
+use crate::store::orderbook::spawn_orderbook_actor;
+use crate::types::db_event_types::{DbEvent, TradeExecutedEvent};
+use crate::types::orderbook_types::EngineState;
+use fred::prelude::*;
+use log::info;
+use redis_client::{RedisManager, RedisRequest, RedisResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+const SERVER_REQUESTS_STREAM: &str = "server_requests";
+const USAGE: &str = "usage: engine replay (--file <path> | --from <stream id> --to <stream id> [--save <path>]) [--out <path>]";
+
+/// One `server_requests` entry, in the same shape it has on the stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub id: String,
+    pub request_id: String,
+    pub data: String,
+}
+
+#[derive(Serialize)]
+struct ReplayedRequest {
+    id: String,
+    request_id: String,
+    action: String,
+    response: RedisResponse<Value>,
+}
+
+#[derive(Serialize)]
+struct ReplayReport {
+    requests: Vec<ReplayedRequest>,
+    trades: Vec<TradeExecutedEvent>,
+    state: EngineState,
+}
+
+// Feeds recorded requests through a fresh engine whose clock follows the stream ids and whose
+// ids are sequential, so the same input always produces the same books, balances and trades.
+pub async fn run_replay(args: &[String]) -> Result<(), String> {
+    let mut options: HashMap<&str, &str> = HashMap::new();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let Some(value) = iter.next() else {
+            return Err(USAGE.into());
+        };
+        match flag.as_str() {
+            "--file" | "--from" | "--to" | "--save" | "--out" => {
+                options.insert(flag.as_str(), value.as_str());
+            }
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let recorded = match (
+        options.get("--file"),
+        options.get("--from"),
+        options.get("--to"),
+    ) {
+        (Some(path), None, None) => read_recorded_file(path)?,
+        (None, Some(from), Some(to)) => {
+            let recorded = read_recorded_range(from, to).await?;
+            if let Some(path) = options.get("--save") {
+                write_recorded_file(path, &recorded)?;
+            }
+            recorded
+        }
+        _ => return Err(USAGE.into()),
+    };
+
+    let mut events = capture_db_events();
+    let report = replay(recorded, &mut events).await?;
+
+    // Going through Value sorts every map by key, so two runs can be diffed directly
+    let report = serde_json::to_value(&report)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .map_err(|e| format!("Failed to serialize replay report: {}", e))?;
+
+    match options.get("--out") {
+        Some(path) => fs::write(path, report)
+            .map_err(|e| format!("Failed to write replay report {}: {}", path, e))?,
+        None => println!("{}", report),
+    }
+
+    Ok(())
+}
+
+async fn replay(
+    recorded: Vec<RecordedRequest>,
+    events: &mut mpsc::Receiver<DbEvent>,
+) -> Result<ReplayReport, String> {
+    let clock = Arc::new(ManualClock::default());
+    let context = EngineContext::new(clock.clone(), Arc::new(SequentialIdGenerator::new(1)));
+    let orderbook = spawn_orderbook_actor(MarketStore::new(), None, context);
+
+    let mut requests = Vec::with_capacity(recorded.len());
+    let mut trades = Vec::new();
+    let mut collect = |event: DbEvent| {
+        if let DbEvent::TradeExecuted(trade) = event {
+            trades.push(trade);
+        }
+    };
+
+    for entry in recorded {
+        if let Some(millis) = stream_id_millis(&entry.id) {
+            clock.set_millis(millis);
+        }
+
+        let request: RedisRequest<Value> = serde_json::from_str(&entry.data)
+            .map_err(|e| format!("Failed to parse request {}: {}", entry.id, e))?;
+        let action = request.action.clone();
+
+        // Drain events while the request runs so a large sweep cannot fill the outbox
+        let handled = handle_request(request, &orderbook);
+        tokio::pin!(handled);
+        let response = loop {
+            tokio::select! {
+                response = &mut handled => break response,
+                Some(event) = events.recv() => collect(event),
+            }
+        };
+        while let Ok(event) = events.try_recv() {
+            collect(event);
+        }
+
+        requests.push(ReplayedRequest {
+            id: entry.id,
+            request_id: entry.request_id,
+            action,
+            response,
+        });
+    }
+
+    let state = orderbook.export_state().await.map_err(|e| e.to_string())?;
+    info!(
+        "Replayed {} requests, {} trades",
+        requests.len(),
+        trades.len()
+    );
+
+    Ok(ReplayReport {
+        requests,
+        trades,
+        state,
+    })
+}
+
+fn stream_id_millis(id: &str) -> Option<i64> {
+    id.split('-').next()?.parse().ok()
+}
+
+fn read_recorded_file(path: &str) -> Result<Vec<RecordedRequest>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+
+    let mut recorded = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: RecordedRequest = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid recorded request in {}: {}", path, e))?;
+        recorded.push(entry);
+    }
+
+    Ok(recorded)
+}
+
+fn write_recorded_file(path: &str, recorded: &[RecordedRequest]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut writer = BufWriter::new(file);
+    for entry in recorded {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize recorded request: {}", e))?;
+        writeln!(writer, "{}", line).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+async fn read_recorded_range(from: &str, to: &str) -> Result<Vec<RecordedRequest>, String> {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+    let redis_manager = RedisManager::init_global(&redis_url)
+        .map_err(|e| format!("Failed to initialize Redis manager: {}", e))?;
+    redis_manager
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to connect to Redis: {}", e))?;
+
+    let entries = redis_manager
+        .client()
+        .xrange_values::<String, String, String, _, _, _>(SERVER_REQUESTS_STREAM, from, to, None)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", SERVER_REQUESTS_STREAM, e))?;
+
+    let recorded = entries
+        .into_iter()
+        .filter_map(|(id, mut fields)| {
+            Some(RecordedRequest {
+                request_id: fields.remove("request_id")?,
+                data: fields.remove("data")?,
+                id,
+            })
+        })
+        .collect();
+
+    Ok(recorded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::db_event_publisher::ENGINE_TEST_LOCK;
+    use serde_json::json;
+
+    fn recorded(id: &str, action: &str, data: Value) -> RecordedRequest {
+        let request = RedisRequest::new("engine", action, "", data);
+        RecordedRequest {
+            id: id.to_string(),
+            request_id: format!("req-{}", id),
+            data: serde_json::to_string(&request).unwrap(),
+        }
+    }
+
+    fn session() -> Vec<RecordedRequest> {
+        let order = |user_id: u64, side: &str, price: u64, qty: u64| {
+            json!({
+                "market_id": 100,
+                "user_id": user_id,
+                "price": price,
+                "original_qty": qty,
+                "remaining_qty": qty,
+                "side": side,
+                "order_type": "Limit"
+            })
+        };
+
+        vec![
+            recorded(
+                "1700000000000-0",
+                "init-event-markets",
+                json!({
+                    "event_id": 1,
+                    "outcomes": [{ "outcome_id": 10, "yes_market_id": 100, "no_market_id": 101 }]
+                }),
+            ),
+            recorded(
+                "1700000000001-0",
+                "create-user",
+                json!({ "id": 1, "name": "alice", "email": "alice@example.com", "balance": 10000 }),
+            ),
+            recorded(
+                "1700000000002-0",
+                "create-user",
+                json!({ "id": 2, "name": "bob", "email": "bob@example.com", "balance": 10000 }),
+            ),
+            recorded("1700000000003-0", "place-order", order(1, "Bid", 55, 10)),
+            recorded(
+                "1700000000004-0",
+                "split-order",
+                json!({ "user_id": 2, "market1_id": 100, "market2_id": 101, "amount": 10 }),
+            ),
+            recorded("1700000000005-0", "place-order", order(2, "Ask", 60, 5)),
+            recorded("1700000000006-0", "place-order", order(2, "Ask", 55, 4)),
+            recorded(
+                "1700000000007-0",
+                "onramp",
+                json!({ "user_id": 1, "amount": 500 }),
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn replaying_the_same_requests_gives_the_same_report() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let mut events = capture_db_events();
+
+        let first = replay(session(), &mut events).await.unwrap();
+        let second = replay(session(), &mut events).await.unwrap();
+
+        assert!(!first.trades.is_empty());
+        assert!(first.requests.iter().all(|r| r.response.success));
+        assert_eq!(
+            serde_json::to_value(&first).unwrap(),
+            serde_json::to_value(&second).unwrap()
+        );
+    }
+}
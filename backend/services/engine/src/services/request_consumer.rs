@@ -1,14 +1,18 @@
-use crate::store::orderbook::Orderbook;
-use crate::types::market_types::MarketMeta;
-use crate::types::orderbook_types::Order;
+use crate::store::orderbook::{ClientOrder, ClosedClientOrder, ClosedStatus, Orderbook};
+use crate::store::probabilities::event_probabilities;
+use crate::types::error_types::EngineError;
+use crate::types::market_types::{MarketMeta, MarketStatus};
+use crate::types::orderbook_types::{Order, SnapshotOptions};
 use crate::types::request_types::*;
 use crate::types::user_types::User;
+use crate::types::withdrawal_types::WithdrawalDecision;
 use fred::prelude::*;
 use log::{error, info, warn};
 use redis_client::RedisManager;
 use redis_client::{RedisRequest, RedisResponse};
+use serde::de::DeserializeOwned;
 use serde_json::{Value, json};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 pub async fn start_request_consumer(orderbook: Orderbook) {
     let redis_manager = match RedisManager::global() {
@@ -24,7 +28,21 @@ pub async fn start_request_consumer(orderbook: Orderbook) {
 
     tokio::spawn(async move {
         info!("Starting request consumer for stream: {}", stream_name);
-        let mut last_id = "0".to_string();
+        // The snapshot and journal remember the last request they applied, so reading resumes
+        // right after it. State bootstrapped from the database has no such position and
+        // already reflects everything before startup.
+        let mut last_id = match orderbook.stream_position().await {
+            Some(id) => id,
+            None => match latest_stream_id(&client, stream_name).await {
+                Ok(Some(id)) => id,
+                Ok(None) => "0".to_string(),
+                Err(e) => {
+                    error!("Failed to read latest id of stream {}: {}", stream_name, e);
+                    "0".to_string()
+                }
+            },
+        };
+        info!("Resuming stream {} after id {}", stream_name, last_id);
 
         loop {
             match read_stream_messages(&client, stream_name, &mut last_id).await {
@@ -45,7 +63,17 @@ pub async fn start_request_consumer(orderbook: Orderbook) {
     });
 }
 
-async fn read_stream_messages(
+pub(crate) async fn latest_stream_id(
+    client: &RedisClient,
+    stream: &str,
+) -> Result<Option<String>, RedisError> {
+    let latest = client
+        .xrevrange_values::<String, String, String, _, _, _>(stream, "+", "-", Some(1))
+        .await?;
+    Ok(latest.into_iter().next().map(|(id, _)| id))
+}
+
+pub(crate) async fn read_stream_messages(
     client: &RedisClient,
     stream: &str,
     last_id: &mut String,
@@ -150,7 +178,7 @@ async fn process_message(
     orderbook: &Orderbook,
 ) -> Result<(), String> {
     for (_stream_name, messages) in stream_data {
-        for (_msg_id, fields) in messages {
+        for (msg_id, fields) in messages {
             let mut request_id: Option<String> = None;
             let mut data_str: Option<String> = None;
 
@@ -174,101 +202,94 @@ async fn process_message(
                 request.action, request_id
             );
 
-            let response = match request.action.as_str() {
-                "place-order" => handle_place_order(request.data, orderbook).await,
-                "cancel-order" => handle_cancel_order(request.data, orderbook).await,
-                "modify-order" => handle_modify_order(request.data, orderbook).await,
-                "get-open-orders" => handle_get_open_orders(request.data, orderbook).await,
-                "get-order-status" => handle_get_order_status(request.data, orderbook).await,
-                "get-order-history" => handle_get_order_history(request.data, orderbook).await,
-                "get-orderbook" => handle_get_orderbook(request.data, orderbook).await,
-                "get-orderbook-by-event" => {
-                    handle_get_orderbooks_by_event(request.data, orderbook).await
-                }
-                "get-orderbook-by-outcome" => {
-                    handle_get_orderbooks_by_outcome(request.data, orderbook).await
-                }
-                "create-user" => handle_create_user(request.data, orderbook).await,
-                "get-balance" => handle_get_balance(request.data, orderbook).await,
-                "onramp" => handle_onramp(request.data, orderbook).await,
-                "get-positions" => handle_get_positions(request.data, orderbook).await,
-                "get-position" => handle_get_position(request.data, orderbook).await,
-                "get-portfolio" => handle_get_portfolio(request.data, orderbook).await,
-                "split-order" => handle_split_order(request.data, orderbook).await,
-                "merge-order" => handle_merge_order(request.data, orderbook).await,
-                "init-event-markets" => handle_init_event_markets(request.data, orderbook).await,
-                "close-event-markets" => handle_close_event_markets(request.data, orderbook).await,
-                _ => {
-                    warn!("Unknown action: {}", request.action);
-                    Ok(RedisResponse::new(
-                        400,
-                        false,
-                        format!("Unknown action: {}", request.action),
-                        serde_json::json!(null),
-                    ))
-                }
-            };
-
-            match response {
-                Ok(resp) => {
-                    send_response(request_id, resp).await?;
-                }
-                Err(e) => {
-                    error!("Error handling request {}: {}", request.action, e);
-                    let error_response = RedisResponse::new(
-                        500,
-                        false,
-                        format!("Error processing request: {}", e),
-                        serde_json::json!(null),
-                    );
-                    send_response(request_id, error_response).await?;
-                }
-            }
+            orderbook.set_stream_position(msg_id).await;
+            let response = handle_request(request, orderbook).await;
+            send_response(request_id, response).await?;
         }
     }
 
     Ok(())
 }
 
+pub(crate) async fn handle_request(
+    request: RedisRequest<Value>,
+    orderbook: &Orderbook,
+) -> RedisResponse<Value> {
+    let response = match request.action.as_str() {
+        "place-order" => handle_place_order(request.data, orderbook).await,
+        "cancel-order" => handle_cancel_order(request.data, orderbook).await,
+        "modify-order" => handle_modify_order(request.data, orderbook).await,
+        "get-open-orders" => handle_get_open_orders(request.data, orderbook).await,
+        "get-order-status" => handle_get_order_status(request.data, orderbook).await,
+        "get-client-order" => handle_get_client_order(request.data, orderbook).await,
+        "get-order-history" => handle_get_order_history(request.data, orderbook).await,
+        "get-orderbook" => handle_get_orderbook(request.data, orderbook).await,
+        "get-orderbook-l3" => handle_get_orderbook_l3(request.data, orderbook).await,
+        "get-top-of-book" => handle_get_top_of_book(request.data, orderbook).await,
+        "list-markets" => handle_list_markets(request.data, orderbook).await,
+        "get-orderbook-by-event" => handle_get_orderbooks_by_event(request.data, orderbook).await,
+        "get-event-probabilities" => handle_get_event_probabilities(request.data, orderbook).await,
+        "get-orderbook-by-outcome" => {
+            handle_get_orderbooks_by_outcome(request.data, orderbook).await
+        }
+        "create-user" => handle_create_user(request.data, orderbook).await,
+        "get-balance" => handle_get_balance(request.data, orderbook).await,
+        "onramp" => handle_onramp(request.data, orderbook).await,
+        "request-withdrawal" => handle_request_withdrawal(request.data, orderbook).await,
+        "approve-withdrawal" => handle_decide_withdrawal(request.data, orderbook, true).await,
+        "reject-withdrawal" => handle_decide_withdrawal(request.data, orderbook, false).await,
+        "create-deposit" => handle_create_deposit(request.data, orderbook).await,
+        "confirm-deposit" => handle_settle_deposit(request.data, orderbook, true).await,
+        "fail-deposit" => handle_settle_deposit(request.data, orderbook, false).await,
+        "get-positions" => handle_get_positions(request.data, orderbook).await,
+        "get-position" => handle_get_position(request.data, orderbook).await,
+        "get-portfolio" => handle_get_portfolio(request.data, orderbook).await,
+        "split-order" => handle_split_order(request.data, orderbook).await,
+        "merge-order" => handle_merge_order(request.data, orderbook).await,
+        "init-event-markets" => handle_init_event_markets(request.data, orderbook).await,
+        "close-event-markets" => handle_close_event_markets(request.data, orderbook).await,
+        _ => {
+            warn!("Unknown action: {}", request.action);
+            Err(EngineError::InvalidRequest(format!(
+                "Unknown action: {}",
+                request.action
+            )))
+        }
+    };
+
+    match response {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!("Error handling request {}: {}", request.action, e);
+            error_response(&e)
+        }
+    }
+}
+
 async fn handle_place_order(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: PlaceOrderRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: PlaceOrderRequest = parse_request(data)?;
 
     let price = match req.order_type {
         crate::types::orderbook_types::OrderType::Market => match req.side {
             crate::types::orderbook_types::OrderSide::Bid => {
                 match orderbook.best_ask(req.market_id).await {
                     Ok(p) if p > 0 => p,
-                    _ => {
-                        return Ok(RedisResponse::new(
-                            400,
-                            false,
-                            "No available ask price for market order".to_string(),
-                            serde_json::json!(null),
-                        ));
-                    }
+                    _ => return Ok(error_response(&EngineError::NoLiquidity)),
                 }
             }
             crate::types::orderbook_types::OrderSide::Ask => {
                 match orderbook.best_bid(req.market_id).await {
                     Ok(p) if p > 0 => p,
-                    _ => {
-                        return Ok(RedisResponse::new(
-                            400,
-                            false,
-                            "No available bid price for market order".to_string(),
-                            serde_json::json!(null),
-                        ));
-                    }
+                    _ => return Ok(error_response(&EngineError::NoLiquidity)),
                 }
             }
         },
         crate::types::orderbook_types::OrderType::Limit => req
             .price
-            .ok_or_else(|| "Price required for limit orders".to_string())?,
+            .ok_or_else(|| EngineError::InvalidRequest("Price required for limit orders".into()))?,
     };
 
     let order = Order {
@@ -280,12 +301,14 @@ async fn handle_place_order(
         remaining_qty: req.remaining_qty,
         side: req.side,
         order_type: req.order_type,
+        hidden: req.hidden,
+        client_order_id: req.client_order_id,
     };
 
     match orderbook.place_order(order).await {
         Ok(result_order) => {
             let order_json = serde_json::to_value(&result_order)
-                .map_err(|e| format!("Failed to serialize order: {}", e))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize order: {}", e)))?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -293,26 +316,48 @@ async fn handle_place_order(
                 order_json,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to place order: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_cancel_order(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: CancelOrderRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: CancelOrderRequest = parse_request(data)?;
+
+    let order_id = match &req.client_order_id {
+        Some(client_order_id) => {
+            let Some(entry) = orderbook
+                .get_client_order(req.user_id, client_order_id.clone())
+                .await
+            else {
+                return Ok(error_response(&EngineError::OrderNotFound));
+            };
 
-    match orderbook.cancel_order(req.market_id, req.order_id).await {
+            match entry {
+                ClientOrder::Open(entry) => entry.order_id,
+                // A retried cancel gets a success rather than "Order not found"
+                ClientOrder::Closed(closed) if closed.status == ClosedStatus::Cancelled => {
+                    return Ok(RedisResponse::new(
+                        200,
+                        true,
+                        "Order cancelled successfully",
+                        closed_client_order_json(req.user_id, client_order_id, &closed),
+                    ));
+                }
+                ClientOrder::Closed(_) => return Ok(error_response(&EngineError::OrderNotFound)),
+            }
+        }
+        None => req.order_id.ok_or_else(|| {
+            EngineError::InvalidRequest("order_id or client_order_id is required".into())
+        })?,
+    };
+
+    match orderbook.cancel_order(req.market_id, order_id).await {
         Ok(result_order) => {
             let order_json = serde_json::to_value(&result_order)
-                .map_err(|e| format!("Failed to serialize order: {}", e))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize order: {}", e)))?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -320,32 +365,53 @@ async fn handle_cancel_order(
                 order_json,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to cancel order: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_modify_order(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: ModifyOrderRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: ModifyOrderRequest = parse_request(data)?;
+
+    let order_id = match &req.client_order_id {
+        Some(client_order_id) => {
+            let Some(ClientOrder::Open(entry)) = orderbook
+                .get_client_order(req.user_id, client_order_id.clone())
+                .await
+            else {
+                return Ok(error_response(&EngineError::OrderNotFound));
+            };
 
-    let existing_order = match orderbook.get_order_status(req.order_id).await {
-        Ok(order) => order,
-        Err(_) => {
-            return Ok(RedisResponse::new(
-                404,
-                false,
-                "Order not found",
-                serde_json::json!(null),
-            ));
+            // Modifications set absolute values, so a retry asking for what the last
+            // modification already produced gets that result back
+            if let Some(modified) = entry.modified
+                && req.price.is_none_or(|price| price == modified.price)
+                && req
+                    .original_qty
+                    .is_none_or(|qty| qty == modified.original_qty)
+            {
+                let order_json = serde_json::to_value(&modified).map_err(|e| {
+                    EngineError::Internal(format!("Failed to serialize order: {}", e))
+                })?;
+                return Ok(RedisResponse::new(
+                    200,
+                    true,
+                    "Order modified successfully",
+                    order_json,
+                ));
+            }
+            entry.order_id
         }
+        None => req.order_id.ok_or_else(|| {
+            EngineError::InvalidRequest("order_id or client_order_id is required".into())
+        })?,
+    };
+
+    let existing_order = match orderbook.get_order_status(order_id).await {
+        Ok(order) => order,
+        Err(e) => return Ok(error_response(&e)),
     };
 
     let mut updated_order = existing_order.clone();
@@ -361,7 +427,7 @@ async fn handle_modify_order(
     match orderbook.modify_order(updated_order).await {
         Ok(result_order) => {
             let order_json = serde_json::to_value(&result_order)
-                .map_err(|e| format!("Failed to serialize order: {}", e))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize order: {}", e)))?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -369,26 +435,35 @@ async fn handle_modify_order(
                 order_json,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to modify order: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
+fn parse_request<T: DeserializeOwned>(data: Value) -> Result<T, EngineError> {
+    serde_json::from_value(data)
+        .map_err(|e| EngineError::InvalidRequest(format!("Invalid request data: {}", e)))
+}
+
+fn error_response(error: &EngineError) -> RedisResponse<Value> {
+    RedisResponse::new(
+        error.status_code(),
+        false,
+        error.to_string(),
+        serde_json::json!(null),
+    )
+    .with_error_code(error.code())
+}
+
 async fn handle_get_open_orders(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: GetOpenOrdersRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetOpenOrdersRequest = parse_request(data)?;
 
     match orderbook.get_user_open_orders(req.user_id).await {
         Ok(orders) => {
             let orders_json = serde_json::to_value(&orders)
-                .map_err(|e| format!("Failed to serialize orders: {}", e))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize orders: {}", e)))?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -396,26 +471,20 @@ async fn handle_get_open_orders(
                 orders_json,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            500,
-            false,
-            format!("Failed to get open orders: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_get_order_status(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: GetOrderStatusRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetOrderStatusRequest = parse_request(data)?;
 
     match orderbook.get_order_status(req.order_id).await {
         Ok(order) => {
             let order_json = serde_json::to_value(&order)
-                .map_err(|e| format!("Failed to serialize order: {}", e))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize order: {}", e)))?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -423,21 +492,62 @@ async fn handle_get_order_status(
                 order_json,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            404,
-            false,
-            format!("Order not found: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
+async fn handle_get_client_order(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetClientOrderRequest = parse_request(data)?;
+
+    let order_json = match orderbook
+        .get_client_order(req.user_id, req.client_order_id.clone())
+        .await
+    {
+        Some(ClientOrder::Open(entry)) => {
+            let order = match orderbook.get_order_status(entry.order_id).await {
+                Ok(order) => order,
+                Err(e) => return Ok(error_response(&e)),
+            };
+            let mut order_json = serde_json::to_value(&order)
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize order: {}", e)))?;
+            order_json["status"] = json!("open");
+            order_json
+        }
+        Some(ClientOrder::Closed(closed)) => {
+            closed_client_order_json(req.user_id, &req.client_order_id, &closed)
+        }
+        None => return Ok(error_response(&EngineError::OrderNotFound)),
+    };
+
+    Ok(RedisResponse::new(
+        200,
+        true,
+        "Order fetched successfully",
+        order_json,
+    ))
+}
+
+fn closed_client_order_json(
+    user_id: u64,
+    client_order_id: &str,
+    closed: &ClosedClientOrder,
+) -> Value {
+    json!({
+        "order_id": closed.order_id,
+        "user_id": user_id,
+        "client_order_id": client_order_id,
+        "status": closed.status,
+    })
+}
+
 async fn handle_get_order_history(
     data: Value,
     _orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let _req: GetOrderHistoryRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let _req: GetOrderHistoryRequest = parse_request(data)?;
 
     Ok(RedisResponse::new(
         200,
@@ -450,14 +560,14 @@ async fn handle_get_order_history(
 async fn handle_get_orderbook(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: GetOrderbookByMarketRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetOrderbookByMarketRequest = parse_request(data)?;
 
-    match orderbook.get_orderbook(req.market_id).await {
+    match orderbook.get_orderbook(req.market_id, req.options).await {
         Ok(snapshot) => {
-            let snapshot_json = serde_json::to_value(snapshot)
-                .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+            let snapshot_json = serde_json::to_value(snapshot).map_err(|e| {
+                EngineError::Internal(format!("Failed to serialize snapshot: {}", e))
+            })?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -465,26 +575,99 @@ async fn handle_get_orderbook(
                 snapshot_json,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            404,
-            false,
-            format!("Failed to fetch orderbook: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
+async fn handle_get_orderbook_l3(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetOrderbookByMarketRequest = parse_request(data)?;
+
+    match orderbook.get_orderbook_l3(req.market_id).await {
+        Ok(snapshot) => {
+            let snapshot_json = serde_json::to_value(snapshot).map_err(|e| {
+                EngineError::Internal(format!("Failed to serialize snapshot: {}", e))
+            })?;
+            Ok(RedisResponse::new(
+                200,
+                true,
+                "L3 orderbook snapshot retrieved successfully",
+                snapshot_json,
+            ))
+        }
+        Err(e) => Ok(error_response(&e)),
+    }
+}
+
+async fn handle_get_top_of_book(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetTopOfBookRequest = parse_request(data)?;
+
+    let tops = orderbook.get_top_of_book(req.market_ids).await;
+    let payload = serde_json::to_value(tops)
+        .map_err(|e| EngineError::Internal(format!("Failed to serialize top of book: {}", e)))?;
+    Ok(RedisResponse::new(
+        200,
+        true,
+        "Top of book retrieved successfully",
+        payload,
+    ))
+}
+
+async fn handle_list_markets(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: ListMarketsRequest = parse_request(data)?;
+
+    let status = match req
+        .status
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        None => None,
+        Some("active") => Some(MarketStatus::Active),
+        Some("paused") => Some(MarketStatus::Paused),
+        Some("resolved") => Some(MarketStatus::Resolved),
+        Some("cancelled") => Some(MarketStatus::Cancelled),
+        Some(other) => {
+            return Err(EngineError::InvalidRequest(format!(
+                "Unknown market status: {}",
+                other
+            )));
+        }
+    };
+
+    let markets = orderbook.list_markets(status, req.event_id).await;
+    let payload = serde_json::to_value(markets)
+        .map_err(|e| EngineError::Internal(format!("Failed to serialize markets: {}", e)))?;
+    Ok(RedisResponse::new(
+        200,
+        true,
+        "Markets retrieved successfully",
+        payload,
+    ))
+}
+
 async fn handle_get_orderbooks_by_event(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: GetOrderbooksByEventRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetOrderbooksByEventRequest = parse_request(data)?;
 
-    match orderbook.get_event_orderbooks(req.event_id).await {
+    match orderbook
+        .get_event_orderbooks(req.event_id, req.options)
+        .await
+    {
         Ok(result) => {
-            let payload = serde_json::to_value(result)
-                .map_err(|e| format!("Failed to serialize event orderbooks: {}", e))?;
+            let payload = serde_json::to_value(result).map_err(|e| {
+                EngineError::Internal(format!("Failed to serialize event orderbooks: {}", e))
+            })?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -492,26 +675,50 @@ async fn handle_get_orderbooks_by_event(
                 payload,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            404,
-            false,
-            format!("Failed to fetch event orderbooks: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
+    }
+}
+
+async fn handle_get_event_probabilities(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetEventProbabilitiesRequest = parse_request(data)?;
+    let options = SnapshotOptions {
+        depth: Some(1),
+        ..SnapshotOptions::default()
+    };
+
+    match orderbook.get_event_orderbooks(req.event_id, options).await {
+        Ok(snapshot) => {
+            let payload = serde_json::to_value(event_probabilities(&snapshot)).map_err(|e| {
+                EngineError::Internal(format!("Failed to serialize event probabilities: {}", e))
+            })?;
+            Ok(RedisResponse::new(
+                200,
+                true,
+                "Event probabilities retrieved successfully",
+                payload,
+            ))
+        }
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_get_orderbooks_by_outcome(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: GetOrderbooksByOutcomeRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetOrderbooksByOutcomeRequest = parse_request(data)?;
 
-    match orderbook.get_outcome_orderbooks(req.outcome_id).await {
+    match orderbook
+        .get_outcome_orderbooks(req.outcome_id, req.options)
+        .await
+    {
         Ok(result) => {
-            let payload = serde_json::to_value(result)
-                .map_err(|e| format!("Failed to serialize outcome orderbooks: {}", e))?;
+            let payload = serde_json::to_value(result).map_err(|e| {
+                EngineError::Internal(format!("Failed to serialize outcome orderbooks: {}", e))
+            })?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -519,21 +726,15 @@ async fn handle_get_orderbooks_by_outcome(
                 payload,
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            404,
-            false,
-            format!("Failed to fetch outcome orderbooks: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_create_user(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: CreateUserRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: CreateUserRequest = parse_request(data)?;
 
     let user = User {
         id: req.id,
@@ -541,12 +742,17 @@ async fn handle_create_user(
         email: req.email,
         balance: req.balance,
         positions: HashMap::new(),
+        locked_balance: 0,
+        locked_positions: HashMap::new(),
+        costs: HashMap::new(),
+        pending_withdrawals: HashMap::new(),
+        deposits: HashMap::new(),
     };
 
     match orderbook.add_user(user.clone()).await {
         Some(_) => {
             let user_json = serde_json::to_value(&user)
-                .map_err(|e| format!("Failed to serialize user: {}", e))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to serialize user: {}", e)))?;
             Ok(RedisResponse::new(
                 200,
                 true,
@@ -554,49 +760,39 @@ async fn handle_create_user(
                 user_json,
             ))
         }
-        None => Ok(RedisResponse::new(
-            500,
-            false,
-            "Failed to create user",
-            serde_json::json!(null),
-        )),
+        None => Ok(error_response(&EngineError::Internal(
+            "Failed to create user".into(),
+        ))),
     }
 }
 
 async fn handle_get_balance(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: GetBalanceRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: GetBalanceRequest = parse_request(data)?;
 
-    match orderbook.get_balance(req.user_id).await {
-        Ok(balance) => Ok(RedisResponse::new(
+    match orderbook.get_user_by_id(req.user_id).await {
+        Some(user) => Ok(RedisResponse::new(
             200,
             true,
             "Balance retrieved successfully",
-            serde_json::json!({ "balance": balance }),
-        )),
-        Err(e) => Ok(RedisResponse::new(
-            404,
-            false,
-            format!("Failed to get balance: {}", e),
-            serde_json::json!(null),
+            balance_json(&user),
         )),
+        None => Ok(error_response(&EngineError::UserNotFound)),
     }
 }
 
-async fn handle_onramp(data: Value, orderbook: &Orderbook) -> Result<RedisResponse<Value>, String> {
-    let req: OnrampRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+async fn handle_onramp(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: OnrampRequest = parse_request(data)?;
 
     if req.amount <= 0 {
-        return Ok(RedisResponse::new(
-            400,
-            false,
-            "Amount must be greater than 0",
-            serde_json::json!(null),
-        ));
+        return Ok(error_response(&EngineError::InvalidRequest(
+            "Amount must be greater than 0".into(),
+        )));
     }
 
     match orderbook.update_balance(req.user_id, req.amount).await {
@@ -607,40 +803,122 @@ async fn handle_onramp(data: Value, orderbook: &Orderbook) -> Result<RedisRespon
                 "Balance updated successfully",
                 serde_json::json!({ "balance": new_balance }),
             )),
-            Err(e) => Ok(RedisResponse::new(
-                500,
-                false,
-                format!("Balance updated but failed to retrieve: {}", e),
-                serde_json::json!(null),
-            )),
+            Err(e) => Ok(error_response(&e)),
         },
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to update balance: {}", e),
-            serde_json::json!(null),
+        Err(e) => Ok(error_response(&e)),
+    }
+}
+
+async fn handle_request_withdrawal(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: WithdrawalRequest = parse_request(data)?;
+
+    match orderbook.request_withdrawal(req.user_id, req.amount).await {
+        Ok(withdrawal) => Ok(RedisResponse::new(
+            200,
+            true,
+            "Withdrawal requested successfully",
+            json!({ "withdrawal": withdrawal }),
+        )),
+        Err(e) => Ok(error_response(&e)),
+    }
+}
+
+async fn handle_decide_withdrawal(
+    data: Value,
+    orderbook: &Orderbook,
+    approve: bool,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let decision: WithdrawalDecision = parse_request(data)?;
+
+    let (result, message) = if approve {
+        (
+            orderbook.approve_withdrawal(decision).await,
+            "Withdrawal approved successfully",
+        )
+    } else {
+        (
+            orderbook.reject_withdrawal(decision).await,
+            "Withdrawal rejected successfully",
+        )
+    };
+
+    match result {
+        Ok(withdrawal) => Ok(RedisResponse::new(
+            200,
+            true,
+            message,
+            json!({ "withdrawal": withdrawal }),
+        )),
+        Err(e) => Ok(error_response(&e)),
+    }
+}
+
+async fn handle_create_deposit(
+    data: Value,
+    orderbook: &Orderbook,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: CreateDepositRequest = parse_request(data)?;
+
+    match orderbook
+        .create_deposit(req.deposit_id, req.user_id, req.amount, req.provider)
+        .await
+    {
+        Ok(deposit) => Ok(RedisResponse::new(
+            200,
+            true,
+            "Deposit created successfully",
+            json!({ "deposit": deposit }),
+        )),
+        Err(e) => Ok(error_response(&e)),
+    }
+}
+
+async fn handle_settle_deposit(
+    data: Value,
+    orderbook: &Orderbook,
+    confirmed: bool,
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: SettleDepositRequest = parse_request(data)?;
+
+    let (result, message) = if confirmed {
+        (
+            orderbook.confirm_deposit(req.deposit_id).await,
+            "Deposit confirmed successfully",
+        )
+    } else {
+        (
+            orderbook.fail_deposit(req.deposit_id).await,
+            "Deposit marked as failed",
+        )
+    };
+
+    match result {
+        Ok(deposit) => Ok(RedisResponse::new(
+            200,
+            true,
+            message,
+            json!({ "deposit": deposit }),
         )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_get_positions(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
+) -> Result<RedisResponse<Value>, EngineError> {
     let user_id = data["user_id"]
         .as_u64()
-        .ok_or_else(|| "Missing or invalid user_id".to_string())?;
+        .ok_or_else(|| EngineError::InvalidRequest("Missing or invalid user_id".into()))?;
 
-    match orderbook.get_user_positions(user_id).await {
-        Ok(positions) => {
-            let positions_vec: Vec<_> = positions
-                .iter()
-                .map(|(market_id, qty)| {
-                    json!({
-                        "market_id": market_id,
-                        "quantity": qty
-                    })
-                })
+    match orderbook.get_user_by_id(user_id).await {
+        Some(user) => {
+            let positions_vec: Vec<_> = held_markets(&user)
+                .into_iter()
+                .map(|market_id| position_json(&user, market_id))
                 .collect();
             Ok(RedisResponse::new(
                 200,
@@ -649,123 +927,203 @@ async fn handle_get_positions(
                 serde_json::json!({ "positions": positions_vec }),
             ))
         }
-        Err(e) => Ok(RedisResponse::new(
-            500,
-            false,
-            format!("Failed to get positions: {}", e),
-            serde_json::json!(null),
-        )),
+        None => Ok(error_response(&EngineError::UserNotFound)),
     }
 }
 
 async fn handle_get_position(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
+) -> Result<RedisResponse<Value>, EngineError> {
     let user_id = data["user_id"]
         .as_u64()
-        .ok_or_else(|| "Missing or invalid user_id".to_string())?;
+        .ok_or_else(|| EngineError::InvalidRequest("Missing or invalid user_id".into()))?;
     let market_id = data["market_id"]
         .as_u64()
-        .ok_or_else(|| "Missing or invalid market_id".to_string())?;
+        .ok_or_else(|| EngineError::InvalidRequest("Missing or invalid market_id".into()))?;
 
-    match orderbook.get_position(user_id, market_id).await {
-        Ok(position) => Ok(RedisResponse::new(
+    match orderbook.get_user_by_id(user_id).await {
+        Some(user) => Ok(RedisResponse::new(
             200,
             true,
             "Position retrieved successfully",
-            serde_json::json!({
-                "market_id": market_id,
-                "quantity": position
-            }),
-        )),
-        Err(e) => Ok(RedisResponse::new(
-            404,
-            false,
-            format!("Failed to get position: {}", e),
-            serde_json::json!(null),
+            position_json(&user, market_id),
         )),
+        None => Ok(error_response(&EngineError::UserNotFound)),
     }
 }
 
 async fn handle_get_portfolio(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
+) -> Result<RedisResponse<Value>, EngineError> {
     let user_id = data["user_id"]
         .as_u64()
-        .ok_or_else(|| "Missing or invalid user_id".to_string())?;
+        .ok_or_else(|| EngineError::InvalidRequest("Missing or invalid user_id".into()))?;
 
-    let positions = match orderbook.get_user_positions(user_id).await {
-        Ok(p) => p,
-        Err(e) => {
-            return Ok(RedisResponse::new(
-                500,
-                false,
-                format!("Failed to get positions: {}", e),
-                serde_json::json!(null),
-            ));
-        }
-    };
-
-    let balance = match orderbook.get_balance(user_id).await {
-        Ok(b) => b,
-        Err(e) => {
-            return Ok(RedisResponse::new(
-                500,
-                false,
-                format!("Failed to get balance: {}", e),
-                serde_json::json!(null),
-            ));
-        }
+    let Some(user) = orderbook.get_user_by_id(user_id).await else {
+        return Ok(error_response(&EngineError::UserNotFound));
     };
+    let balance = user.total_balance();
 
     let mut total_value = balance as u64;
+    let mut total_realized = 0i64;
+    let mut total_unrealized = 0i64;
     let mut positions_with_value = Vec::new();
-
-    for (market_id, quantity) in &positions {
-        let best_bid = orderbook.best_bid(*market_id).await.unwrap_or(0);
-        let best_ask = orderbook.best_ask(*market_id).await.unwrap_or(0);
-        let market_price = if best_bid > 0 && best_ask > 0 {
-            (best_bid + best_ask) / 2
-        } else if best_bid > 0 {
-            best_bid
-        } else if best_ask > 0 {
-            best_ask
+    let mut events: BTreeMap<Option<u64>, EventPnl> = BTreeMap::new();
+
+    let mut markets = held_markets(&user);
+    markets.extend(user.costs.keys().copied());
+    markets.sort_unstable();
+    markets.dedup();
+
+    for market_id in markets {
+        let quantity = user.held_position(market_id);
+        let cost = user.cost(market_id);
+        let market_price = if quantity > 0 {
+            mark_price(orderbook, market_id).await
         } else {
             0
         };
 
-        let position_value = *quantity * market_price;
+        let position_value = quantity * market_price;
+        let unrealized_pnl = if quantity > 0 {
+            position_value as i64 - cost.cost_basis
+        } else {
+            0
+        };
         total_value += position_value;
+        total_realized += cost.realized_pnl;
+        total_unrealized += unrealized_pnl;
+
+        let event_id = orderbook
+            .get_market(market_id)
+            .await
+            .and_then(|market| market.event_id);
+        let event = events.entry(event_id).or_default();
+        event.cost_basis += cost.cost_basis;
+        event.value += position_value;
+        event.realized_pnl += cost.realized_pnl;
+        event.unrealized_pnl += unrealized_pnl;
 
         positions_with_value.push(json!({
             "market_id": market_id,
+            "event_id": event_id,
             "quantity": quantity,
+            "available": user.position(market_id),
+            "locked": user.locked_position(market_id),
+            "avg_entry_price": user.average_entry_price(market_id),
+            "cost_basis": cost.cost_basis,
             "market_price": market_price,
-            "value": position_value
+            "value": position_value,
+            "realized_pnl": cost.realized_pnl,
+            "unrealized_pnl": unrealized_pnl
         }));
     }
 
+    let events_json: Vec<Value> = events
+        .into_iter()
+        .map(|(event_id, pnl)| {
+            json!({
+                "event_id": event_id,
+                "cost_basis": pnl.cost_basis,
+                "value": pnl.value,
+                "realized_pnl": pnl.realized_pnl,
+                "unrealized_pnl": pnl.unrealized_pnl,
+                "pnl": pnl.realized_pnl + pnl.unrealized_pnl
+            })
+        })
+        .collect();
+
     Ok(RedisResponse::new(
         200,
         true,
         "Portfolio retrieved successfully",
         serde_json::json!({
             "balance": balance,
+            "available_balance": user.balance,
+            "locked_balance": user.locked_balance,
             "total_value": total_value,
             "positions": positions_with_value,
-            "pnl": total_value as i64 - balance
+            "events": events_json,
+            "realized_pnl": total_realized,
+            "unrealized_pnl": total_unrealized,
+            "pnl": total_realized + total_unrealized
         }),
     ))
 }
 
+#[derive(Default)]
+struct EventPnl {
+    cost_basis: i64,
+    value: u64,
+    realized_pnl: i64,
+    unrealized_pnl: i64,
+}
+
+// Mid of the best bid and ask, else whichever side exists, else the last trade price.
+async fn mark_price(orderbook: &Orderbook, market_id: u64) -> u64 {
+    let best_bid = orderbook.best_bid(market_id).await.unwrap_or(0);
+    let best_ask = orderbook.best_ask(market_id).await.unwrap_or(0);
+    if best_bid > 0 && best_ask > 0 {
+        (best_bid + best_ask) / 2
+    } else if best_bid > 0 {
+        best_bid
+    } else if best_ask > 0 {
+        best_ask
+    } else {
+        orderbook
+            .get_orderbook(market_id, SnapshotOptions::default())
+            .await
+            .ok()
+            .and_then(|snapshot| snapshot.last_price)
+            .unwrap_or(0)
+    }
+}
+
+fn balance_json(user: &User) -> Value {
+    json!({
+        "balance": user.balance,
+        "available": user.balance,
+        "locked": user.locked_balance,
+        "total": user.total_balance()
+    })
+}
+
+// Markets where the user holds shares, whether free or tied up in resting asks.
+fn held_markets(user: &User) -> Vec<u64> {
+    let mut markets: Vec<u64> = user
+        .positions
+        .keys()
+        .chain(user.locked_positions.keys())
+        .copied()
+        .collect();
+    markets.sort_unstable();
+    markets.dedup();
+    markets
+}
+
+fn position_json(user: &User, market_id: u64) -> Value {
+    let available = user.position(market_id);
+    let locked = user.locked_position(market_id);
+    let cost = user.cost(market_id);
+    json!({
+        "market_id": market_id,
+        "quantity": available + locked,
+        "available": available,
+        "locked": locked,
+        "total": available + locked,
+        "avg_entry_price": user.average_entry_price(market_id),
+        "cost_basis": cost.cost_basis,
+        "realized_pnl": cost.realized_pnl
+    })
+}
+
 async fn handle_split_order(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: SplitOrderRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: SplitOrderRequest = parse_request(data)?;
 
     match orderbook
         .create_split_postion(req.user_id, req.market1_id, req.market2_id, req.amount)
@@ -777,21 +1135,15 @@ async fn handle_split_order(
             "Split order executed successfully",
             serde_json::json!({ "market1_id": req.market1_id, "market2_id": req.market2_id, "amount": req.amount }),
         )),
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to execute split order: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_merge_order(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: MergeOrderRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: MergeOrderRequest = parse_request(data)?;
 
     match orderbook
         .merge_position(req.user_id, req.market1_id, req.market2_id)
@@ -803,21 +1155,15 @@ async fn handle_merge_order(
             "Merge order executed successfully",
             serde_json::json!({ "market1_id": req.market1_id, "market2_id": req.market2_id }),
         )),
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to execute merge order: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_init_event_markets(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: InitEventMarketsRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: InitEventMarketsRequest = parse_request(data)?;
 
     let metas: Vec<MarketMeta> = req
         .outcomes
@@ -837,21 +1183,15 @@ async fn handle_init_event_markets(
             "Markets initialized successfully",
             serde_json::json!({ "event_id": req.event_id }),
         )),
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to init markets: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
 async fn handle_close_event_markets(
     data: Value,
     orderbook: &Orderbook,
-) -> Result<RedisResponse<Value>, String> {
-    let req: CloseEventMarketsRequest =
-        serde_json::from_value(data).map_err(|e| format!("Invalid request data: {}", e))?;
+) -> Result<RedisResponse<Value>, EngineError> {
+    let req: CloseEventMarketsRequest = parse_request(data)?;
 
     match orderbook
         .close_event_markets(req.event_id, req.winning_outcome_id)
@@ -863,12 +1203,7 @@ async fn handle_close_event_markets(
             "Event markets closed successfully",
             serde_json::json!({ "event_id": req.event_id, "winning_outcome_id": req.winning_outcome_id }),
         )),
-        Err(e) => Ok(RedisResponse::new(
-            400,
-            false,
-            format!("Failed to close event markets: {}", e),
-            serde_json::json!(null),
-        )),
+        Err(e) => Ok(error_response(&e)),
     }
 }
 
@@ -889,3 +1224,139 @@ async fn send_response(request_id: String, response: RedisResponse<Value>) -> Re
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::db_event_publisher::ENGINE_TEST_LOCK;
+    use crate::store::orderbook::test_support::{YES, open_market, spawn_engine};
+
+    const USER: u64 = 1;
+
+    async fn request(orderbook: &Orderbook, action: &str, data: Value) -> RedisResponse<Value> {
+        handle_request(RedisRequest::new("engine", action, "", data), orderbook).await
+    }
+
+    fn place(price: u64, qty: u64) -> Value {
+        json!({
+            "market_id": YES,
+            "user_id": USER,
+            "price": price,
+            "original_qty": qty,
+            "remaining_qty": qty,
+            "side": "Bid",
+            "client_order_id": "bid-1",
+        })
+    }
+
+    #[tokio::test]
+    async fn a_retried_place_returns_the_original_order() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let orderbook = spawn_engine(None);
+        open_market(&orderbook, &[USER]).await;
+
+        let first = request(&orderbook, "place-order", place(40, 10)).await;
+        let retry = request(&orderbook, "place-order", place(40, 10)).await;
+
+        assert_eq!(first.status_code, 200);
+        assert_eq!(retry.status_code, 200);
+        assert_eq!(retry.data, first.data);
+        let open = orderbook.get_user_open_orders(USER).await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(orderbook.get_balance(USER).await.unwrap(), 10_000 - 400);
+    }
+
+    #[tokio::test]
+    async fn a_reused_id_with_a_different_order_is_rejected() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let orderbook = spawn_engine(None);
+        open_market(&orderbook, &[USER]).await;
+
+        let first = request(&orderbook, "place-order", place(40, 10)).await;
+        for changed in [place(41, 10), place(40, 11)] {
+            let response = request(&orderbook, "place-order", changed).await;
+            assert_eq!(response.status_code, 409);
+            assert_eq!(
+                response.error_code.as_deref(),
+                Some("DUPLICATE_CLIENT_ORDER_ID")
+            );
+        }
+
+        // The id stays taken after the order is gone
+        request(
+            &orderbook,
+            "cancel-order",
+            json!({ "market_id": YES, "user_id": USER, "client_order_id": "bid-1" }),
+        )
+        .await;
+        let response = request(&orderbook, "place-order", place(40, 10)).await;
+        assert_eq!(
+            response.error_code.as_deref(),
+            Some("DUPLICATE_CLIENT_ORDER_ID")
+        );
+        assert_eq!(
+            response.message,
+            format!(
+                "client_order_id is already used by order {}",
+                first.data["order_id"]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn a_retried_cancel_returns_the_first_result() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let orderbook = spawn_engine(None);
+        open_market(&orderbook, &[USER]).await;
+
+        let placed = request(&orderbook, "place-order", place(40, 10)).await;
+        let cancel = json!({ "market_id": YES, "user_id": USER, "client_order_id": "bid-1" });
+        let first = request(&orderbook, "cancel-order", cancel.clone()).await;
+        let retry = request(&orderbook, "cancel-order", cancel).await;
+
+        assert_eq!(first.status_code, 200);
+        assert_eq!(retry.status_code, 200);
+        assert_eq!(retry.data["order_id"], placed.data["order_id"]);
+        assert_eq!(retry.data["status"], "cancelled");
+        assert_eq!(orderbook.get_balance(USER).await.unwrap(), 10_000);
+    }
+
+    #[tokio::test]
+    async fn a_retried_modify_returns_the_first_result() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let orderbook = spawn_engine(None);
+        open_market(&orderbook, &[USER]).await;
+
+        request(&orderbook, "place-order", place(40, 10)).await;
+        let modify = json!({ "user_id": USER, "client_order_id": "bid-1", "price": 45 });
+        let first = request(&orderbook, "modify-order", modify.clone()).await;
+        let retry = request(&orderbook, "modify-order", modify).await;
+
+        assert_eq!(first.status_code, 200);
+        assert_eq!(retry.status_code, 200);
+        assert_eq!(retry.data, first.data);
+        assert_eq!(orderbook.get_balance(USER).await.unwrap(), 10_000 - 450);
+    }
+
+    #[tokio::test]
+    async fn a_second_confirm_deposit_credits_nothing() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let orderbook = spawn_engine(None);
+        open_market(&orderbook, &[USER]).await;
+
+        let deposit = json!({
+            "deposit_id": "mock_1",
+            "user_id": USER,
+            "amount": 500,
+            "provider": "mock",
+        });
+        request(&orderbook, "create-deposit", deposit).await;
+        let settle = json!({ "deposit_id": "mock_1" });
+        let first = request(&orderbook, "confirm-deposit", settle.clone()).await;
+        let retry = request(&orderbook, "confirm-deposit", settle).await;
+
+        assert_eq!(first.status_code, 200);
+        assert_eq!(retry.error_code.as_deref(), Some("DEPOSIT_NOT_FOUND"));
+        assert_eq!(orderbook.get_balance(USER).await.unwrap(), 10_000 + 500);
+    }
+}
@@ -1,142 +1,132 @@
-use chrono::Utc;
 use std::collections::HashMap;
 
 use crate::services::db_event_publisher::publish_db_event;
+use crate::store::context::EngineContext;
 use crate::types::db_event_types::{BalanceUpdatedEvent, DbEvent, PositionUpdatedEvent};
+use crate::types::error_types::EngineError;
+use crate::types::ledger_types::{LedgerAccount, LedgerEntry, LedgerKind};
 use crate::types::orderbook_types::{Order, OrderSide};
 use crate::types::user_types::User;
 
-pub async fn reserve_balance(order: &Order, users: &mut HashMap<u64, User>) -> Result<(), String> {
+// Moves what a new order needs from the user's available cash or shares into the locked
+// amounts, where it stays until the order fills or is cancelled.
+pub async fn reserve_balance(
+    order: &Order,
+    users: &mut HashMap<u64, User>,
+    context: &EngineContext,
+) -> Result<(), EngineError> {
+    let Some(user) = users.get_mut(&order.user_id) else {
+        return Err(EngineError::UserNotFound);
+    };
+
     match order.side {
         OrderSide::Bid => {
             let total_cost = (order.original_qty as i64) * (order.price as i64);
-            update_balance(users, order.user_id, -total_cost)
+            if user.balance < total_cost {
+                return Err(EngineError::InsufficientBalance);
+            }
+            user.balance -= total_cost;
+            user.locked_balance += total_cost;
+            let ledger = LedgerEntry::new(LedgerKind::Reservation)
+                .transfer(LedgerAccount::Available, LedgerAccount::Locked, total_cost)
+                .order(order.order_id);
+            publish_balance(user, ledger, context).await;
         }
         OrderSide::Ask => {
-            if !check_position_sufficient(users, order.user_id, order.market_id, order.original_qty)
-            {
-                return Err("Insufficient positions quantity".into());
+            if user.position(order.market_id) < order.original_qty {
+                return Err(EngineError::InsufficientPosition);
             }
-            update_position(
-                users,
-                order.user_id,
+            adjust_position(
+                &mut user.positions,
                 order.market_id,
                 -(order.original_qty as i64),
-            )
+            );
+            adjust_position(
+                &mut user.locked_positions,
+                order.market_id,
+                order.original_qty as i64,
+            );
+            publish_position(user, order.market_id, context).await;
         }
     }
+
+    Ok(())
 }
 
+// Releases whatever is still locked for the unfilled part of an order.
 pub async fn return_reserved_balance(
     order: &Order,
     users: &mut HashMap<u64, User>,
-) -> Result<(), String> {
-    match order.side {
-        OrderSide::Bid => {
-            let reserved = (order.remaining_qty as i64) * (order.price as i64);
-            update_balance(users, order.user_id, reserved)
-        }
-        OrderSide::Ask => update_position(
-            users,
-            order.user_id,
-            order.market_id,
-            order.remaining_qty as i64,
-        ),
-    }
-}
+    context: &EngineContext,
+) -> Result<(), EngineError> {
+    let Some(user) = users.get_mut(&order.user_id) else {
+        return Err(EngineError::UserNotFound);
+    };
 
-pub async fn return_unused_reservation(
-    order: &Order,
-    users: &mut HashMap<u64, User>,
-) -> Result<(), String> {
     match order.side {
         OrderSide::Bid => {
-            let original_reservation = (order.original_qty as i64) * (order.price as i64);
-            let used = ((order.original_qty - order.remaining_qty) as i64) * (order.price as i64);
-            let unused = original_reservation - used;
-            if unused > 0 {
-                update_balance(users, order.user_id, unused)
-            } else {
-                Ok(())
-            }
+            let reserved = ((order.remaining_qty as i64) * (order.price as i64))
+                .min(user.locked_balance.max(0));
+            user.locked_balance -= reserved;
+            user.balance += reserved;
+            let ledger = LedgerEntry::new(LedgerKind::Release)
+                .transfer(LedgerAccount::Locked, LedgerAccount::Available, reserved)
+                .order(order.order_id);
+            publish_balance(user, ledger, context).await;
         }
         OrderSide::Ask => {
-            let unused = order.remaining_qty as i64;
-            if unused > 0 {
-                update_position(users, order.user_id, order.market_id, unused)
-            } else {
-                Ok(())
-            }
+            let reserved = order
+                .remaining_qty
+                .min(user.locked_position(order.market_id));
+            adjust_position(
+                &mut user.locked_positions,
+                order.market_id,
+                -(reserved as i64),
+            );
+            adjust_position(&mut user.positions, order.market_id, reserved as i64);
+            publish_position(user, order.market_id, context).await;
         }
     }
-}
-
-fn update_balance(users: &mut HashMap<u64, User>, user_id: u64, amount: i64) -> Result<(), String> {
-    let Some(user) = users.get_mut(&user_id) else {
-        return Err("User not found".into());
-    };
-    user.balance += amount;
-
-    let balance = user.balance;
-    let timestamp = Utc::now();
-    tokio::spawn(async move {
-        let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-            user_id,
-            balance,
-            timestamp,
-        }))
-        .await;
-    });
 
     Ok(())
 }
 
-fn update_position(
-    users: &mut HashMap<u64, User>,
-    user_id: u64,
-    market_id: u64,
-    amount: i64,
-) -> Result<(), String> {
-    let Some(user) = users.get_mut(&user_id) else {
-        return Err("User not found".into());
-    };
-    let current = user.positions.entry(market_id).or_insert(0);
-    if amount < 0 && (*current as i64) < -amount {
-        return Err("Insufficient position".into());
-    }
-    *current = ((*current as i64) + amount) as u64;
-    let final_qty = if *current == 0 {
-        user.positions.remove(&market_id);
-        0
-    } else {
-        *current
-    };
-
-    let quantity = final_qty;
-    let timestamp = Utc::now();
-    tokio::spawn(async move {
-        let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-            user_id,
-            market_id,
-            quantity,
-            timestamp,
-        }))
-        .await;
-    });
+// Every change to a user's cash goes out with the ledger entry that explains it.
+pub async fn publish_balance(user: &mut User, ledger: LedgerEntry, context: &EngineContext) {
+    let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
+        user_id: user.id,
+        balance: user.balance,
+        locked_balance: user.locked_balance,
+        ledger: Some(ledger),
+        sequence: context.next_sequence(),
+        timestamp: context.now(),
+    }))
+    .await;
+}
 
-    Ok(())
+pub async fn publish_position(user: &mut User, market_id: u64, context: &EngineContext) {
+    let cost = user.cost(market_id);
+    let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
+        user_id: user.id,
+        market_id,
+        quantity: user.position(market_id),
+        locked_quantity: user.locked_position(market_id),
+        cost_basis: cost.cost_basis,
+        realized_pnl: cost.realized_pnl,
+        sequence: context.next_sequence(),
+        timestamp: context.now(),
+    }))
+    .await;
 }
 
-fn check_position_sufficient(
-    users: &HashMap<u64, User>,
-    user_id: u64,
-    market_id: u64,
-    required_qty: u64,
-) -> bool {
-    users
-        .get(&user_id)
-        .and_then(|u| u.positions.get(&market_id))
-        .copied()
-        .unwrap_or(0)
-        >= required_qty
+/// Adds `amount` (which may be negative) to a position map, never going below zero and
+/// dropping entries that reach zero.
+pub fn adjust_position(positions: &mut HashMap<u64, u64>, market_id: u64, amount: i64) {
+    let current = positions.get(&market_id).copied().unwrap_or(0) as i64;
+    let next = (current + amount).max(0) as u64;
+    if next == 0 {
+        positions.remove(&market_id);
+    } else {
+        positions.insert(market_id, next);
+    }
 }
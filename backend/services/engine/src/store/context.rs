@@ -0,0 +1,151 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> u64;
+
+    /// Makes sure ids handed out later are greater than one that already exists.
+    fn observe(&self, id: u64);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when it is set, so a replay stamps every event the same way each run.
+#[derive(Default)]
+pub struct ManualClock {
+    millis: AtomicI64,
+}
+
+impl ManualClock {
+    pub fn set_millis(&self, millis: i64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.millis.load(Ordering::SeqCst))
+            .single()
+            .unwrap_or_default()
+    }
+}
+
+/// Strictly increasing ids seeded from the wall clock in microseconds, so they keep
+/// increasing across restarts and still fit in a signed BIGINT column.
+#[derive(Default)]
+pub struct MonotonicIdGenerator {
+    last: AtomicU64,
+}
+
+impl IdGenerator for MonotonicIdGenerator {
+    fn next_id(&self) -> u64 {
+        let now = Utc::now().timestamp_micros().max(0) as u64;
+        let mut last = self.last.load(Ordering::SeqCst);
+        loop {
+            let next = now.max(last + 1);
+            match self
+                .last
+                .compare_exchange(last, next, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return next,
+                Err(current) => last = current,
+            }
+        }
+    }
+
+    fn observe(&self, id: u64) {
+        self.last.fetch_max(id, Ordering::SeqCst);
+    }
+}
+
+pub struct SequentialIdGenerator {
+    next: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    pub fn new(start: u64) -> Self {
+        Self {
+            next: AtomicU64::new(start),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::SeqCst)
+    }
+
+    fn observe(&self, id: u64) {
+        self.next.fetch_max(id.saturating_add(1), Ordering::SeqCst);
+    }
+}
+
+/// Source of time and ids for the engine. Everything the actor stamps or numbers goes
+/// through here so a run can be reproduced exactly.
+#[derive(Clone)]
+pub struct EngineContext {
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    /// Last sequence stamped on a DB event. One counter covers every event, so db_worker can
+    /// tell a redelivered event from a new one.
+    sequence: Arc<AtomicU64>,
+}
+
+impl EngineContext {
+    pub fn new(clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            clock,
+            ids,
+            sequence: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn system() -> Self {
+        Self::new(
+            Arc::new(SystemClock),
+            Arc::new(MonotonicIdGenerator::default()),
+        )
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub fn next_id(&self) -> u64 {
+        self.ids.next_id()
+    }
+
+    pub fn observe_id(&self, id: u64) {
+        self.ids.observe(id);
+    }
+
+    pub fn next_sequence(&self) -> u64 {
+        self.sequence.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn last_sequence(&self) -> u64 {
+        self.sequence.load(Ordering::SeqCst)
+    }
+
+    /// Makes sure sequences handed out later are greater than one that was already used.
+    pub fn observe_sequence(&self, sequence: u64) {
+        self.sequence.fetch_max(sequence, Ordering::SeqCst);
+    }
+}
+
+impl Default for EngineContext {
+    fn default() -> Self {
+        Self::system()
+    }
+}
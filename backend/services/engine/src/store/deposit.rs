@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::services::db_event_publisher::publish_db_event;
+use crate::store::balance::publish_balance;
+use crate::store::context::EngineContext;
+use crate::types::db_event_types::{DbEvent, DepositUpdatedEvent};
+use crate::types::deposit_types::{Deposit, DepositStatus};
+use crate::types::error_types::EngineError;
+use crate::types::ledger_types::{LedgerAccount, LedgerEntry, LedgerKind};
+use crate::types::user_types::User;
+
+// Records the intent only; nothing is credited until the provider confirms it.
+pub async fn create_deposit(
+    mut deposit: Deposit,
+    users: &mut HashMap<u64, User>,
+    owners: &mut HashMap<String, u64>,
+    context: &EngineContext,
+) -> Result<Deposit, EngineError> {
+    let Some(user) = users.get_mut(&deposit.user_id) else {
+        return Err(EngineError::UserNotFound);
+    };
+    if deposit.amount <= 0 {
+        return Err(EngineError::InvalidRequest(
+            "Amount must be greater than 0".into(),
+        ));
+    }
+    if let Some(existing) = user.deposits.get(&deposit.deposit_id) {
+        return Ok(existing.clone());
+    }
+
+    deposit.status = DepositStatus::Pending;
+    user.deposits
+        .insert(deposit.deposit_id.clone(), deposit.clone());
+    owners.insert(deposit.deposit_id.clone(), user.id);
+    publish_deposit(&deposit, context).await;
+
+    Ok(deposit)
+}
+
+// Only pending deposits are kept, indexed by id in `owners`; settling drops the deposit, so
+// a retried webhook for it gets `DepositNotFound`.
+pub async fn settle_deposit(
+    deposit_id: String,
+    confirmed: bool,
+    users: &mut HashMap<u64, User>,
+    owners: &mut HashMap<String, u64>,
+    context: &EngineContext,
+) -> Result<Deposit, EngineError> {
+    let Some(user) = owners
+        .remove(&deposit_id)
+        .and_then(|user_id| users.get_mut(&user_id))
+    else {
+        return Err(EngineError::DepositNotFound);
+    };
+    let Some(mut deposit) = user.deposits.remove(&deposit_id) else {
+        return Err(EngineError::DepositNotFound);
+    };
+    // Snapshots taken before settled deposits were dropped can still hold some
+    if deposit.status != DepositStatus::Pending {
+        return Ok(deposit);
+    }
+
+    if confirmed {
+        deposit.status = DepositStatus::Confirmed;
+        user.balance += deposit.amount;
+        let ledger = LedgerEntry::new(LedgerKind::Deposit)
+            .transfer(
+                LedgerAccount::External,
+                LedgerAccount::Available,
+                deposit.amount,
+            )
+            .deposit(&deposit.deposit_id);
+        publish_balance(user, ledger, context).await;
+    } else {
+        deposit.status = DepositStatus::Failed;
+    }
+    publish_deposit(&deposit, context).await;
+
+    Ok(deposit)
+}
+
+/// Pending deposit ids of `user`, paired with the user's id.
+pub fn pending_deposit_owners(user: &User) -> impl Iterator<Item = (String, u64)> + '_ {
+    user.deposits.keys().map(|id| (id.clone(), user.id))
+}
+
+async fn publish_deposit(deposit: &Deposit, context: &EngineContext) {
+    let _ = publish_db_event(DbEvent::DepositUpdated(DepositUpdatedEvent {
+        deposit_id: deposit.deposit_id.clone(),
+        user_id: deposit.user_id,
+        amount: deposit.amount,
+        provider: deposit.provider.clone(),
+        status: deposit.status,
+        sequence: context.next_sequence(),
+        timestamp: context.now(),
+    }))
+    .await;
+}
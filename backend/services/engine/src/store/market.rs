@@ -83,6 +83,44 @@ impl MarketStore {
         Ok(())
     }
 
+    pub fn restore_markets(&self, markets: Vec<Market>) -> Result<(), String> {
+        let mut guard = self
+            .inner
+            .write()
+            .map_err(|_| "failed to restore markets".to_string())?;
+        let mut event_guard = self
+            .event_markets
+            .write()
+            .map_err(|_| "failed to restore event markets".to_string())?;
+        let mut outcome_guard = self
+            .outcome_markets
+            .write()
+            .map_err(|_| "failed to restore outcome markets".to_string())?;
+
+        for market in markets {
+            if let Some(event_id) = market.event_id {
+                event_guard
+                    .entry(event_id)
+                    .or_insert_with(Vec::new)
+                    .push(market.market_id);
+            }
+            if let Some(outcome_id) = market.outcome_id {
+                outcome_guard
+                    .entry(outcome_id)
+                    .or_insert_with(Vec::new)
+                    .push(market.market_id);
+            }
+            guard.insert(market.market_id, market);
+        }
+
+        for markets in event_guard.values_mut().chain(outcome_guard.values_mut()) {
+            markets.sort_unstable();
+            markets.dedup();
+        }
+
+        Ok(())
+    }
+
     pub fn get_market(&self, market_id: u64) -> Option<Market> {
         self.inner.read().ok()?.get(&market_id).cloned()
     }
@@ -1,33 +1,38 @@
-use chrono::Utc;
 use std::collections::HashMap;
-use uuid::Uuid;
 
 use crate::services::db_event_publisher::publish_db_event;
+use crate::store::balance::{adjust_position, publish_balance, publish_position};
+use crate::store::context::EngineContext;
 use crate::store::market::MarketStore;
-use crate::types::db_event_types::{
-    BalanceUpdatedEvent, DbEvent, OrderFilledEvent, PositionUpdatedEvent, TradeExecutedEvent,
-};
+use crate::store::order_feed::record_order_change;
+use crate::store::orderbook::market_data::publish_trade_print;
+use crate::types::db_event_types::{DbEvent, OrderFilledEvent, TradeExecutedEvent};
+use crate::types::error_types::EngineError;
+use crate::types::ledger_types::{LedgerAccount, LedgerEntry, LedgerKind};
+use crate::types::market_data_types::TradePrint;
 use crate::types::market_types::MarketStatus;
-use crate::types::orderbook_types::{Order, OrderSide, OrderbookData};
+use crate::types::orderbook_types::{Order, OrderFeedKind, OrderSide, OrderbookData};
 use crate::types::user_types::User;
 
+/// Matches `order` against the book and returns the resting orders it filled completely.
 pub async fn match_order(
     order: &mut Order,
     book: &mut OrderbookData,
     users: &mut HashMap<u64, User>,
     market_store: &MarketStore,
-) -> Result<(), String> {
+    context: &EngineContext,
+) -> Result<Vec<Order>, EngineError> {
     let Some(market) = market_store.get_market(order.market_id) else {
-        return Err("Market not found".into());
+        return Err(EngineError::MarketNotFound);
     };
 
     if market.status != MarketStatus::Active {
-        return Err("Market not active to trade".into());
+        return Err(EngineError::MarketInactive);
     };
 
     match order.side {
-        OrderSide::Bid => match_bid_against_asks(order, book, users).await,
-        OrderSide::Ask => match_ask_against_bids(order, book, users).await,
+        OrderSide::Bid => match_bid_against_asks(order, book, users, market_store, context).await,
+        OrderSide::Ask => match_ask_against_bids(order, book, users, market_store, context).await,
     }
 }
 
@@ -35,28 +40,43 @@ async fn match_bid_against_asks(
     order: &mut Order,
     book: &mut OrderbookData,
     users: &mut HashMap<u64, User>,
-) -> Result<(), String> {
+    market_store: &MarketStore,
+    context: &EngineContext,
+) -> Result<Vec<Order>, EngineError> {
+    let mut filled_makers = Vec::new();
     while order.remaining_qty > 0 {
-        let Some((&ask_price, _)) = book.asks.first_key_value() else {
-            break;
+        let displayed = book.asks.first_key_value().map(|(&price, _)| price);
+        let hidden = book.hidden_asks.first_key_value().map(|(&price, _)| price);
+        let (ask_price, is_hidden) = match (displayed, hidden) {
+            (Some(d), Some(h)) if h < d => (h, true),
+            (Some(d), _) => (d, false),
+            (None, Some(h)) => (h, true),
+            (None, None) => break,
         };
 
-        if matches!(
-            order.order_type,
-            crate::types::orderbook_types::OrderType::Limit
-        ) && order.price < ask_price
-        {
+        // Market bids reserved cash at the ask they were quoted, so they stop there too
+        // rather than paying more than was locked
+        if order.price < ask_price {
             break;
         }
 
-        let Some(order_ids) = book.ask_queue.get_mut(&ask_price) else {
-            book.asks.remove(&ask_price);
+        let (queue_map, levels) = if is_hidden {
+            (&mut book.hidden_ask_queue, &mut book.hidden_asks)
+        } else {
+            (&mut book.ask_queue, &mut book.asks)
+        };
+
+        let Some(order_ids) = queue_map.get_mut(&ask_price) else {
+            levels.remove(&ask_price);
+            if !is_hidden {
+                book.depth.record(OrderSide::Ask, ask_price, 0);
+            }
             continue;
         };
 
-        while let Some(&maker_order_id) = order_ids.first() {
+        while let Some(maker_order_id) = order_ids.front() {
             let Some(maker_order) = book.orders.get_mut(&maker_order_id) else {
-                order_ids.remove(0);
+                order_ids.pop_front();
                 continue;
             };
 
@@ -68,34 +88,27 @@ async fn match_bid_against_asks(
             order.remaining_qty -= fill_qty;
             maker_order.remaining_qty -= fill_qty;
 
-            *book.asks.get_mut(&ask_price).unwrap() -= fill_qty;
-
-            let price_diff = match order.order_type {
-                crate::types::orderbook_types::OrderType::Market => 0,
-                crate::types::orderbook_types::OrderType::Limit => {
-                    (order.price as i64) - (fill_price as i64)
-                }
-            };
-            let refund = price_diff * (fill_qty as i64);
-            update_balance(users, order.user_id, refund)?;
-
-            update_position(users, order.user_id, order.market_id, fill_qty as i64)?;
-
-            let maker_revenue = (fill_price as i64) * (fill_qty as i64);
-            update_balance(users, maker_order.user_id, maker_revenue)?;
+            let level_qty = levels.get_mut(&ask_price).unwrap();
+            *level_qty -= fill_qty;
+            if !is_hidden {
+                book.depth.record(OrderSide::Ask, ask_price, *level_qty);
+            }
+            record_order_change(
+                &mut book.order_feed,
+                OrderFeedKind::Execute,
+                maker_order,
+                Some(fill_qty),
+            );
 
-            update_position(
-                users,
-                maker_order.user_id,
-                maker_order.market_id,
-                -(fill_qty as i64),
-            )?;
+            let trade_id = context.next_id().to_string();
+            let taker_ledger = settle_buy(users, order, fill_price, fill_qty, &trade_id)?;
+            let maker_ledger = settle_sell(users, maker_order, fill_price, fill_qty, &trade_id)?;
 
-            let trade_id = Uuid::new_v4().to_string();
-            let timestamp = Utc::now();
+            let timestamp = context.now();
 
             let taker_order_id = order.order_id.unwrap_or(0);
             let maker_order_id = maker_order.order_id.unwrap_or(0);
+            book.sequence += 1;
             let _ = publish_db_event(DbEvent::TradeExecuted(TradeExecutedEvent {
                 trade_id: trade_id.clone(),
                 market_id: order.market_id,
@@ -106,9 +119,26 @@ async fn match_bid_against_asks(
                 price: fill_price,
                 quantity: fill_qty,
                 taker_side: "Bid".to_string(),
+                sequence: context.next_sequence(),
+                market_sequence: book.sequence,
                 timestamp,
             }))
             .await;
+            publish_trade_print(
+                TradePrint {
+                    trade_id: trade_id.clone(),
+                    market_id: order.market_id,
+                    outcome_id: None,
+                    event_id: None,
+                    price: fill_price,
+                    quantity: fill_qty,
+                    taker_side: "Bid".to_string(),
+                    sequence: book.sequence,
+                    timestamp,
+                },
+                market_store,
+            )
+            .await;
 
             let taker_status = if order.remaining_qty == 0 {
                 "filled"
@@ -116,6 +146,7 @@ async fn match_bid_against_asks(
                 "partially_filled"
             };
             let taker_filled_qty = order.original_qty - order.remaining_qty;
+            book.sequence += 1;
             let _ = publish_db_event(DbEvent::OrderFilled(OrderFilledEvent {
                 order_id: taker_order_id,
                 user_id: order.user_id,
@@ -123,6 +154,8 @@ async fn match_bid_against_asks(
                 filled_qty: taker_filled_qty,
                 remaining_qty: order.remaining_qty,
                 status: taker_status.to_string(),
+                sequence: context.next_sequence(),
+                market_sequence: book.sequence,
                 timestamp,
             }))
             .await;
@@ -133,6 +166,7 @@ async fn match_bid_against_asks(
                 "partially_filled"
             };
             let maker_filled_qty = maker_order.original_qty - maker_order.remaining_qty;
+            book.sequence += 1;
             let _ = publish_db_event(DbEvent::OrderFilled(OrderFilledEvent {
                 order_id: maker_order_id,
                 user_id: maker_order.user_id,
@@ -140,62 +174,29 @@ async fn match_bid_against_asks(
                 filled_qty: maker_filled_qty,
                 remaining_qty: maker_order.remaining_qty,
                 status: maker_status.to_string(),
+                sequence: context.next_sequence(),
+                market_sequence: book.sequence,
                 timestamp,
             }))
             .await;
 
-            let taker_balance = users.get(&order.user_id).map(|u| u.balance).unwrap_or(0);
-            let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                user_id: order.user_id,
-                balance: taker_balance,
-                timestamp,
-            }))
-            .await;
-
-            let maker_balance = users
-                .get(&maker_order.user_id)
-                .map(|u| u.balance)
-                .unwrap_or(0);
-            let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                user_id: maker_order.user_id,
-                balance: maker_balance,
-                timestamp,
-            }))
-            .await;
-
-            let taker_position = users
-                .get(&order.user_id)
-                .and_then(|u| u.positions.get(&order.market_id))
-                .copied()
-                .unwrap_or(0);
-            let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                user_id: order.user_id,
-                market_id: order.market_id,
-                quantity: taker_position,
-                timestamp,
-            }))
-            .await;
-
-            let maker_position = users
-                .get(&maker_order.user_id)
-                .and_then(|u| u.positions.get(&maker_order.market_id))
-                .copied()
-                .unwrap_or(0);
-            let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                user_id: maker_order.user_id,
-                market_id: maker_order.market_id,
-                quantity: maker_position,
-                timestamp,
-            }))
-            .await;
+            for (user_id, market_id, ledger) in [
+                (order.user_id, order.market_id, taker_ledger),
+                (maker_order.user_id, maker_order.market_id, maker_ledger),
+            ] {
+                if let Some(user) = users.get_mut(&user_id) {
+                    publish_balance(user, ledger, context).await;
+                    publish_position(user, market_id, context).await;
+                }
+            }
 
             if maker_order.remaining_qty == 0 {
-                book.orders.remove(&maker_order_id);
-                order_ids.remove(0);
+                filled_makers.extend(book.orders.remove(&maker_order_id));
+                order_ids.pop_front();
 
                 if order_ids.is_empty() {
-                    book.ask_queue.remove(&ask_price);
-                    book.asks.remove(&ask_price);
+                    queue_map.remove(&ask_price);
+                    levels.remove(&ask_price);
                     break;
                 }
             } else {
@@ -206,17 +207,25 @@ async fn match_bid_against_asks(
             }
         }
     }
-    Ok(())
+    Ok(filled_makers)
 }
 
 async fn match_ask_against_bids(
     order: &mut Order,
     book: &mut OrderbookData,
     users: &mut HashMap<u64, User>,
-) -> Result<(), String> {
+    market_store: &MarketStore,
+    context: &EngineContext,
+) -> Result<Vec<Order>, EngineError> {
+    let mut filled_makers = Vec::new();
     while order.remaining_qty > 0 {
-        let Some((&bid_price, _)) = book.bids.last_key_value() else {
-            break;
+        let displayed = book.bids.last_key_value().map(|(&price, _)| price);
+        let hidden = book.hidden_bids.last_key_value().map(|(&price, _)| price);
+        let (bid_price, is_hidden) = match (displayed, hidden) {
+            (Some(d), Some(h)) if h > d => (h, true),
+            (Some(d), _) => (d, false),
+            (None, Some(h)) => (h, true),
+            (None, None) => break,
         };
 
         if matches!(
@@ -227,14 +236,23 @@ async fn match_ask_against_bids(
             break;
         }
 
-        let Some(order_ids) = book.bid_queue.get_mut(&bid_price) else {
-            book.bids.remove(&bid_price);
+        let (queue_map, levels) = if is_hidden {
+            (&mut book.hidden_bid_queue, &mut book.hidden_bids)
+        } else {
+            (&mut book.bid_queue, &mut book.bids)
+        };
+
+        let Some(order_ids) = queue_map.get_mut(&bid_price) else {
+            levels.remove(&bid_price);
+            if !is_hidden {
+                book.depth.record(OrderSide::Bid, bid_price, 0);
+            }
             continue;
         };
 
-        while let Some(&maker_order_id) = order_ids.first() {
+        while let Some(maker_order_id) = order_ids.front() {
             let Some(maker_order) = book.orders.get_mut(&maker_order_id) else {
-                order_ids.remove(0);
+                order_ids.pop_front();
                 continue;
             };
 
@@ -246,34 +264,27 @@ async fn match_ask_against_bids(
             order.remaining_qty -= fill_qty;
             maker_order.remaining_qty -= fill_qty;
 
-            *book.bids.get_mut(&bid_price).unwrap() -= fill_qty;
-
-            let payment = match order.order_type {
-                crate::types::orderbook_types::OrderType::Market => {
-                    (fill_price as i64) * (fill_qty as i64)
-                }
-                crate::types::orderbook_types::OrderType::Limit => 0,
-            };
-            update_balance(users, order.user_id, payment)?;
-
-            update_position(users, order.user_id, order.market_id, -(fill_qty as i64))?;
-
-            let maker_price_diff = (maker_order.price as i64) - (fill_price as i64);
-            let maker_refund = maker_price_diff * (fill_qty as i64);
-            update_balance(users, maker_order.user_id, maker_refund)?;
+            let level_qty = levels.get_mut(&bid_price).unwrap();
+            *level_qty -= fill_qty;
+            if !is_hidden {
+                book.depth.record(OrderSide::Bid, bid_price, *level_qty);
+            }
+            record_order_change(
+                &mut book.order_feed,
+                OrderFeedKind::Execute,
+                maker_order,
+                Some(fill_qty),
+            );
 
-            update_position(
-                users,
-                maker_order.user_id,
-                maker_order.market_id,
-                fill_qty as i64,
-            )?;
+            let trade_id = context.next_id().to_string();
+            let taker_ledger = settle_sell(users, order, fill_price, fill_qty, &trade_id)?;
+            let maker_ledger = settle_buy(users, maker_order, fill_price, fill_qty, &trade_id)?;
 
-            let trade_id = Uuid::new_v4().to_string();
-            let timestamp = Utc::now();
+            let timestamp = context.now();
 
             let taker_order_id = order.order_id.unwrap_or(0);
             let maker_order_id = maker_order.order_id.unwrap_or(0);
+            book.sequence += 1;
             let _ = publish_db_event(DbEvent::TradeExecuted(TradeExecutedEvent {
                 trade_id: trade_id.clone(),
                 market_id: order.market_id,
@@ -284,9 +295,26 @@ async fn match_ask_against_bids(
                 price: fill_price,
                 quantity: fill_qty,
                 taker_side: "Ask".to_string(),
+                sequence: context.next_sequence(),
+                market_sequence: book.sequence,
                 timestamp,
             }))
             .await;
+            publish_trade_print(
+                TradePrint {
+                    trade_id: trade_id.clone(),
+                    market_id: order.market_id,
+                    outcome_id: None,
+                    event_id: None,
+                    price: fill_price,
+                    quantity: fill_qty,
+                    taker_side: "Ask".to_string(),
+                    sequence: book.sequence,
+                    timestamp,
+                },
+                market_store,
+            )
+            .await;
 
             let taker_status = if order.remaining_qty == 0 {
                 "filled"
@@ -294,6 +322,7 @@ async fn match_ask_against_bids(
                 "partially_filled"
             };
             let taker_filled_qty = order.original_qty - order.remaining_qty;
+            book.sequence += 1;
             let _ = publish_db_event(DbEvent::OrderFilled(OrderFilledEvent {
                 order_id: taker_order_id,
                 user_id: order.user_id,
@@ -301,6 +330,8 @@ async fn match_ask_against_bids(
                 filled_qty: taker_filled_qty,
                 remaining_qty: order.remaining_qty,
                 status: taker_status.to_string(),
+                sequence: context.next_sequence(),
+                market_sequence: book.sequence,
                 timestamp,
             }))
             .await;
@@ -311,6 +342,7 @@ async fn match_ask_against_bids(
                 "partially_filled"
             };
             let maker_filled_qty = maker_order.original_qty - maker_order.remaining_qty;
+            book.sequence += 1;
             let _ = publish_db_event(DbEvent::OrderFilled(OrderFilledEvent {
                 order_id: maker_order_id,
                 user_id: maker_order.user_id,
@@ -318,62 +350,29 @@ async fn match_ask_against_bids(
                 filled_qty: maker_filled_qty,
                 remaining_qty: maker_order.remaining_qty,
                 status: maker_status.to_string(),
+                sequence: context.next_sequence(),
+                market_sequence: book.sequence,
                 timestamp,
             }))
             .await;
 
-            let taker_balance = users.get(&order.user_id).map(|u| u.balance).unwrap_or(0);
-            let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                user_id: order.user_id,
-                balance: taker_balance,
-                timestamp,
-            }))
-            .await;
-
-            let maker_balance = users
-                .get(&maker_order.user_id)
-                .map(|u| u.balance)
-                .unwrap_or(0);
-            let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                user_id: maker_order.user_id,
-                balance: maker_balance,
-                timestamp,
-            }))
-            .await;
-
-            let taker_position = users
-                .get(&order.user_id)
-                .and_then(|u| u.positions.get(&order.market_id))
-                .copied()
-                .unwrap_or(0);
-            let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                user_id: order.user_id,
-                market_id: order.market_id,
-                quantity: taker_position,
-                timestamp,
-            }))
-            .await;
-
-            let maker_position = users
-                .get(&maker_order.user_id)
-                .and_then(|u| u.positions.get(&maker_order.market_id))
-                .copied()
-                .unwrap_or(0);
-            let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                user_id: maker_order.user_id,
-                market_id: maker_order.market_id,
-                quantity: maker_position,
-                timestamp,
-            }))
-            .await;
+            for (user_id, market_id, ledger) in [
+                (order.user_id, order.market_id, taker_ledger),
+                (maker_order.user_id, maker_order.market_id, maker_ledger),
+            ] {
+                if let Some(user) = users.get_mut(&user_id) {
+                    publish_balance(user, ledger, context).await;
+                    publish_position(user, market_id, context).await;
+                }
+            }
 
             if maker_order.remaining_qty == 0 {
-                book.orders.remove(&maker_order_id);
-                order_ids.remove(0);
+                filled_makers.extend(book.orders.remove(&maker_order_id));
+                order_ids.pop_front();
 
                 if order_ids.is_empty() {
-                    book.bid_queue.remove(&bid_price);
-                    book.bids.remove(&bid_price);
+                    queue_map.remove(&bid_price);
+                    levels.remove(&bid_price);
                     break;
                 }
             } else {
@@ -385,33 +384,262 @@ async fn match_ask_against_bids(
             }
         }
     }
-    Ok(())
+    Ok(filled_makers)
 }
 
-fn update_balance(users: &mut HashMap<u64, User>, user_id: u64, amount: i64) -> Result<(), String> {
-    let Some(user) = users.get_mut(&user_id) else {
-        return Err("User not found".into());
+// The buyer's cash was locked at its limit price; the fill uses that reservation and hands
+// back any price improvement.
+fn settle_buy(
+    users: &mut HashMap<u64, User>,
+    buyer: &Order,
+    fill_price: u64,
+    fill_qty: u64,
+    trade_id: &str,
+) -> Result<LedgerEntry, EngineError> {
+    let Some(user) = users.get_mut(&buyer.user_id) else {
+        return Err(EngineError::UserNotFound);
     };
-    user.balance += amount;
-    Ok(())
+    let reserved = (buyer.price as i64) * (fill_qty as i64);
+    let cost = (fill_price as i64) * (fill_qty as i64);
+    user.locked_balance -= reserved;
+    user.balance += reserved - cost;
+    user.record_buy(buyer.market_id, cost);
+    adjust_position(&mut user.positions, buyer.market_id, fill_qty as i64);
+    Ok(LedgerEntry::new(LedgerKind::Trade)
+        .transfer(LedgerAccount::Locked, LedgerAccount::Trading, cost)
+        .transfer(
+            LedgerAccount::Locked,
+            LedgerAccount::Available,
+            reserved - cost,
+        )
+        .order(buyer.order_id)
+        .trade(trade_id))
 }
 
-fn update_position(
+fn settle_sell(
     users: &mut HashMap<u64, User>,
-    user_id: u64,
-    market_id: u64,
-    amount: i64,
-) -> Result<(), String> {
-    let Some(user) = users.get_mut(&user_id) else {
-        return Err("User not found".into());
+    seller: &Order,
+    fill_price: u64,
+    fill_qty: u64,
+    trade_id: &str,
+) -> Result<LedgerEntry, EngineError> {
+    let Some(user) = users.get_mut(&seller.user_id) else {
+        return Err(EngineError::UserNotFound);
     };
-    let current = user.positions.entry(market_id).or_insert(0);
-    if amount < 0 && (*current as i64) < -amount {
-        return Err("Insufficient position".into());
+    let proceeds = (fill_price as i64) * (fill_qty as i64);
+    user.record_sell(seller.market_id, fill_qty, proceeds);
+    adjust_position(
+        &mut user.locked_positions,
+        seller.market_id,
+        -(fill_qty as i64),
+    );
+    user.balance += proceeds;
+    Ok(LedgerEntry::new(LedgerKind::Trade)
+        .transfer(LedgerAccount::Trading, LedgerAccount::Available, proceeds)
+        .order(seller.order_id)
+        .trade(trade_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::db_event_publisher::ENGINE_TEST_LOCK;
+    use crate::store::orderbook::Orderbook;
+    use crate::store::orderbook::test_support::{NO, YES, limit, open_market, spawn_engine};
+    use crate::store::orderbook_actions::add_order_to_book;
+    use crate::types::market_types::MarketMeta;
+    use crate::types::orderbook_types::OrderType;
+
+    const MARKET: u64 = 100;
+
+    fn market_store() -> MarketStore {
+        let store = MarketStore::new();
+        store
+            .register_market_pair(MarketMeta {
+                event_id: 1,
+                outcome_id: 10,
+                yes_market_id: MARKET,
+                no_market_id: 101,
+            })
+            .unwrap();
+        store
+    }
+
+    fn users(ids: &[u64]) -> HashMap<u64, User> {
+        ids.iter()
+            .map(|&id| {
+                let user = User {
+                    id,
+                    name: format!("user{}", id),
+                    email: format!("user{}@example.com", id),
+                    balance: 10_000,
+                    positions: HashMap::new(),
+                    locked_balance: 0,
+                    locked_positions: HashMap::from([(MARKET, 100)]),
+                    costs: HashMap::new(),
+                    pending_withdrawals: HashMap::new(),
+                    deposits: HashMap::new(),
+                };
+                (id, user)
+            })
+            .collect()
+    }
+
+    fn order(order_id: u64, user_id: u64, side: OrderSide, price: u64, qty: u64) -> Order {
+        Order {
+            order_id: Some(order_id),
+            market_id: MARKET,
+            user_id,
+            price,
+            original_qty: qty,
+            remaining_qty: qty,
+            side,
+            order_type: OrderType::Limit,
+            hidden: false,
+            client_order_id: None,
+        }
     }
-    *current = ((*current as i64) + amount) as u64;
-    if *current == 0 {
-        user.positions.remove(&market_id);
+
+    fn hidden(order: Order) -> Order {
+        Order {
+            hidden: true,
+            ..order
+        }
+    }
+
+    fn rest(book: &mut OrderbookData, order: Order) {
+        add_order_to_book(order.order_id.unwrap(), &order, book);
+    }
+
+    async fn take(
+        book: &mut OrderbookData,
+        users: &mut HashMap<u64, User>,
+        mut taker: Order,
+    ) -> (Order, Vec<u64>) {
+        let store = market_store();
+        let context = EngineContext::default();
+        let filled = match_order(&mut taker, book, users, &store, &context)
+            .await
+            .unwrap();
+        let filled = filled.iter().filter_map(|o| o.order_id).collect();
+        (taker, filled)
+    }
+
+    #[tokio::test]
+    async fn displayed_asks_fill_before_hidden_ones_at_the_same_price() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let mut book = OrderbookData::new(MARKET);
+        let mut users = users(&[1, 2, 3]);
+        // The hidden order is older, but displayed size keeps priority at a price
+        rest(&mut book, hidden(order(1, 2, OrderSide::Ask, 50, 5)));
+        rest(&mut book, order(2, 3, OrderSide::Ask, 50, 5));
+
+        let (taker, filled) = take(&mut book, &mut users, order(3, 1, OrderSide::Bid, 50, 7)).await;
+
+        assert_eq!(taker.remaining_qty, 0);
+        assert_eq!(filled, vec![2]);
+        assert_eq!(book.orders[&1].remaining_qty, 3);
+        assert!(book.asks.is_empty());
+        assert_eq!(book.hidden_asks.get(&50), Some(&3));
+    }
+
+    #[tokio::test]
+    async fn displayed_bids_fill_before_hidden_ones_at_the_same_price() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let mut book = OrderbookData::new(MARKET);
+        let mut users = users(&[1, 2, 3]);
+        rest(&mut book, hidden(order(1, 2, OrderSide::Bid, 40, 5)));
+        rest(&mut book, order(2, 3, OrderSide::Bid, 40, 5));
+
+        let (taker, filled) = take(&mut book, &mut users, order(3, 1, OrderSide::Ask, 40, 7)).await;
+
+        assert_eq!(taker.remaining_qty, 0);
+        assert_eq!(filled, vec![2]);
+        assert_eq!(book.orders[&1].remaining_qty, 3);
+        assert!(book.bids.is_empty());
+        assert_eq!(book.hidden_bids.get(&40), Some(&3));
+    }
+
+    #[tokio::test]
+    async fn a_better_hidden_price_still_fills_first() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let mut book = OrderbookData::new(MARKET);
+        let mut users = users(&[1, 2, 3]);
+        rest(&mut book, order(1, 2, OrderSide::Ask, 50, 5));
+        rest(&mut book, hidden(order(2, 3, OrderSide::Ask, 49, 5)));
+
+        let (taker, filled) = take(&mut book, &mut users, order(3, 1, OrderSide::Bid, 50, 6)).await;
+
+        assert_eq!(taker.remaining_qty, 0);
+        assert_eq!(filled, vec![2]);
+        assert_eq!(book.orders[&1].remaining_qty, 4);
+        assert!(book.hidden_asks.is_empty());
+        assert_eq!(book.last_price, Some(50));
+    }
+
+    // Cash and shares held by the users, counting what their orders have locked
+    async fn holdings(orderbook: &Orderbook, user_ids: &[u64]) -> (i64, u64, u64) {
+        let mut totals = (0, 0, 0);
+        for &id in user_ids {
+            let user = orderbook.get_user_by_id(id).await.unwrap();
+            totals.0 += user.balance + user.locked_balance;
+            totals.1 += user.position(YES) + user.locked_position(YES);
+            totals.2 += user.position(NO) + user.locked_position(NO);
+        }
+        totals
+    }
+
+    #[tokio::test]
+    async fn partial_fills_and_cancels_only_move_locked_amounts_between_users() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let orderbook = spawn_engine(None);
+        open_market(&orderbook, &[1, 2]).await;
+        orderbook
+            .create_split_postion(1, YES, NO, 20)
+            .await
+            .unwrap();
+        let before = holdings(&orderbook, &[1, 2]).await;
+
+        // Selling NO at 45 rests as a YES bid at 55, and half of it fills
+        let no_ask = orderbook
+            .place_order(limit(2, NO, OrderSide::Ask, 45, 10))
+            .await
+            .unwrap();
+        orderbook
+            .place_order(limit(1, YES, OrderSide::Ask, 55, 5))
+            .await
+            .unwrap();
+        let buyer = orderbook.get_user_by_id(2).await.unwrap();
+        assert_eq!(buyer.locked_balance, 5 * 55);
+        assert_eq!(holdings(&orderbook, &[1, 2]).await, before);
+
+        // A YES ask fills in part against a YES bid
+        let yes_ask = orderbook
+            .place_order(limit(1, YES, OrderSide::Ask, 60, 8))
+            .await
+            .unwrap();
+        orderbook
+            .place_order(limit(2, YES, OrderSide::Bid, 60, 3))
+            .await
+            .unwrap();
+        let seller = orderbook.get_user_by_id(1).await.unwrap();
+        assert_eq!(seller.locked_position(YES), 5);
+        assert_eq!(holdings(&orderbook, &[1, 2]).await, before);
+
+        orderbook
+            .cancel_order(NO, no_ask.order_id.unwrap())
+            .await
+            .unwrap();
+        orderbook
+            .cancel_order(YES, yes_ask.order_id.unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(holdings(&orderbook, &[1, 2]).await, before);
+        for id in [1, 2] {
+            let user = orderbook.get_user_by_id(id).await.unwrap();
+            assert_eq!(user.locked_balance, 0);
+            assert!(user.locked_positions.values().all(|&qty| qty == 0));
+        }
     }
-    Ok(())
 }
@@ -1,5 +1,10 @@
 pub mod balance;
+pub mod context;
+pub mod deposit;
 pub mod market;
 pub mod matching;
 pub mod orderbook;
+pub mod order_feed;
 pub mod orderbook_actions;
+pub mod probabilities;
+pub mod withdrawal;
@@ -0,0 +1,51 @@
+use std::env;
+use std::sync::OnceLock;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::types::orderbook_types::{Order, OrderFeed, OrderFeedKind};
+
+type HmacSha256 = Hmac<Sha256>;
+
+static PUBLIC_ID_SECRET: OnceLock<String> = OnceLock::new();
+
+/// Reads `L3_ORDER_ID_SECRET`. Public order ids have to stay the same across restarts and
+/// replays, so the engine does not start without it.
+pub fn init_public_order_ids() -> Result<(), String> {
+    let secret =
+        env::var("L3_ORDER_ID_SECRET").map_err(|_| "L3_ORDER_ID_SECRET must be set".to_string())?;
+    if secret.is_empty() {
+        return Err("L3_ORDER_ID_SECRET must not be empty".into());
+    }
+    let _ = PUBLIC_ID_SECRET.set(secret);
+    Ok(())
+}
+
+// Internal order ids are sequential and tie orders to users through the REST API, so the
+// L3 feed only shows an HMAC-SHA256 of them. Tests and benches that never call
+// `init_public_order_ids` get an empty key.
+pub fn public_order_id(order_id: u64) -> String {
+    let secret = PUBLIC_ID_SECRET
+        .get()
+        .map(String::as_str)
+        .unwrap_or_default();
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(&order_id.to_be_bytes());
+    hex::encode(&mac.finalize().into_bytes()[..8])
+}
+
+// Hidden orders never show up in market data.
+pub fn record_order_change(
+    feed: &mut OrderFeed,
+    kind: OrderFeedKind,
+    order: &Order,
+    executed_qty: Option<u64>,
+) {
+    if order.hidden {
+        return;
+    }
+    let public_id = public_order_id(order.order_id.unwrap_or_default());
+    feed.record(kind, public_id, order, executed_qty);
+}
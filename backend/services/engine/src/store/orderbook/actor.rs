@@ -1,29 +1,50 @@
-use chrono::Utc;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use log::{error, info};
+use std::collections::{HashMap, HashSet, VecDeque};
 use tokio::sync::mpsc;
-use uuid::Uuid;
+use tokio::time::{Instant, Interval, interval_at};
 
-use crate::services::db_event_publisher::publish_db_event;
-use crate::store::balance::{reserve_balance, return_reserved_balance, return_unused_reservation};
+use crate::services::db_event_publisher::{publish_db_event, set_db_event_publishing};
+use crate::services::market_data_publisher::set_market_data_publishing;
+use crate::store::balance::{
+    adjust_position, publish_balance, publish_position, reserve_balance, return_reserved_balance,
+};
+use crate::store::context::EngineContext;
+use crate::store::deposit::{create_deposit, pending_deposit_owners, settle_deposit};
+use crate::store::withdrawal::{decide_withdrawal, pending_withdrawal_owners, request_withdrawal};
 use crate::store::market::MarketStore;
 use crate::store::matching::match_order;
 use crate::store::orderbook::api::Orderbook;
+use crate::store::orderbook::client_orders::{
+    ClientOrder, ClientOrderIndex, ClosedStatus, same_placement,
+};
 use crate::store::orderbook::commands::Command;
-use crate::store::orderbook::helpers::{denormalize_price, normalize_order};
-use crate::store::orderbook::snapshot::build_orderbook_snapshot;
+use crate::store::orderbook::helpers::{
+    denormalize_price, is_no_market, normalize_order, validate_order,
+};
+use crate::store::orderbook::market_data::{publish_book_changes, publish_market_status};
+use crate::store::orderbook::persistence::{JournalEntry, Persistence};
+use crate::store::orderbook::snapshot::{
+    build_l3_snapshot, build_orderbook_snapshot, build_top_of_book,
+};
+use crate::store::order_feed::record_order_change;
 use crate::store::orderbook_actions::{add_order_to_book, remove_order_from_book};
 use crate::types::db_event_types::{
-    BalanceUpdatedEvent, DbEvent, OrderCancelledEvent, OrderModifiedEvent, OrderPlacedEvent,
-    PositionUpdatedEvent,
+    DbEvent, OrderCancelledEvent, OrderModifiedEvent, OrderPlacedEvent,
 };
-use crate::types::market_types::{MarketSide, MarketStatus};
+use crate::types::error_types::EngineError;
+use crate::types::ledger_types::{LedgerAccount, LedgerEntry, LedgerKind};
+use crate::types::market_types::{MarketSide, MarketStatus, MarketSummary};
 use crate::types::orderbook_types::{
-    EventOrderbookSnapshot, MarketOrderbookSnapshot, Order, OrderSide, OrderbookData,
-    OutcomeOrderbookSnapshot,
+    EngineState, EventOrderbookSnapshot, MarketOrderbookSnapshot, Order, OrderFeedKind, OrderSide,
+    OrderbookData, OutcomeOrderbookSnapshot,
 };
 use crate::types::user_types::User;
 
-pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
+pub fn spawn_orderbook_actor(
+    market_store: MarketStore,
+    mut persistence: Option<Persistence>,
+    context: EngineContext,
+) -> Orderbook {
     let (tx, mut rx) = mpsc::channel::<Command>(1000);
 
     tokio::spawn(async move {
@@ -31,10 +52,141 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
         let mut users: HashMap<u64, User> = HashMap::new();
         let mut alias_map: HashMap<u64, u64> = HashMap::new();
         let mut order_original_market: HashMap<u64, u64> = HashMap::new();
+        let mut client_orders = ClientOrderIndex::default();
+        let mut withdrawal_owners: HashMap<u64, u64> = HashMap::new();
+        let mut deposit_owners: HashMap<String, u64> = HashMap::new();
+
+        let mut replay: VecDeque<Command> = VecDeque::new();
+        let mut recovering = false;
+        let mut snapshot_timer = persistence.as_ref().map(|p| {
+            interval_at(
+                Instant::now() + p.snapshot_interval(),
+                p.snapshot_interval(),
+            )
+        });
+
+        if let Some(p) = persistence.as_mut() {
+            match p.recover() {
+                Ok((snapshot, entries)) => {
+                    recovering = snapshot.is_some() || !entries.is_empty();
+                    if let Some(snapshot) = snapshot {
+                        if let Err(e) = market_store.restore_markets(snapshot.markets) {
+                            error!("Failed to restore markets from snapshot: {}", e);
+                        }
+                        orderbooks = snapshot.orderbooks;
+                        users = snapshot.users;
+                        withdrawal_owners =
+                            users.values().flat_map(pending_withdrawal_owners).collect();
+                        deposit_owners = users.values().flat_map(pending_deposit_owners).collect();
+                        alias_map = snapshot.alias_map;
+                        order_original_market = snapshot.order_original_market;
+                        client_orders = snapshot.client_orders;
+                        context.observe_sequence(snapshot.event_sequence);
+                        for book in orderbooks.values() {
+                            book.orders.keys().for_each(|id| context.observe_id(*id));
+                        }
+                    }
+                    replay.extend(entries.into_iter().map(JournalEntry::into_command));
+                }
+                Err(e) => error!("Failed to recover engine state: {}", e),
+            }
+            if recovering {
+                info!("Replaying {} journaled engine commands", replay.len());
+                set_db_event_publishing(false);
+                set_market_data_publishing(false);
+            }
+        }
+
+        loop {
+            let replaying = !replay.is_empty();
+            let mut cmd = match replay.pop_front() {
+                Some(cmd) => cmd,
+                None => {
+                    if recovering {
+                        recovering = false;
+                        set_db_event_publishing(true);
+                        set_market_data_publishing(true);
+                        if let Some(p) = persistence.as_mut()
+                            && let Err(e) = p.write_snapshot(
+                                &orderbooks,
+                                &users,
+                                &alias_map,
+                                &order_original_market,
+                                &market_store,
+                                &client_orders,
+                                context.last_sequence(),
+                            )
+                        {
+                            error!("Failed to write engine snapshot: {}", e);
+                        }
+                    }
+
+                    tokio::select! {
+                        cmd = rx.recv() => match cmd {
+                            Some(cmd) => cmd,
+                            None => break,
+                        },
+                        _ = next_snapshot_tick(&mut snapshot_timer) => {
+                            if let Some(p) = persistence.as_mut()
+                                && let Err(e) = p.write_snapshot(
+                                    &orderbooks,
+                                    &users,
+                                    &alias_map,
+                                    &order_original_market,
+                                    &market_store,
+                                    &client_orders,
+                                    context.last_sequence(),
+                                )
+                            {
+                                error!("Failed to write engine snapshot: {}", e);
+                            }
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            if let Command::PlaceOrder(order, _) | Command::RestoreOrder(order, _) = &mut cmd {
+                match order.order_id {
+                    Some(id) => context.observe_id(id),
+                    None => order.order_id = Some(context.next_id()),
+                }
+            }
+            if let Command::RequestWithdrawal(withdrawal, _) = &mut cmd {
+                match withdrawal.withdrawal_id {
+                    Some(id) => context.observe_id(id),
+                    None => withdrawal.withdrawal_id = Some(context.next_id()),
+                }
+            }
+
+            if !replaying
+                && let (Some(p), Some(entry)) =
+                    (persistence.as_mut(), JournalEntry::from_command(&cmd))
+                && let Err(e) = p.append(entry)
+            {
+                error!("Failed to journal engine command: {}", e);
+            }
 
-        while let Some(cmd) = rx.recv().await {
             match cmd {
                 Command::PlaceOrder(mut order, reply) => {
+                    if let Some(client_order_id) = &order.client_order_id
+                        && let Some(existing) = client_orders.get(order.user_id, client_order_id)
+                    {
+                        // A retry gets the first answer; anything else reusing the id is refused
+                        let result = match existing {
+                            ClientOrder::Open(entry) if same_placement(&order, &entry.placed) => {
+                                Ok(entry.placed)
+                            }
+                            other => Err(EngineError::DuplicateClientOrderId(other.order_id())),
+                        };
+                        let _ = reply.send(result);
+                        continue;
+                    }
+                    if let Err(e) = validate_order(&order) {
+                        let _ = reply.send(Err(e));
+                        continue;
+                    }
+
                     let original_market_id = order.market_id;
                     let original_price = order.price;
                     let original_side = order.side.clone();
@@ -48,30 +200,44 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                     };
 
                     let Some(book) = orderbooks.get_mut(&canonical_market_id) else {
-                        let _ = reply.send(Err("Orderbook not found for market".into()));
+                        let _ = reply.send(Err(EngineError::MarketNotFound));
                         continue;
                     };
 
-                    let id = Uuid::new_v4().as_u128() as u64;
+                    let id = order.order_id.unwrap_or_else(|| context.next_id());
                     order.order_id = Some(id);
                     order_original_market.insert(id, original_market_id);
 
-                    if let Err(e) = reserve_balance(&order, &mut users).await {
+                    if let Err(e) = reserve_balance(&order, &mut users, &context).await {
                         let _ = reply.send(Err(e));
                         continue;
                     }
 
-                    if let Err(e) = match_order(&mut order, book, &mut users, &market_store).await {
-                        let _ = return_reserved_balance(&order, &mut users).await;
-                        order_original_market.remove(&id);
-                        let _ = reply.send(Err(e));
-                        continue;
+                    let filled_makers =
+                        match match_order(&mut order, book, &mut users, &market_store, &context)
+                            .await
+                        {
+                            Ok(filled_makers) => filled_makers,
+                            Err(e) => {
+                                let _ = return_reserved_balance(&order, &mut users, &context).await;
+                                order_original_market.remove(&id);
+                                let _ = reply.send(Err(e));
+                                continue;
+                            }
+                        };
+                    for maker in &filled_makers {
+                        client_orders.close(maker, ClosedStatus::Filled);
                     }
 
                     if order.remaining_qty > 0 {
                         add_order_to_book(id, &order, book);
+                        record_order_change(
+                            &mut book.order_feed,
+                            OrderFeedKind::Add,
+                            &order,
+                            None,
+                        );
                     } else {
-                        let _ = return_unused_reservation(&order, &mut users).await;
                         order_original_market.remove(&id);
                     }
 
@@ -87,16 +253,83 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         price: original_price,
                         original_qty: order.original_qty,
                         remaining_qty: order.remaining_qty,
-                        timestamp: Utc::now(),
+                        hidden: order.hidden,
+                        client_order_id: order.client_order_id.clone(),
+                        sequence: context.next_sequence(),
+                        market_sequence: book.next_sequence(),
+                        timestamp: context.now(),
                     }))
                     .await;
+                    publish_book_changes(
+                        canonical_market_id,
+                        &mut orderbooks,
+                        &market_store,
+                        &context,
+                    )
+                    .await;
 
                     let mut response_order = order.clone();
                     response_order.market_id = original_market_id;
                     response_order.price = original_price;
                     response_order.side = original_side;
+                    if let Some(client_order_id) = response_order.client_order_id.clone() {
+                        client_orders.insert(
+                            response_order.user_id,
+                            client_order_id,
+                            id,
+                            response_order.clone(),
+                        );
+                        if response_order.remaining_qty == 0 {
+                            client_orders.close(&response_order, ClosedStatus::Filled);
+                        }
+                    }
                     let _ = reply.send(Ok(response_order));
                 }
+                Command::RestoreOrder(mut order, reply) => {
+                    let original_market_id = order.market_id;
+
+                    let Some(id) = order.order_id else {
+                        let _ = reply.send(Err(EngineError::InvalidRequest(
+                            "Order id is required".into(),
+                        )));
+                        continue;
+                    };
+
+                    if let Some(client_order_id) = order.client_order_id.clone() {
+                        client_orders.insert(order.user_id, client_order_id, id, order.clone());
+                    }
+
+                    let canonical_market_id = match normalize_order(&mut order, &market_store) {
+                        Ok(id) => id,
+                        Err(e) => {
+                            let _ = reply.send(Err(e));
+                            continue;
+                        }
+                    };
+
+                    let Some(book) = orderbooks.get_mut(&canonical_market_id) else {
+                        let _ = reply.send(Err(EngineError::MarketNotFound));
+                        continue;
+                    };
+
+                    // The reservation for a resting order is already reflected in the stored
+                    // locked balance or position, so it only needs to be put back on the book.
+                    order_original_market.insert(id, original_market_id);
+                    add_order_to_book(id, &order, book);
+                    record_order_change(&mut book.order_feed, OrderFeedKind::Add, &order, None);
+                    publish_book_changes(
+                        canonical_market_id,
+                        &mut orderbooks,
+                        &market_store,
+                        &context,
+                    )
+                    .await;
+                    let _ = reply.send(Ok(order));
+                }
+                Command::RestoreClientOrder(user_id, client_order_id, closed, reply) => {
+                    client_orders.restore_closed(user_id, client_order_id, closed);
+                    let _ = reply.send(());
+                }
                 Command::CancelOrder(market_id, order_id, reply) => {
                     let original_market_id = order_original_market
                         .get(&order_id)
@@ -107,12 +340,12 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         .copied()
                         .unwrap_or(original_market_id);
                     let Some(book) = orderbooks.get_mut(&canonical_id) else {
-                        let _ = reply.send(Err("Market not found".into()));
+                        let _ = reply.send(Err(EngineError::MarketNotFound));
                         continue;
                     };
 
                     let Some(order) = book.orders.get(&order_id).cloned() else {
-                        let _ = reply.send(Err("Order not found".into()));
+                        let _ = reply.send(Err(EngineError::OrderNotFound));
                         continue;
                     };
 
@@ -137,24 +370,34 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         };
 
                     remove_order_from_book(order_id, &order, book);
+                    record_order_change(&mut book.order_feed, OrderFeedKind::Cancel, &order, None);
 
-                    let _ = return_reserved_balance(&order, &mut users).await;
+                    let _ = return_reserved_balance(&order, &mut users, &context).await;
 
                     let _ = publish_db_event(DbEvent::OrderCancelled(OrderCancelledEvent {
                         order_id,
                         user_id: order.user_id,
                         market_id: original_market_id,
-                        timestamp: Utc::now(),
+                        sequence: context.next_sequence(),
+                        market_sequence: book.next_sequence(),
+                        timestamp: context.now(),
                     }))
                     .await;
+                    publish_book_changes(canonical_id, &mut orderbooks, &market_store, &context)
+                        .await;
 
                     let mut response_order = order;
                     response_order.market_id = original_market_id;
                     response_order.price = original_price;
                     response_order.side = original_side;
+                    client_orders.close(&response_order, ClosedStatus::Cancelled);
                     let _ = reply.send(Ok(response_order));
                 }
                 Command::ModifyOrder(mut order, reply) => {
+                    if let Err(e) = validate_order(&order) {
+                        let _ = reply.send(Err(e));
+                        continue;
+                    }
                     let original_market_id = order.market_id;
                     let original_price = order.price;
                     let original_side = order.side.clone();
@@ -168,17 +411,19 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                     };
 
                     let Some(book) = orderbooks.get_mut(&canonical_market_id) else {
-                        let _ = reply.send(Err("Market not found".into()));
+                        let _ = reply.send(Err(EngineError::MarketNotFound));
                         continue;
                     };
 
                     let Some(order_id) = order.order_id else {
-                        let _ = reply.send(Err("Order id not found".into()));
+                        let _ = reply.send(Err(EngineError::InvalidRequest(
+                            "Order id is required".into(),
+                        )));
                         continue;
                     };
 
                     let Some(existing_order) = book.orders.get(&order_id).cloned() else {
-                        let _ = reply.send(Err("Order not found".into()));
+                        let _ = reply.send(Err(EngineError::OrderNotFound));
                         continue;
                     };
 
@@ -186,7 +431,7 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                     remove_order_from_book(order_id, &existing_order, book);
 
-                    let _ = return_reserved_balance(&existing_order, &mut users).await;
+                    let _ = return_reserved_balance(&existing_order, &mut users, &context).await;
 
                     order.order_id = Some(order_id);
                     order.remaining_qty = order.original_qty;
@@ -194,21 +439,58 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         order_original_market.insert(order_id, old_market);
                     }
 
-                    if let Err(e) = reserve_balance(&order, &mut users).await {
+                    // A failed modify leaves the old order cancelled
+                    if let Err(e) = reserve_balance(&order, &mut users, &context).await {
+                        client_orders.close(&existing_order, ClosedStatus::Cancelled);
+                        record_order_change(
+                            &mut book.order_feed,
+                            OrderFeedKind::Cancel,
+                            &existing_order,
+                            None,
+                        );
                         let _ = reply.send(Err(e));
                         continue;
                     }
 
-                    if let Err(e) = match_order(&mut order, book, &mut users, &market_store).await {
-                        let _ = return_reserved_balance(&order, &mut users).await;
-                        let _ = reply.send(Err(e));
-                        continue;
+                    let filled_makers =
+                        match match_order(&mut order, book, &mut users, &market_store, &context)
+                            .await
+                        {
+                            Ok(filled_makers) => filled_makers,
+                            Err(e) => {
+                                let _ = return_reserved_balance(&order, &mut users, &context).await;
+                                client_orders.close(&existing_order, ClosedStatus::Cancelled);
+                                record_order_change(
+                                    &mut book.order_feed,
+                                    OrderFeedKind::Cancel,
+                                    &existing_order,
+                                    None,
+                                );
+                                let _ = reply.send(Err(e));
+                                continue;
+                            }
+                        };
+                    for maker in &filled_makers {
+                        client_orders.close(maker, ClosedStatus::Filled);
                     }
 
                     if order.remaining_qty > 0 {
                         add_order_to_book(order_id, &order, book);
+                    }
+                    if order.remaining_qty > 0 && !order.hidden {
+                        let kind = if existing_order.hidden {
+                            OrderFeedKind::Add
+                        } else {
+                            OrderFeedKind::Modify
+                        };
+                        record_order_change(&mut book.order_feed, kind, &order, None);
                     } else {
-                        let _ = return_unused_reservation(&order, &mut users).await;
+                        record_order_change(
+                            &mut book.order_feed,
+                            OrderFeedKind::Cancel,
+                            &existing_order,
+                            None,
+                        );
                     }
 
                     let _ = publish_db_event(DbEvent::OrderModified(OrderModifiedEvent {
@@ -218,25 +500,44 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         price: original_price,
                         original_qty: order.original_qty,
                         remaining_qty: order.remaining_qty,
-                        timestamp: Utc::now(),
+                        sequence: context.next_sequence(),
+                        market_sequence: book.next_sequence(),
+                        timestamp: context.now(),
                     }))
                     .await;
+                    publish_book_changes(
+                        canonical_market_id,
+                        &mut orderbooks,
+                        &market_store,
+                        &context,
+                    )
+                    .await;
 
                     let mut response_order = order;
                     response_order.market_id = original_market_id;
                     response_order.price = original_price;
                     response_order.side = original_side;
+                    if response_order.remaining_qty > 0 {
+                        client_orders.record_modified(&response_order);
+                    } else {
+                        client_orders.close(&response_order, ClosedStatus::Filled);
+                    }
                     let _ = reply.send(Ok(response_order));
                 }
                 Command::GetBestBid(market_id, reply) => {
                     let canonical_id = alias_map.get(&market_id).copied().unwrap_or(market_id);
                     let Some(book) = orderbooks.get(&canonical_id) else {
-                        let _ = reply.send(Err("Market not found".into()));
+                        let _ = reply.send(Err(EngineError::MarketNotFound));
                         continue;
                     };
 
-                    let Some((best_bid_price, _)) = book.bids.last_key_value() else {
-                        let _ = reply.send(Err("No bids available".into()));
+                    let best_bid = if is_no_market(market_id, &market_store) {
+                        book.asks.first_key_value()
+                    } else {
+                        book.bids.last_key_value()
+                    };
+                    let Some((best_bid_price, _)) = best_bid else {
+                        let _ = reply.send(Err(EngineError::NoLiquidity));
                         continue;
                     };
 
@@ -247,12 +548,17 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                 Command::GetBestAsk(market_id, reply) => {
                     let canonical_id = alias_map.get(&market_id).copied().unwrap_or(market_id);
                     let Some(book) = orderbooks.get(&canonical_id) else {
-                        let _ = reply.send(Err("Market not found".into()));
+                        let _ = reply.send(Err(EngineError::MarketNotFound));
                         continue;
                     };
 
-                    let Some((best_ask_price, _)) = book.asks.first_key_value() else {
-                        let _ = reply.send(Err(" No asks available".into()));
+                    let best_ask = if is_no_market(market_id, &market_store) {
+                        book.bids.last_key_value()
+                    } else {
+                        book.asks.first_key_value()
+                    };
+                    let Some((best_ask_price, _)) = best_ask else {
+                        let _ = reply.send(Err(EngineError::NoLiquidity));
                         continue;
                     };
 
@@ -260,9 +566,10 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         denormalize_price(market_id, *best_ask_price, &market_store);
                     let _ = reply.send(Ok(denormalized_price));
                 }
-                Command::GetOrderBook(market_id, reply) => {
+                Command::GetOrderBook(market_id, options, reply) => {
                     match build_orderbook_snapshot(
                         market_id,
+                        &options,
                         &alias_map,
                         &orderbooks,
                         &market_store,
@@ -275,15 +582,23 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                         }
                     }
                 }
-                Command::GetOrderbooksByEvent(event_id, reply) => {
+                Command::GetOrderBookL3(market_id, reply) => {
+                    let _ = reply.send(build_l3_snapshot(
+                        market_id,
+                        &alias_map,
+                        &orderbooks,
+                        &market_store,
+                    ));
+                }
+                Command::GetOrderbooksByEvent(event_id, options, reply) => {
                     let market_ids = market_store.get_markets_by_event(event_id);
                     if market_ids.is_empty() {
-                        let _ = reply.send(Err("No markets found for event".into()));
+                        let _ = reply.send(Err(EngineError::EventNotFound));
                         continue;
                     }
 
                     let mut outcomes: HashMap<u64, OutcomeOrderbookSnapshot> = HashMap::new();
-                    let mut error: Option<String> = None;
+                    let mut error: Option<EngineError> = None;
 
                     for market_id in market_ids {
                         let Some(market_meta) = market_store.get_market(market_id) else {
@@ -295,6 +610,7 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                         match build_orderbook_snapshot(
                             market_id,
+                            &options,
                             &alias_map,
                             &orderbooks,
                             &market_store,
@@ -326,7 +642,7 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                     }
 
                     if outcomes.is_empty() {
-                        let _ = reply.send(Err("No orderbooks found for event".into()));
+                        let _ = reply.send(Err(EngineError::EventNotFound));
                         continue;
                     }
 
@@ -345,16 +661,16 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                     let _ = reply.send(Ok(response));
                 }
-                Command::GetOrderbooksByOutcome(outcome_id, reply) => {
+                Command::GetOrderbooksByOutcome(outcome_id, options, reply) => {
                     let market_ids = market_store.get_markets_by_outcome(outcome_id);
                     if market_ids.is_empty() {
-                        let _ = reply.send(Err("No markets found for outcome".into()));
+                        let _ = reply.send(Err(EngineError::OutcomeNotFound));
                         continue;
                     }
 
                     let mut markets: Vec<MarketOrderbookSnapshot> = Vec::new();
                     let mut event_id_for_outcome: Option<u64> = None;
-                    let mut error: Option<String> = None;
+                    let mut error: Option<EngineError> = None;
 
                     for market_id in market_ids {
                         let Some(market_meta) = market_store.get_market(market_id) else {
@@ -366,6 +682,7 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                         match build_orderbook_snapshot(
                             market_id,
+                            &options,
                             &alias_map,
                             &orderbooks,
                             &market_store,
@@ -390,7 +707,7 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                     }
 
                     if markets.is_empty() {
-                        let _ = reply.send(Err("No orderbooks found for outcome".into()));
+                        let _ = reply.send(Err(EngineError::OutcomeNotFound));
                         continue;
                     }
 
@@ -404,6 +721,58 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                     let _ = reply.send(Ok(response));
                 }
+                Command::GetTopOfBook(market_ids, reply) => {
+                    let mut market_ids =
+                        market_ids.unwrap_or_else(|| alias_map.keys().copied().collect());
+                    market_ids.sort_unstable();
+                    market_ids.dedup();
+
+                    let tops = market_ids
+                        .into_iter()
+                        .filter_map(|market_id| {
+                            build_top_of_book(market_id, &alias_map, &orderbooks, &market_store)
+                        })
+                        .collect();
+                    let _ = reply.send(tops);
+                }
+                Command::ListMarkets(status, event_id, reply) => {
+                    let mut markets: Vec<MarketSummary> = market_store
+                        .list_markets()
+                        .into_iter()
+                        .filter(|market| status.as_ref().is_none_or(|s| &market.status == s))
+                        .filter(|market| event_id.is_none_or(|id| market.event_id == Some(id)))
+                        .map(|market| {
+                            let top = build_top_of_book(
+                                market.market_id,
+                                &alias_map,
+                                &orderbooks,
+                                &market_store,
+                            );
+                            let (best_bid, best_ask, last_price) = match top {
+                                Some(top) => (top.best_bid, top.best_ask, top.last_price),
+                                None => (None, None, None),
+                            };
+                            let spread = best_bid
+                                .as_ref()
+                                .zip(best_ask.as_ref())
+                                .map(|(bid, ask)| ask.price as i64 - bid.price as i64);
+                            MarketSummary {
+                                market_id: market.market_id,
+                                side: market.side,
+                                paired_market_id: market.paired_market_id,
+                                event_id: market.event_id,
+                                outcome_id: market.outcome_id,
+                                status: market.status,
+                                best_bid,
+                                best_ask,
+                                last_price,
+                                spread,
+                            }
+                        })
+                        .collect();
+                    markets.sort_unstable_by_key(|market| market.market_id);
+                    let _ = reply.send(markets);
+                }
                 Command::GetUserOpenOrders(user_id, reply) => {
                     let mut user_orders = Vec::new();
 
@@ -489,14 +858,22 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                             let _ = reply.send(Ok(order));
                         }
                         None => {
-                            let _ = reply.send(Err("Order not found".into()));
+                            let _ = reply.send(Err(EngineError::OrderNotFound));
                         }
                     }
                 }
+                Command::GetClientOrder(user_id, client_order_id, reply) => {
+                    let _ = reply.send(client_orders.get(user_id, &client_order_id));
+                }
+                Command::GetMarket(market_id, reply) => {
+                    let _ = reply.send(market_store.get_market(market_id));
+                }
                 Command::AddUser(user, reply) => {
                     let id = user.id;
-                    users.insert(id, user.clone());
-                    let _ = reply.send(Some(user));
+                    withdrawal_owners.extend(pending_withdrawal_owners(&user));
+                    deposit_owners.extend(pending_deposit_owners(&user));
+                    users.insert(id, (*user).clone());
+                    let _ = reply.send(Some(*user));
                 }
                 Command::GetUserById(id, reply) => {
                     let user = users.get(&id).cloned();
@@ -510,65 +887,88 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                     let res = users
                         .get(&id)
                         .map(|u| Ok(u.balance))
-                        .unwrap_or_else(|| Err("User not found".into()));
+                        .unwrap_or_else(|| Err(EngineError::UserNotFound));
                     let _ = reply.send(res);
                 }
                 Command::UpdateBalance(id, amount, reply) => {
                     if let Some(u) = users.get_mut(&id) {
                         u.balance += amount;
-                        let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                            user_id: id,
-                            balance: u.balance,
-                            timestamp: Utc::now(),
-                        }))
-                        .await;
+                        let ledger = LedgerEntry::new(LedgerKind::Onramp).transfer(
+                            LedgerAccount::External,
+                            LedgerAccount::Available,
+                            amount,
+                        );
+                        publish_balance(u, ledger, &context).await;
                         let _ = reply.send(Ok(()));
                     } else {
-                        let _ = reply.send(Err("User not found".into()));
+                        let _ = reply.send(Err(EngineError::UserNotFound));
                     }
                 }
-                Command::GetPosition(user_id, market_id, reply) => {
-                    let position = users
-                        .get(&user_id)
-                        .and_then(|u| u.positions.get(&market_id))
-                        .copied()
-                        .unwrap_or(0);
-
-                    let _ = reply.send(Ok(position));
+                Command::RequestWithdrawal(withdrawal, reply) => {
+                    let res = request_withdrawal(
+                        withdrawal,
+                        &mut users,
+                        &mut withdrawal_owners,
+                        &context,
+                    )
+                    .await;
+                    let _ = reply.send(res);
                 }
-                Command::GetUserPositions(user_id, reply) => {
-                    let positions = users
-                        .get(&user_id)
-                        .map(|u| u.positions.clone())
-                        .unwrap_or_default();
-                    let _ = reply.send(Ok(positions));
+                Command::ApproveWithdrawal(decision, reply) => {
+                    let res = decide_withdrawal(
+                        decision,
+                        true,
+                        &mut users,
+                        &mut withdrawal_owners,
+                        &context,
+                    )
+                    .await;
+                    let _ = reply.send(res);
+                }
+                Command::RejectWithdrawal(decision, reply) => {
+                    let res = decide_withdrawal(
+                        decision,
+                        false,
+                        &mut users,
+                        &mut withdrawal_owners,
+                        &context,
+                    )
+                    .await;
+                    let _ = reply.send(res);
+                }
+                Command::CreateDeposit(deposit, reply) => {
+                    let res =
+                        create_deposit(deposit, &mut users, &mut deposit_owners, &context).await;
+                    let _ = reply.send(res);
+                }
+                Command::ConfirmDeposit(deposit_id, reply) => {
+                    let res =
+                        settle_deposit(deposit_id, true, &mut users, &mut deposit_owners, &context)
+                            .await;
+                    let _ = reply.send(res);
+                }
+                Command::FailDeposit(deposit_id, reply) => {
+                    let res = settle_deposit(
+                        deposit_id,
+                        false,
+                        &mut users,
+                        &mut deposit_owners,
+                        &context,
+                    )
+                    .await;
+                    let _ = reply.send(res);
                 }
                 Command::UpdatePosition(user_id, market_id, amount, reply) => {
                     if let Some(user) = users.get_mut(&user_id) {
-                        let current = user.positions.entry(market_id).or_insert(0);
-
-                        if amount < 0 && (*current as i64) < -amount {
-                            let _ = reply.send(Err("Insufficient position".into()));
+                        if amount < 0 && (user.position(market_id) as i64) < -amount {
+                            let _ = reply.send(Err(EngineError::InsufficientPosition));
                         } else {
-                            *current = ((*current as i64) + amount) as u64;
-                            let final_qty = if *current == 0 {
-                                user.positions.remove(&market_id);
-                                0
-                            } else {
-                                *current
-                            };
-                            let _ =
-                                publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                                    user_id,
-                                    market_id,
-                                    quantity: final_qty,
-                                    timestamp: Utc::now(),
-                                }))
-                                .await;
+                            adjust_position(&mut user.positions, market_id, amount);
+                            publish_position(user, market_id, &context).await;
                             let _ = reply.send(Ok(()));
                         }
                     } else {
-                        let _ = reply.send(Err("User not found".into()));
+                        let _ = reply.send(Err(EngineError::UserNotFound));
                     }
                 }
                 Command::CheckPositionSufficient(user_id, market_id, required_qty, reply) => {
@@ -582,45 +982,37 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                 }
                 Command::CreateSplitPosition(user_id, market1_id, market2_id, amount, reply) => {
                     let Some(user) = users.get_mut(&user_id) else {
-                        let _ = reply.send(Err("User not found".into()));
+                        let _ = reply.send(Err(EngineError::UserNotFound));
                         continue;
                     };
 
                     if user.balance < amount as i64 {
-                        let _ = reply.send(Err("Insufficient balance".into()));
+                        let _ = reply.send(Err(EngineError::InsufficientBalance));
                         continue;
                     }
 
+                    // The split price is shared evenly between the two legs
+                    let market1_cost = amount as i64 / 2;
                     user.balance -= amount as i64;
+                    user.record_buy(market1_id, market1_cost);
+                    user.record_buy(market2_id, amount as i64 - market1_cost);
                     *user.positions.entry(market1_id).or_insert(0) += amount;
                     *user.positions.entry(market2_id).or_insert(0) += amount;
 
-                    let _ = publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                        user_id,
-                        balance: user.balance,
-                        timestamp: Utc::now(),
-                    }))
-                    .await;
-                    let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                        user_id,
-                        market_id: market1_id,
-                        quantity: user.positions[&market1_id],
-                        timestamp: Utc::now(),
-                    }))
-                    .await;
-                    let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                        user_id,
-                        market_id: market2_id,
-                        quantity: user.positions[&market2_id],
-                        timestamp: Utc::now(),
-                    }))
-                    .await;
+                    let ledger = LedgerEntry::new(LedgerKind::Split).transfer(
+                        LedgerAccount::Available,
+                        LedgerAccount::Collateral,
+                        amount as i64,
+                    );
+                    publish_balance(user, ledger, &context).await;
+                    publish_position(user, market1_id, &context).await;
+                    publish_position(user, market2_id, &context).await;
 
                     let _ = reply.send(Ok(()));
                 }
                 Command::MergePosition(user_id, market1_id, market2_id, reply) => {
                     let Some(user) = users.get_mut(&user_id) else {
-                        let _ = reply.send(Err("User not found".into()));
+                        let _ = reply.send(Err(EngineError::UserNotFound));
                         continue;
                     };
 
@@ -629,70 +1021,37 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                     let merge_qty = position1.min(position2);
                     if merge_qty == 0 {
-                        let _ = reply.send(Err("Cannot merge, Insufficient positions".into()));
+                        let _ = reply.send(Err(EngineError::InsufficientPosition));
                         continue;
                     }
 
-                    user.positions
-                        .entry(market1_id)
-                        .and_modify(|p| *p -= merge_qty);
-                    let pos1_final = if user.positions[&market1_id] == 0 {
-                        user.positions.remove(&market1_id);
-                        0
-                    } else {
-                        user.positions[&market1_id]
-                    };
-
-                    user.positions
-                        .entry(market2_id)
-                        .and_modify(|p| *p -= merge_qty);
-                    let pos2_final = if user.positions[&market2_id] == 0 {
-                        user.positions.remove(&market2_id);
-                        0
-                    } else {
-                        user.positions[&market2_id]
-                    };
+                    // Merging pays nothing out, so the merged shares close with no proceeds
+                    user.record_sell(market1_id, merge_qty, 0);
+                    user.record_sell(market2_id, merge_qty, 0);
+                    adjust_position(&mut user.positions, market1_id, -(merge_qty as i64));
+                    adjust_position(&mut user.positions, market2_id, -(merge_qty as i64));
 
-                    let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                        user_id,
-                        market_id: market1_id,
-                        quantity: pos1_final,
-                        timestamp: Utc::now(),
-                    }))
-                    .await;
-                    let _ = publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                        user_id,
-                        market_id: market2_id,
-                        quantity: pos2_final,
-                        timestamp: Utc::now(),
-                    }))
-                    .await;
+                    publish_position(user, market1_id, &context).await;
+                    publish_position(user, market2_id, &context).await;
 
                     let _ = reply.send(Ok(()));
                 }
                 Command::InitMarkets(metas, reply) => {
-                    let mut error_msg: Option<String> = None;
+                    let mut error_msg: Option<EngineError> = None;
                     for meta in &metas {
                         if let Err(e) = market_store.register_market_pair(meta.clone()) {
-                            error_msg = Some(format!("Failed to register market pair: {}", e));
+                            error_msg = Some(EngineError::Internal(format!(
+                                "Failed to register market pair: {}",
+                                e
+                            )));
                             break;
                         }
 
                         alias_map.insert(meta.yes_market_id, meta.yes_market_id);
                         alias_map.insert(meta.no_market_id, meta.yes_market_id);
 
-                        orderbooks.insert(
-                            meta.yes_market_id,
-                            OrderbookData {
-                                market_id: meta.yes_market_id,
-                                asks: BTreeMap::new(),
-                                bids: BTreeMap::new(),
-                                ask_queue: HashMap::new(),
-                                bid_queue: HashMap::new(),
-                                orders: HashMap::new(),
-                                last_price: None,
-                            },
-                        );
+                        orderbooks
+                            .insert(meta.yes_market_id, OrderbookData::new(meta.yes_market_id));
                     }
                     match error_msg {
                         Some(e) => {
@@ -714,9 +1073,10 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                     }
 
                     for canonical_id in canonical_ids {
-                        if let Some(book) = orderbooks.get(&canonical_id) {
-                            for (order_id, order) in &book.orders {
-                                let _ = return_reserved_balance(order, &mut users).await;
+                        if let Some(book) = orderbooks.get_mut(&canonical_id) {
+                            let orders = std::mem::take(&mut book.orders);
+                            for (order_id, order) in &orders {
+                                let _ = return_reserved_balance(order, &mut users, &context).await;
                                 let original_market_id = order_original_market
                                     .get(order_id)
                                     .copied()
@@ -726,11 +1086,14 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                                         order_id: *order_id,
                                         user_id: order.user_id,
                                         market_id: original_market_id,
-                                        timestamp: Utc::now(),
+                                        sequence: context.next_sequence(),
+                                        market_sequence: book.next_sequence(),
+                                        timestamp: context.now(),
                                     },
                                 ))
                                 .await;
                                 order_original_market.remove(order_id);
+                                client_orders.close(order, ClosedStatus::Cancelled);
                             }
                         }
                         orderbooks.remove(&canonical_id);
@@ -760,10 +1123,13 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
                                         MarketSide::No => market_outcome_id != winning_outcome_id,
                                     };
 
-                                    if is_winning {
-                                        let payout = (*quantity as i64) * 100;
-                                        total_payout += payout;
-                                    }
+                                    let payout = if is_winning {
+                                        (*quantity as i64) * 100
+                                    } else {
+                                        0
+                                    };
+                                    total_payout += payout;
+                                    user.record_sell(*market_id, *quantity, payout);
                                     positions_to_remove.push(*market_id);
                                 }
                             }
@@ -771,36 +1137,70 @@ pub fn spawn_orderbook_actor(market_store: MarketStore) -> Orderbook {
 
                         if total_payout > 0 {
                             user.balance += total_payout;
-                            let _ =
-                                publish_db_event(DbEvent::BalanceUpdated(BalanceUpdatedEvent {
-                                    user_id: user.id,
-                                    balance: user.balance,
-                                    timestamp: Utc::now(),
-                                }))
-                                .await;
+                            let ledger = LedgerEntry::new(LedgerKind::Settlement)
+                                .transfer(
+                                    LedgerAccount::Settlement,
+                                    LedgerAccount::Available,
+                                    total_payout,
+                                )
+                                .event(event_id);
+                            publish_balance(user, ledger, &context).await;
                         }
 
                         for market_id in positions_to_remove {
                             user.positions.remove(&market_id);
-                            let _ =
-                                publish_db_event(DbEvent::PositionUpdated(PositionUpdatedEvent {
-                                    user_id: user.id,
-                                    market_id,
-                                    quantity: 0,
-                                    timestamp: Utc::now(),
-                                }))
-                                .await;
+                            publish_position(user, market_id, &context).await;
                         }
                     }
 
                     let _ =
                         market_store.update_status_bulk(market_ids.clone(), MarketStatus::Resolved);
+                    for market_id in &market_ids {
+                        publish_market_status(
+                            *market_id,
+                            MarketStatus::Resolved,
+                            &market_store,
+                            &context,
+                        )
+                        .await;
+                    }
                     let _ = market_store.remove_markets_by_event(event_id);
                     let _ = reply.send(Ok(()));
                 }
+                Command::RestoreSequence(sequence, reply) => {
+                    context.observe_sequence(sequence);
+                    let _ = reply.send(());
+                }
+                Command::ExportState(reply) => {
+                    let _ = reply.send(EngineState {
+                        orderbooks: orderbooks.clone(),
+                        users: users.clone(),
+                    });
+                }
+                Command::SetStreamPosition(stream_id, reply) => {
+                    if let Some(p) = persistence.as_mut() {
+                        p.set_stream_id(stream_id);
+                    }
+                    let _ = reply.send(());
+                }
+                Command::GetStreamPosition(reply) => {
+                    let position = persistence
+                        .as_ref()
+                        .and_then(|p| p.applied_stream_id().map(str::to_string));
+                    let _ = reply.send(position);
+                }
             }
         }
     });
 
     Orderbook::new(tx)
 }
+
+async fn next_snapshot_tick(timer: &mut Option<Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        }
+        None => std::future::pending::<()>().await,
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::HashMap;
-
 use tokio::sync::{mpsc, oneshot};
 
+use crate::store::orderbook::client_orders::{ClientOrder, ClosedClientOrder};
 use crate::store::orderbook::commands::Command;
-use crate::types::market_types::MarketMeta;
+use crate::types::deposit_types::{Deposit, DepositStatus};
+use crate::types::error_types::EngineError;
+use crate::types::market_types::{Market, MarketMeta, MarketStatus, MarketSummary};
 use crate::types::orderbook_types::{
-    EventOrderbookSnapshot, Order, OrderbookSnapshot, OutcomeOrderbookSnapshot,
+    EngineState, EventOrderbookSnapshot, Order, OrderbookL3Snapshot, OrderbookSnapshot,
+    OutcomeOrderbookSnapshot, SnapshotOptions, TopOfBook,
 };
 use crate::types::user_types::User;
+use crate::types::withdrawal_types::{Withdrawal, WithdrawalDecision, WithdrawalStatus};
 
 #[derive(Clone)]
 pub struct Orderbook {
@@ -19,94 +22,185 @@ impl Orderbook {
         Self { tx }
     }
 
-    pub async fn place_order(&self, order: Order) -> Result<Order, String> {
+    pub async fn place_order(&self, order: Order) -> Result<Order, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::PlaceOrder(order, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to place order".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to place order".into())))
     }
 
-    pub async fn cancel_order(&self, market_id: u64, order_id: u64) -> Result<Order, String> {
+    pub async fn restore_order(&self, order: Order) -> Result<Order, EngineError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::RestoreOrder(order, tx)).await;
+        rx.await
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to restore order".into())))
+    }
+
+    pub async fn restore_client_order(
+        &self,
+        user_id: u64,
+        client_order_id: String,
+        closed: ClosedClientOrder,
+    ) {
+        let (tx, rx) = oneshot::channel();
+        let _ = self
+            .tx
+            .send(Command::RestoreClientOrder(
+                user_id,
+                client_order_id,
+                closed,
+                tx,
+            ))
+            .await;
+        let _ = rx.await;
+    }
+
+    pub async fn cancel_order(&self, market_id: u64, order_id: u64) -> Result<Order, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
             .send(Command::CancelOrder(market_id, order_id, tx))
             .await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to cancel order".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to cancel order".into())))
     }
 
-    pub async fn modify_order(&self, order: Order) -> Result<Order, String> {
+    pub async fn modify_order(&self, order: Order) -> Result<Order, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::ModifyOrder(order, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to modify order".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to modify order".into())))
     }
 
-    pub async fn best_bid(&self, market_id: u64) -> Result<u64, String> {
+    pub async fn best_bid(&self, market_id: u64) -> Result<u64, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::GetBestBid(market_id, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get best bid".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get best bid".into())))
     }
 
-    pub async fn best_ask(&self, market_id: u64) -> Result<u64, String> {
+    pub async fn best_ask(&self, market_id: u64) -> Result<u64, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::GetBestAsk(market_id, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get best ask".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get best ask".into())))
     }
 
-    pub async fn get_orderbook(&self, market_id: u64) -> Result<OrderbookSnapshot, String> {
+    pub async fn get_orderbook(
+        &self,
+        market_id: u64,
+        options: SnapshotOptions,
+    ) -> Result<OrderbookSnapshot, EngineError> {
         let (tx, rx) = oneshot::channel();
-        let _ = self.tx.send(Command::GetOrderBook(market_id, tx)).await;
+        let _ = self
+            .tx
+            .send(Command::GetOrderBook(market_id, options, tx))
+            .await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get orderbook".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get orderbook".into())))
+    }
+
+    pub async fn get_orderbook_l3(
+        &self,
+        market_id: u64,
+    ) -> Result<OrderbookL3Snapshot, EngineError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::GetOrderBookL3(market_id, tx)).await;
+        rx.await
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get orderbook".into())))
     }
 
     pub async fn get_event_orderbooks(
         &self,
         event_id: u64,
-    ) -> Result<EventOrderbookSnapshot, String> {
+        options: SnapshotOptions,
+    ) -> Result<EventOrderbookSnapshot, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
-            .send(Command::GetOrderbooksByEvent(event_id, tx))
+            .send(Command::GetOrderbooksByEvent(event_id, options, tx))
             .await;
-        rx.await
-            .unwrap_or_else(|_| Err("Failed to get event orderbooks".into()))
+        rx.await.unwrap_or_else(|_| {
+            Err(EngineError::Internal(
+                "Failed to get event orderbooks".into(),
+            ))
+        })
     }
 
     pub async fn get_outcome_orderbooks(
         &self,
         outcome_id: u64,
-    ) -> Result<OutcomeOrderbookSnapshot, String> {
+        options: SnapshotOptions,
+    ) -> Result<OutcomeOrderbookSnapshot, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
-            .send(Command::GetOrderbooksByOutcome(outcome_id, tx))
+            .send(Command::GetOrderbooksByOutcome(outcome_id, options, tx))
             .await;
-        rx.await
-            .unwrap_or_else(|_| Err("Failed to get outcome orderbooks".into()))
+        rx.await.unwrap_or_else(|_| {
+            Err(EngineError::Internal(
+                "Failed to get outcome orderbooks".into(),
+            ))
+        })
+    }
+
+    // Every market with a book when `market_ids` is None; unknown ids are skipped.
+    pub async fn get_top_of_book(&self, market_ids: Option<Vec<u64>>) -> Vec<TopOfBook> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::GetTopOfBook(market_ids, tx)).await;
+        rx.await.unwrap_or_default()
+    }
+
+    // Every registered market, optionally narrowed to one status and/or event.
+    pub async fn list_markets(
+        &self,
+        status: Option<MarketStatus>,
+        event_id: Option<u64>,
+    ) -> Vec<MarketSummary> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self
+            .tx
+            .send(Command::ListMarkets(status, event_id, tx))
+            .await;
+        rx.await.unwrap_or_default()
     }
 
-    pub async fn get_user_open_orders(&self, user_id: u64) -> Result<Vec<Order>, String> {
+    pub async fn get_user_open_orders(&self, user_id: u64) -> Result<Vec<Order>, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::GetUserOpenOrders(user_id, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get user orders".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get user orders".into())))
     }
 
-    pub async fn get_order_status(&self, order_id: u64) -> Result<Order, String> {
+    pub async fn get_order_status(&self, order_id: u64) -> Result<Order, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::GetOrderStatus(order_id, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get order status".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get order status".into())))
+    }
+
+    pub async fn get_client_order(
+        &self,
+        user_id: u64,
+        client_order_id: String,
+    ) -> Option<ClientOrder> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self
+            .tx
+            .send(Command::GetClientOrder(user_id, client_order_id, tx))
+            .await;
+        rx.await.ok().flatten()
+    }
+
+    pub async fn get_market(&self, market_id: u64) -> Option<Market> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::GetMarket(market_id, tx)).await;
+        rx.await.ok().flatten()
     }
 
     pub async fn add_user(&self, user: User) -> Option<User> {
         let (tx, rx) = oneshot::channel();
-        let _ = self.tx.send(Command::AddUser(user, tx)).await;
+        let _ = self.tx.send(Command::AddUser(Box::new(user), tx)).await;
         rx.await.ok().flatten()
     }
 
@@ -122,35 +216,92 @@ impl Orderbook {
         rx.await.ok().flatten()
     }
 
-    pub async fn get_balance(&self, id: u64) -> Result<i64, String> {
+    pub async fn get_balance(&self, id: u64) -> Result<i64, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::GetBalance(id, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("failed to get balance".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to get balance".into())))
     }
 
-    pub async fn update_balance(&self, id: u64, amount: i64) -> Result<(), String> {
+    pub async fn update_balance(&self, id: u64, amount: i64) -> Result<(), EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::UpdateBalance(id, amount, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("failed to update balance".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to update balance".into())))
     }
 
-    pub async fn get_position(&self, user_id: u64, market_id: u64) -> Result<u64, String> {
+    pub async fn request_withdrawal(
+        &self,
+        user_id: u64,
+        amount: i64,
+    ) -> Result<Withdrawal, EngineError> {
+        let withdrawal = Withdrawal {
+            withdrawal_id: None,
+            user_id,
+            amount,
+            status: WithdrawalStatus::Pending,
+        };
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
-            .send(Command::GetPosition(user_id, market_id, tx))
+            .send(Command::RequestWithdrawal(withdrawal, tx))
             .await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get positions".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to request withdrawal".into())))
+    }
+
+    pub async fn approve_withdrawal(
+        &self,
+        decision: WithdrawalDecision,
+    ) -> Result<Withdrawal, EngineError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::ApproveWithdrawal(decision, tx)).await;
+        rx.await
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to approve withdrawal".into())))
+    }
+
+    pub async fn reject_withdrawal(
+        &self,
+        decision: WithdrawalDecision,
+    ) -> Result<Withdrawal, EngineError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::RejectWithdrawal(decision, tx)).await;
+        rx.await
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to reject withdrawal".into())))
+    }
+
+    pub async fn create_deposit(
+        &self,
+        deposit_id: String,
+        user_id: u64,
+        amount: i64,
+        provider: String,
+    ) -> Result<Deposit, EngineError> {
+        let deposit = Deposit {
+            deposit_id,
+            user_id,
+            amount,
+            provider,
+            status: DepositStatus::Pending,
+        };
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::CreateDeposit(deposit, tx)).await;
+        rx.await
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to create deposit".into())))
+    }
+
+    pub async fn confirm_deposit(&self, deposit_id: String) -> Result<Deposit, EngineError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::ConfirmDeposit(deposit_id, tx)).await;
+        rx.await
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to confirm deposit".into())))
     }
 
-    pub async fn get_user_positions(&self, user_id: u64) -> Result<HashMap<u64, u64>, String> {
+    pub async fn fail_deposit(&self, deposit_id: String) -> Result<Deposit, EngineError> {
         let (tx, rx) = oneshot::channel();
-        let _ = self.tx.send(Command::GetUserPositions(user_id, tx)).await;
+        let _ = self.tx.send(Command::FailDeposit(deposit_id, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to get user positions".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to fail deposit".into())))
     }
 
     pub async fn update_position(
@@ -158,14 +309,14 @@ impl Orderbook {
         user_id: u64,
         market_id: u64,
         amount: i64,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
             .send(Command::UpdatePosition(user_id, market_id, amount, tx))
             .await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to update position".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to update position".into())))
     }
 
     pub async fn check_position_sufficient(
@@ -173,7 +324,7 @@ impl Orderbook {
         user_id: u64,
         market_id: u64,
         required_qty: u64,
-    ) -> Result<bool, String> {
+    ) -> Result<bool, EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
@@ -185,7 +336,7 @@ impl Orderbook {
             ))
             .await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to check position".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to check position".into())))
     }
 
     pub async fn create_split_postion(
@@ -194,7 +345,7 @@ impl Orderbook {
         market1_id: u64,
         market2_id: u64,
         amount: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
@@ -202,8 +353,11 @@ impl Orderbook {
                 user_id, market1_id, market2_id, amount, tx,
             ))
             .await;
-        rx.await
-            .unwrap_or_else(|_| Err("Failed to create split position".into()))
+        rx.await.unwrap_or_else(|_| {
+            Err(EngineError::Internal(
+                "Failed to create split position".into(),
+            ))
+        })
     }
 
     pub async fn merge_position(
@@ -211,34 +365,67 @@ impl Orderbook {
         user_id: u64,
         market1_id: u64,
         market2_id: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
             .send(Command::MergePosition(user_id, market1_id, market2_id, tx))
             .await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to merge position".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to merge position".into())))
     }
 
-    pub async fn init_markets(&self, metas: Vec<MarketMeta>) -> Result<(), String> {
+    pub async fn init_markets(&self, metas: Vec<MarketMeta>) -> Result<(), EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self.tx.send(Command::InitMarkets(metas, tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to init markets".into()))
+            .unwrap_or_else(|_| Err(EngineError::Internal("Failed to init markets".into())))
     }
 
     pub async fn close_event_markets(
         &self,
         event_id: u64,
         winning_outcome_id: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let (tx, rx) = oneshot::channel();
         let _ = self
             .tx
             .send(Command::CloseEventMarkets(event_id, winning_outcome_id, tx))
             .await;
+        rx.await.unwrap_or_else(|_| {
+            Err(EngineError::Internal(
+                "Failed to close event markets".into(),
+            ))
+        })
+    }
+
+    /// Continues DB event sequences after the last one db_worker has applied.
+    pub async fn restore_sequence(&self, sequence: u64) {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::RestoreSequence(sequence, tx)).await;
+        let _ = rx.await;
+    }
+
+    pub async fn export_state(&self) -> Result<EngineState, EngineError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::ExportState(tx)).await;
         rx.await
-            .unwrap_or_else(|_| Err("Failed to close event markets".into()))
+            .map_err(|_| EngineError::Internal("Failed to export engine state".into()))
+    }
+
+    /// Tags the commands that follow with the `server_requests` entry they came from.
+    pub async fn set_stream_position(&self, stream_id: String) {
+        let (tx, rx) = oneshot::channel();
+        let _ = self
+            .tx
+            .send(Command::SetStreamPosition(stream_id, tx))
+            .await;
+        let _ = rx.await;
+    }
+
+    pub async fn stream_position(&self) -> Option<String> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.tx.send(Command::GetStreamPosition(tx)).await;
+        rx.await.ok().flatten()
     }
 }
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::orderbook_types::{Order, OrderSide, OrderType};
+
+/// What the engine answered for an open order placed with a `client_order_id`, kept so a
+/// retried request gets the same answer instead of acting twice.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientOrderEntry {
+    pub order_id: u64,
+    pub placed: Order,
+    pub modified: Option<Order>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosedStatus {
+    Filled,
+    Cancelled,
+}
+
+/// What is left of a client order id once its order is done. The `orders` table keeps ids
+/// unique per user, so the id stays taken.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClosedClientOrder {
+    pub order_id: u64,
+    pub status: ClosedStatus,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClientOrder {
+    Open(ClientOrderEntry),
+    Closed(ClosedClientOrder),
+}
+
+impl ClientOrder {
+    pub fn order_id(&self) -> u64 {
+        match self {
+            ClientOrder::Open(entry) => entry.order_id,
+            ClientOrder::Closed(closed) => closed.order_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientOrderIndex {
+    by_user: HashMap<u64, HashMap<String, ClientOrderEntry>>,
+    #[serde(default)]
+    closed: HashMap<u64, HashMap<String, ClosedClientOrder>>,
+}
+
+impl ClientOrderIndex {
+    pub fn get(&self, user_id: u64, client_order_id: &str) -> Option<ClientOrder> {
+        if let Some(entry) = self
+            .by_user
+            .get(&user_id)
+            .and_then(|orders| orders.get(client_order_id))
+        {
+            return Some(ClientOrder::Open(entry.clone()));
+        }
+        self.closed
+            .get(&user_id)?
+            .get(client_order_id)
+            .cloned()
+            .map(ClientOrder::Closed)
+    }
+
+    pub fn insert(&mut self, user_id: u64, client_order_id: String, order_id: u64, placed: Order) {
+        self.by_user.entry(user_id).or_default().insert(
+            client_order_id,
+            ClientOrderEntry {
+                order_id,
+                placed,
+                modified: None,
+            },
+        );
+    }
+
+    pub fn record_modified(&mut self, order: &Order) {
+        let Some(client_order_id) = order.client_order_id.as_ref() else {
+            return;
+        };
+        if let Some(entry) = self
+            .by_user
+            .get_mut(&order.user_id)
+            .and_then(|orders| orders.get_mut(client_order_id))
+        {
+            entry.modified = Some(order.clone());
+        }
+    }
+
+    pub fn restore_closed(
+        &mut self,
+        user_id: u64,
+        client_order_id: String,
+        closed: ClosedClientOrder,
+    ) {
+        self.closed
+            .entry(user_id)
+            .or_default()
+            .insert(client_order_id, closed);
+    }
+
+    /// Drops what was kept for retries once an order is filled or cancelled, leaving only
+    /// the id and how it ended.
+    pub fn close(&mut self, order: &Order, status: ClosedStatus) {
+        let (Some(order_id), Some(client_order_id)) = (order.order_id, &order.client_order_id)
+        else {
+            return;
+        };
+        if let Some(orders) = self.by_user.get_mut(&order.user_id) {
+            orders.remove(client_order_id);
+            if orders.is_empty() {
+                self.by_user.remove(&order.user_id);
+            }
+        }
+        self.closed.entry(order.user_id).or_default().insert(
+            client_order_id.clone(),
+            ClosedClientOrder { order_id, status },
+        );
+    }
+}
+
+/// Whether a place request asks for the same order as one already placed. Market orders are
+/// priced by the engine at the time, so their price is left out.
+pub fn same_placement(request: &Order, placed: &Order) -> bool {
+    let same_price = match request.order_type {
+        OrderType::Market => matches!(placed.order_type, OrderType::Market),
+        OrderType::Limit => {
+            matches!(placed.order_type, OrderType::Limit) && request.price == placed.price
+        }
+    };
+    same_price
+        && request.market_id == placed.market_id
+        && matches!(
+            (&request.side, &placed.side),
+            (OrderSide::Bid, OrderSide::Bid) | (OrderSide::Ask, OrderSide::Ask)
+        )
+        && request.original_qty == placed.original_qty
+        && request.hidden == placed.hidden
+}
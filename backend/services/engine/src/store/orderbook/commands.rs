@@ -1,41 +1,80 @@
 use tokio::sync::oneshot;
 
-use crate::types::market_types::MarketMeta;
+use crate::store::orderbook::client_orders::{ClientOrder, ClosedClientOrder};
+use crate::types::deposit_types::Deposit;
+use crate::types::error_types::EngineError;
+use crate::types::market_types::{Market, MarketMeta, MarketStatus, MarketSummary};
 use crate::types::orderbook_types::{
-    EventOrderbookSnapshot, Order, OrderbookSnapshot, OutcomeOrderbookSnapshot,
+    EngineState, EventOrderbookSnapshot, Order, OrderbookL3Snapshot, OrderbookSnapshot,
+    OutcomeOrderbookSnapshot, SnapshotOptions, TopOfBook,
 };
 use crate::types::user_types::User;
+use crate::types::withdrawal_types::{Withdrawal, WithdrawalDecision};
 
 #[derive(Debug)]
 pub enum Command {
-    PlaceOrder(Order, oneshot::Sender<Result<Order, String>>),
-    CancelOrder(u64, u64, oneshot::Sender<Result<Order, String>>),
-    ModifyOrder(Order, oneshot::Sender<Result<Order, String>>),
+    PlaceOrder(Order, oneshot::Sender<Result<Order, EngineError>>),
+    RestoreOrder(Order, oneshot::Sender<Result<Order, EngineError>>),
+    RestoreClientOrder(u64, String, ClosedClientOrder, oneshot::Sender<()>),
+    CancelOrder(u64, u64, oneshot::Sender<Result<Order, EngineError>>),
+    ModifyOrder(Order, oneshot::Sender<Result<Order, EngineError>>),
 
-    GetBestBid(u64, oneshot::Sender<Result<u64, String>>),
-    GetBestAsk(u64, oneshot::Sender<Result<u64, String>>),
-    GetOrderBook(u64, oneshot::Sender<Result<OrderbookSnapshot, String>>),
-    GetOrderbooksByEvent(u64, oneshot::Sender<Result<EventOrderbookSnapshot, String>>),
+    GetBestBid(u64, oneshot::Sender<Result<u64, EngineError>>),
+    GetBestAsk(u64, oneshot::Sender<Result<u64, EngineError>>),
+    GetOrderBook(
+        u64,
+        SnapshotOptions,
+        oneshot::Sender<Result<OrderbookSnapshot, EngineError>>,
+    ),
+    GetOrderBookL3(
+        u64,
+        oneshot::Sender<Result<OrderbookL3Snapshot, EngineError>>,
+    ),
+    GetOrderbooksByEvent(
+        u64,
+        SnapshotOptions,
+        oneshot::Sender<Result<EventOrderbookSnapshot, EngineError>>,
+    ),
     GetOrderbooksByOutcome(
         u64,
-        oneshot::Sender<Result<OutcomeOrderbookSnapshot, String>>,
+        SnapshotOptions,
+        oneshot::Sender<Result<OutcomeOrderbookSnapshot, EngineError>>,
+    ),
+    GetTopOfBook(Option<Vec<u64>>, oneshot::Sender<Vec<TopOfBook>>),
+    ListMarkets(
+        Option<MarketStatus>,
+        Option<u64>,
+        oneshot::Sender<Vec<MarketSummary>>,
     ),
-    GetUserOpenOrders(u64, oneshot::Sender<Result<Vec<Order>, String>>),
-    GetOrderStatus(u64, oneshot::Sender<Result<Order, String>>),
-    AddUser(User, oneshot::Sender<Option<User>>),
+    GetUserOpenOrders(u64, oneshot::Sender<Result<Vec<Order>, EngineError>>),
+    GetOrderStatus(u64, oneshot::Sender<Result<Order, EngineError>>),
+    GetClientOrder(u64, String, oneshot::Sender<Option<ClientOrder>>),
+    GetMarket(u64, oneshot::Sender<Option<Market>>),
+    AddUser(Box<User>, oneshot::Sender<Option<User>>),
     GetUserByEmail(String, oneshot::Sender<Option<User>>),
     GetUserById(u64, oneshot::Sender<Option<User>>),
-    GetBalance(u64, oneshot::Sender<Result<i64, String>>),
-    UpdateBalance(u64, i64, oneshot::Sender<Result<(), String>>),
-    GetPosition(u64, u64, oneshot::Sender<Result<u64, String>>),
-    GetUserPositions(
-        u64,
-        oneshot::Sender<Result<::std::collections::HashMap<u64, u64>, String>>,
+    GetBalance(u64, oneshot::Sender<Result<i64, EngineError>>),
+    UpdateBalance(u64, i64, oneshot::Sender<Result<(), EngineError>>),
+    RequestWithdrawal(Withdrawal, oneshot::Sender<Result<Withdrawal, EngineError>>),
+    ApproveWithdrawal(
+        WithdrawalDecision,
+        oneshot::Sender<Result<Withdrawal, EngineError>>,
+    ),
+    RejectWithdrawal(
+        WithdrawalDecision,
+        oneshot::Sender<Result<Withdrawal, EngineError>>,
     ),
-    UpdatePosition(u64, u64, i64, oneshot::Sender<Result<(), String>>),
-    CheckPositionSufficient(u64, u64, u64, oneshot::Sender<Result<bool, String>>),
-    CreateSplitPosition(u64, u64, u64, u64, oneshot::Sender<Result<(), String>>),
-    MergePosition(u64, u64, u64, oneshot::Sender<Result<(), String>>),
-    InitMarkets(Vec<MarketMeta>, oneshot::Sender<Result<(), String>>),
-    CloseEventMarkets(u64, u64, oneshot::Sender<Result<(), String>>),
+    CreateDeposit(Deposit, oneshot::Sender<Result<Deposit, EngineError>>),
+    ConfirmDeposit(String, oneshot::Sender<Result<Deposit, EngineError>>),
+    FailDeposit(String, oneshot::Sender<Result<Deposit, EngineError>>),
+    UpdatePosition(u64, u64, i64, oneshot::Sender<Result<(), EngineError>>),
+    CheckPositionSufficient(u64, u64, u64, oneshot::Sender<Result<bool, EngineError>>),
+    CreateSplitPosition(u64, u64, u64, u64, oneshot::Sender<Result<(), EngineError>>),
+    MergePosition(u64, u64, u64, oneshot::Sender<Result<(), EngineError>>),
+    InitMarkets(Vec<MarketMeta>, oneshot::Sender<Result<(), EngineError>>),
+    CloseEventMarkets(u64, u64, oneshot::Sender<Result<(), EngineError>>),
+    RestoreSequence(u64, oneshot::Sender<()>),
+    ExportState(oneshot::Sender<EngineState>),
+    SetStreamPosition(String, oneshot::Sender<()>),
+    GetStreamPosition(oneshot::Sender<Option<String>>),
 }
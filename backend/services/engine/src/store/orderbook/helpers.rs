@@ -1,14 +1,25 @@
 use crate::store::market::MarketStore;
+use crate::types::error_types::EngineError;
 use crate::types::market_types::MarketSide;
-use crate::types::orderbook_types::{Order, OrderSide};
+use crate::types::orderbook_types::{Order, OrderSide, OrderType};
 
-pub fn normalize_order(order: &mut Order, market_store: &MarketStore) -> Result<u64, String> {
+// Hidden orders rest on the book, so they have to carry a limit price.
+pub fn validate_order(order: &Order) -> Result<(), EngineError> {
+    if order.hidden && matches!(order.order_type, OrderType::Market) {
+        return Err(EngineError::InvalidRequest(
+            "Hidden orders must be limit orders".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn normalize_order(order: &mut Order, market_store: &MarketStore) -> Result<u64, EngineError> {
     let Some(market) = market_store.get_market(order.market_id) else {
-        return Err("Market not found".into());
+        return Err(EngineError::MarketNotFound);
     };
 
     if order.price > 100 {
-        return Err("Price must be between 0 and 100".into());
+        return Err(EngineError::InvalidPrice);
     }
 
     if let Some(side) = &market.side {
@@ -44,3 +55,20 @@ pub fn denormalize_price(market_id: u64, canonical_price: u64, market_store: &Ma
         canonical_price
     }
 }
+
+/// NO markets rest on the YES book with mirrored prices, so in their own terms the YES bids
+/// are their asks and the YES asks their bids. Snapshots, deltas and best prices all follow
+/// that.
+pub fn is_no_market(market_id: u64, market_store: &MarketStore) -> bool {
+    market_store
+        .get_market(market_id)
+        .is_some_and(|market| market.side == Some(MarketSide::No))
+}
+
+pub fn denormalize_side(market_id: u64, side: &OrderSide, market_store: &MarketStore) -> OrderSide {
+    match (is_no_market(market_id, market_store), side) {
+        (true, OrderSide::Bid) => OrderSide::Ask,
+        (true, OrderSide::Ask) => OrderSide::Bid,
+        (false, side) => side.clone(),
+    }
+}
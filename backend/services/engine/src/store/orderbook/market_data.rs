@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::services::market_data_publisher::publish_market_data;
+use crate::store::context::EngineContext;
+use crate::store::market::MarketStore;
+use crate::store::orderbook::helpers::{denormalize_price, denormalize_side};
+use crate::types::market_data_types::{
+    BookDelta, MarketDataEvent, MarketStatusUpdate, OrderFeedUpdate, TradePrint,
+};
+use crate::types::market_types::{MarketSide, MarketStatus};
+use crate::types::orderbook_types::{LevelDelta, OrderFeedEntry, OrderbookData};
+
+// Drains the canonical book's level deltas and order feed. Both markets of a pair read that
+// book, so each gets them with prices and sides in its own terms, the same way snapshots are
+// built.
+pub async fn publish_book_changes(
+    canonical_id: u64,
+    orderbooks: &mut HashMap<u64, OrderbookData>,
+    market_store: &MarketStore,
+    context: &EngineContext,
+) {
+    let Some(book) = orderbooks.get_mut(&canonical_id) else {
+        return;
+    };
+    let deltas = book.depth.take();
+    let entries = book.order_feed.take();
+
+    for market_id in market_pair(canonical_id, market_store) {
+        let market = market_store.get_market(market_id);
+        let outcome_id = market.as_ref().and_then(|m| m.outcome_id);
+        let event_id = market.as_ref().and_then(|m| m.event_id);
+
+        if !deltas.is_empty() {
+            let deltas = deltas
+                .iter()
+                .map(|delta| LevelDelta {
+                    side: denormalize_side(market_id, &delta.side, market_store),
+                    price: denormalize_price(market_id, delta.price, market_store),
+                    ..delta.clone()
+                })
+                .collect();
+            publish_market_data(MarketDataEvent::Delta(BookDelta {
+                market_id,
+                outcome_id,
+                event_id,
+                deltas,
+                timestamp: context.now(),
+            }))
+            .await;
+        }
+
+        if !entries.is_empty() {
+            let entries = entries
+                .iter()
+                .map(|entry| OrderFeedEntry {
+                    side: denormalize_side(market_id, &entry.side, market_store),
+                    price: denormalize_price(market_id, entry.price, market_store),
+                    ..entry.clone()
+                })
+                .collect();
+            publish_market_data(MarketDataEvent::Orders(OrderFeedUpdate {
+                market_id,
+                outcome_id,
+                event_id,
+                entries,
+                timestamp: context.now(),
+            }))
+            .await;
+        }
+    }
+}
+
+// `print` is in canonical terms; the paired NO market gets it with the price mirrored and
+// the taker side flipped.
+pub async fn publish_trade_print(print: TradePrint, market_store: &MarketStore) {
+    for market_id in market_pair(print.market_id, market_store) {
+        let market = market_store.get_market(market_id);
+        let mirrored = matches!(
+            market.as_ref().and_then(|m| m.side.as_ref()),
+            Some(MarketSide::No)
+        );
+        let taker_side = match (mirrored, print.taker_side.as_str()) {
+            (true, "Bid") => "Ask".to_string(),
+            (true, "Ask") => "Bid".to_string(),
+            (_, side) => side.to_string(),
+        };
+        publish_market_data(MarketDataEvent::Trade(TradePrint {
+            market_id,
+            outcome_id: market.as_ref().and_then(|m| m.outcome_id),
+            event_id: market.as_ref().and_then(|m| m.event_id),
+            price: denormalize_price(market_id, print.price, market_store),
+            taker_side,
+            ..print.clone()
+        }))
+        .await;
+    }
+}
+
+pub async fn publish_market_status(
+    market_id: u64,
+    status: MarketStatus,
+    market_store: &MarketStore,
+    context: &EngineContext,
+) {
+    let market = market_store.get_market(market_id);
+    publish_market_data(MarketDataEvent::Status(MarketStatusUpdate {
+        market_id,
+        outcome_id: market.as_ref().and_then(|m| m.outcome_id),
+        event_id: market.as_ref().and_then(|m| m.event_id),
+        status,
+        timestamp: context.now(),
+    }))
+    .await;
+}
+
+fn market_pair(market_id: u64, market_store: &MarketStore) -> Vec<u64> {
+    let paired = market_store
+        .get_market(market_id)
+        .and_then(|m| m.paired_market_id);
+    std::iter::once(market_id).chain(paired).collect()
+}
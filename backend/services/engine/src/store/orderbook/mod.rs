@@ -1,8 +1,15 @@
 mod actor;
 mod api;
+mod client_orders;
 mod commands;
 mod helpers;
+pub(crate) mod market_data;
+mod persistence;
 mod snapshot;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 pub use actor::spawn_orderbook_actor;
 pub use api::Orderbook;
+pub use client_orders::{ClientOrder, ClosedClientOrder, ClosedStatus};
+pub use persistence::Persistence;
@@ -0,0 +1,438 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::store::market::MarketStore;
+use crate::store::orderbook::client_orders::{ClientOrderIndex, ClosedClientOrder};
+use crate::store::orderbook::commands::Command;
+use crate::types::deposit_types::Deposit;
+use crate::types::market_types::{Market, MarketMeta};
+use crate::types::orderbook_types::{Order, OrderbookData};
+use crate::types::user_types::User;
+use crate::types::withdrawal_types::{Withdrawal, WithdrawalDecision};
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.json.tmp";
+const JOURNAL_FILE: &str = "journal.log";
+const DEFAULT_DATA_DIR: &str = "engine_data";
+const DEFAULT_SNAPSHOT_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JournalEntry {
+    PlaceOrder(Order),
+    RestoreOrder(Order),
+    RestoreClientOrder(u64, String, ClosedClientOrder),
+    CancelOrder(u64, u64),
+    ModifyOrder(Order),
+    AddUser(Box<User>),
+    UpdateBalance(u64, i64),
+    RequestWithdrawal(Withdrawal),
+    ApproveWithdrawal(WithdrawalDecision),
+    RejectWithdrawal(WithdrawalDecision),
+    CreateDeposit(Deposit),
+    ConfirmDeposit(String),
+    FailDeposit(String),
+    UpdatePosition(u64, u64, i64),
+    CreateSplitPosition(u64, u64, u64, u64),
+    MergePosition(u64, u64, u64),
+    InitMarkets(Vec<MarketMeta>),
+    CloseEventMarkets(u64, u64),
+    RestoreSequence(u64),
+}
+
+impl JournalEntry {
+    pub fn from_command(cmd: &Command) -> Option<Self> {
+        let entry = match cmd {
+            Command::PlaceOrder(order, _) => JournalEntry::PlaceOrder(order.clone()),
+            Command::RestoreOrder(order, _) => JournalEntry::RestoreOrder(order.clone()),
+            Command::RestoreClientOrder(user_id, client_order_id, closed, _) => {
+                JournalEntry::RestoreClientOrder(*user_id, client_order_id.clone(), closed.clone())
+            }
+            Command::CancelOrder(market_id, order_id, _) => {
+                JournalEntry::CancelOrder(*market_id, *order_id)
+            }
+            Command::ModifyOrder(order, _) => JournalEntry::ModifyOrder(order.clone()),
+            Command::AddUser(user, _) => JournalEntry::AddUser(user.clone()),
+            Command::UpdateBalance(user_id, amount, _) => {
+                JournalEntry::UpdateBalance(*user_id, *amount)
+            }
+            Command::RequestWithdrawal(withdrawal, _) => {
+                JournalEntry::RequestWithdrawal(withdrawal.clone())
+            }
+            Command::ApproveWithdrawal(decision, _) => {
+                JournalEntry::ApproveWithdrawal(decision.clone())
+            }
+            Command::RejectWithdrawal(decision, _) => {
+                JournalEntry::RejectWithdrawal(decision.clone())
+            }
+            Command::CreateDeposit(deposit, _) => JournalEntry::CreateDeposit(deposit.clone()),
+            Command::ConfirmDeposit(deposit_id, _) => {
+                JournalEntry::ConfirmDeposit(deposit_id.clone())
+            }
+            Command::FailDeposit(deposit_id, _) => JournalEntry::FailDeposit(deposit_id.clone()),
+            Command::UpdatePosition(user_id, market_id, amount, _) => {
+                JournalEntry::UpdatePosition(*user_id, *market_id, *amount)
+            }
+            Command::CreateSplitPosition(user_id, market1_id, market2_id, amount, _) => {
+                JournalEntry::CreateSplitPosition(*user_id, *market1_id, *market2_id, *amount)
+            }
+            Command::MergePosition(user_id, market1_id, market2_id, _) => {
+                JournalEntry::MergePosition(*user_id, *market1_id, *market2_id)
+            }
+            Command::InitMarkets(metas, _) => JournalEntry::InitMarkets(metas.clone()),
+            Command::CloseEventMarkets(event_id, winning_outcome_id, _) => {
+                JournalEntry::CloseEventMarkets(*event_id, *winning_outcome_id)
+            }
+            Command::RestoreSequence(sequence, _) => JournalEntry::RestoreSequence(*sequence),
+            _ => return None,
+        };
+        Some(entry)
+    }
+
+    // Replayed commands have nobody waiting on them, so the receivers are dropped.
+    pub fn into_command(self) -> Command {
+        match self {
+            JournalEntry::PlaceOrder(order) => Command::PlaceOrder(order, oneshot::channel().0),
+            JournalEntry::RestoreOrder(order) => Command::RestoreOrder(order, oneshot::channel().0),
+            JournalEntry::RestoreClientOrder(user_id, client_order_id, closed) => {
+                Command::RestoreClientOrder(user_id, client_order_id, closed, oneshot::channel().0)
+            }
+            JournalEntry::CancelOrder(market_id, order_id) => {
+                Command::CancelOrder(market_id, order_id, oneshot::channel().0)
+            }
+            JournalEntry::ModifyOrder(order) => Command::ModifyOrder(order, oneshot::channel().0),
+            JournalEntry::AddUser(user) => Command::AddUser(user, oneshot::channel().0),
+            JournalEntry::UpdateBalance(user_id, amount) => {
+                Command::UpdateBalance(user_id, amount, oneshot::channel().0)
+            }
+            JournalEntry::RequestWithdrawal(withdrawal) => {
+                Command::RequestWithdrawal(withdrawal, oneshot::channel().0)
+            }
+            JournalEntry::ApproveWithdrawal(decision) => {
+                Command::ApproveWithdrawal(decision, oneshot::channel().0)
+            }
+            JournalEntry::RejectWithdrawal(decision) => {
+                Command::RejectWithdrawal(decision, oneshot::channel().0)
+            }
+            JournalEntry::CreateDeposit(deposit) => {
+                Command::CreateDeposit(deposit, oneshot::channel().0)
+            }
+            JournalEntry::ConfirmDeposit(deposit_id) => {
+                Command::ConfirmDeposit(deposit_id, oneshot::channel().0)
+            }
+            JournalEntry::FailDeposit(deposit_id) => {
+                Command::FailDeposit(deposit_id, oneshot::channel().0)
+            }
+            JournalEntry::UpdatePosition(user_id, market_id, amount) => {
+                Command::UpdatePosition(user_id, market_id, amount, oneshot::channel().0)
+            }
+            JournalEntry::CreateSplitPosition(user_id, market1_id, market2_id, amount) => {
+                Command::CreateSplitPosition(
+                    user_id,
+                    market1_id,
+                    market2_id,
+                    amount,
+                    oneshot::channel().0,
+                )
+            }
+            JournalEntry::MergePosition(user_id, market1_id, market2_id) => {
+                Command::MergePosition(user_id, market1_id, market2_id, oneshot::channel().0)
+            }
+            JournalEntry::InitMarkets(metas) => Command::InitMarkets(metas, oneshot::channel().0),
+            JournalEntry::CloseEventMarkets(event_id, winning_outcome_id) => {
+                Command::CloseEventMarkets(event_id, winning_outcome_id, oneshot::channel().0)
+            }
+            JournalEntry::RestoreSequence(sequence) => {
+                Command::RestoreSequence(sequence, oneshot::channel().0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalRecord {
+    seq: u64,
+    #[serde(default)]
+    stream_id: Option<String>,
+    entry: JournalEntry,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EngineSnapshot {
+    pub last_seq: u64,
+    pub orderbooks: HashMap<u64, OrderbookData>,
+    pub users: HashMap<u64, User>,
+    pub alias_map: HashMap<u64, u64>,
+    pub order_original_market: HashMap<u64, u64>,
+    pub markets: Vec<Market>,
+    #[serde(default)]
+    pub client_orders: ClientOrderIndex,
+    #[serde(default)]
+    pub stream_id: Option<String>,
+    #[serde(default)]
+    pub event_sequence: u64,
+}
+
+#[derive(Serialize)]
+struct EngineSnapshotRef<'a> {
+    last_seq: u64,
+    orderbooks: &'a HashMap<u64, OrderbookData>,
+    users: &'a HashMap<u64, User>,
+    alias_map: &'a HashMap<u64, u64>,
+    order_original_market: &'a HashMap<u64, u64>,
+    markets: Vec<Market>,
+    client_orders: &'a ClientOrderIndex,
+    stream_id: Option<&'a str>,
+    event_sequence: u64,
+}
+
+pub struct Persistence {
+    dir: PathBuf,
+    snapshot_interval: Duration,
+    journal: Option<BufWriter<File>>,
+    next_seq: u64,
+    // `server_requests` entry being handled, and the last one that changed engine state
+    stream_id: Option<String>,
+    applied_stream_id: Option<String>,
+}
+
+impl Persistence {
+    pub fn new(dir: impl Into<PathBuf>, snapshot_interval: Duration) -> Result<Self, String> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create engine data dir {}: {}", dir.display(), e))?;
+
+        Ok(Self {
+            dir,
+            snapshot_interval,
+            journal: None,
+            next_seq: 1,
+            stream_id: None,
+            applied_stream_id: None,
+        })
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let dir = env::var("ENGINE_DATA_DIR").unwrap_or_else(|_| DEFAULT_DATA_DIR.to_string());
+        let interval_secs = env::var("ENGINE_SNAPSHOT_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_SNAPSHOT_INTERVAL_SECS);
+
+        Self::new(dir, Duration::from_secs(interval_secs))
+    }
+
+    pub fn snapshot_interval(&self) -> Duration {
+        self.snapshot_interval
+    }
+
+    pub fn set_stream_id(&mut self, stream_id: String) {
+        self.stream_id = Some(stream_id);
+    }
+
+    /// The last `server_requests` entry whose commands are covered by the snapshot and journal.
+    pub fn applied_stream_id(&self) -> Option<&str> {
+        self.applied_stream_id.as_deref()
+    }
+
+    pub fn has_saved_state(&self) -> bool {
+        let journal_len = fs::metadata(self.dir.join(JOURNAL_FILE))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        self.dir.join(SNAPSHOT_FILE).exists() || journal_len > 0
+    }
+
+    pub fn recover(&mut self) -> Result<(Option<EngineSnapshot>, Vec<JournalEntry>), String> {
+        let snapshot_path = self.dir.join(SNAPSHOT_FILE);
+        let snapshot = if snapshot_path.exists() {
+            let file = File::open(&snapshot_path)
+                .map_err(|e| format!("Failed to open snapshot: {}", e))?;
+            let snapshot: EngineSnapshot = serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| format!("Failed to parse snapshot: {}", e))?;
+            Some(snapshot)
+        } else {
+            None
+        };
+
+        let last_seq = snapshot.as_ref().map(|s| s.last_seq).unwrap_or(0);
+        self.next_seq = last_seq + 1;
+        self.applied_stream_id = snapshot.as_ref().and_then(|s| s.stream_id.clone());
+
+        let mut entries = Vec::new();
+        let journal_path = self.dir.join(JOURNAL_FILE);
+        if journal_path.exists() {
+            let file =
+                File::open(&journal_path).map_err(|e| format!("Failed to open journal: {}", e))?;
+
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| format!("Failed to read journal: {}", e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                // A torn final write is expected after a crash; everything before it is intact
+                let record: JournalRecord = match serde_json::from_str(&line) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        warn!("Stopping journal replay at unreadable record: {}", e);
+                        break;
+                    }
+                };
+
+                if record.seq <= last_seq {
+                    continue;
+                }
+                self.next_seq = record.seq + 1;
+                if record.stream_id.is_some() {
+                    self.applied_stream_id = record.stream_id;
+                }
+                entries.push(record.entry);
+            }
+        }
+
+        Ok((snapshot, entries))
+    }
+
+    pub fn append(&mut self, entry: JournalEntry) -> Result<(), String> {
+        if self.journal.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.dir.join(JOURNAL_FILE))
+                .map_err(|e| format!("Failed to open journal: {}", e))?;
+            self.journal = Some(BufWriter::new(file));
+        }
+
+        let record = JournalRecord {
+            seq: self.next_seq,
+            stream_id: self.stream_id.clone(),
+            entry,
+        };
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize journal entry: {}", e))?;
+
+        let Some(journal) = self.journal.as_mut() else {
+            return Err("Journal not open".into());
+        };
+        writeln!(journal, "{}", line)
+            .and_then(|_| journal.flush())
+            .map_err(|e| format!("Failed to write journal: {}", e))?;
+
+        self.next_seq += 1;
+        if self.stream_id.is_some() {
+            self.applied_stream_id = self.stream_id.clone();
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_snapshot(
+        &mut self,
+        orderbooks: &HashMap<u64, OrderbookData>,
+        users: &HashMap<u64, User>,
+        alias_map: &HashMap<u64, u64>,
+        order_original_market: &HashMap<u64, u64>,
+        market_store: &MarketStore,
+        client_orders: &ClientOrderIndex,
+        event_sequence: u64,
+    ) -> Result<(), String> {
+        let snapshot = EngineSnapshotRef {
+            last_seq: self.next_seq - 1,
+            orderbooks,
+            users,
+            alias_map,
+            order_original_market,
+            markets: market_store.list_markets(),
+            client_orders,
+            stream_id: self.applied_stream_id.as_deref(),
+            event_sequence,
+        };
+
+        let tmp_path = self.dir.join(SNAPSHOT_TMP_FILE);
+        let file =
+            File::create(&tmp_path).map_err(|e| format!("Failed to create snapshot: {}", e))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &snapshot)
+            .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+        let file = writer
+            .into_inner()
+            .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to sync snapshot: {}", e))?;
+        fs::rename(&tmp_path, self.dir.join(SNAPSHOT_FILE))
+            .map_err(|e| format!("Failed to replace snapshot: {}", e))?;
+
+        // Entries up to last_seq are now covered by the snapshot and skipped on recovery
+        // even if the process dies before the journal is truncated.
+        let file = File::create(self.dir.join(JOURNAL_FILE))
+            .map_err(|e| format!("Failed to truncate journal: {}", e))?;
+        self.journal = Some(BufWriter::new(file));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::db_event_publisher::ENGINE_TEST_LOCK;
+    use crate::store::orderbook::test_support::{NO, YES, limit, open_market, spawn_engine};
+    use crate::types::orderbook_types::OrderSide;
+    use std::process;
+
+    #[tokio::test]
+    async fn snapshot_and_journal_restore_the_exported_state() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let dir = env::temp_dir().join(format!("engine-recovery-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let persistence = || Persistence::new(&dir, Duration::from_secs(3600)).unwrap();
+
+        let first = spawn_engine(Some(persistence()));
+        open_market(&first, &[1, 2, 3]).await;
+        let bid = first
+            .place_order(limit(1, YES, OrderSide::Bid, 55, 10))
+            .await
+            .unwrap();
+        first.create_split_postion(2, YES, NO, 10).await.unwrap();
+        drop(first);
+
+        // Recovering from the journal alone writes a snapshot before the next command runs,
+        // so what this engine journals afterwards has to be replayed on top of that snapshot
+        let second = spawn_engine(Some(persistence()));
+        second
+            .place_order(limit(2, YES, OrderSide::Ask, 55, 4))
+            .await
+            .unwrap();
+        second
+            .place_order(limit(2, NO, OrderSide::Ask, 40, 3))
+            .await
+            .unwrap();
+        second
+            .cancel_order(YES, bid.order_id.unwrap())
+            .await
+            .unwrap();
+        second.update_balance(3, 500).await.unwrap();
+        let expected = second.export_state().await.unwrap();
+        drop(second);
+        assert_eq!(expected.orderbooks[&YES].orders.len(), 1);
+
+        assert!(dir.join(SNAPSHOT_FILE).exists());
+        assert!(fs::metadata(dir.join(JOURNAL_FILE)).unwrap().len() > 0);
+
+        let third = spawn_engine(Some(persistence()));
+        let restored = third.export_state().await.unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            serde_json::to_value(&restored).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+    }
+}
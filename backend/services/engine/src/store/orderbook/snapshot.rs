@@ -1,38 +1,61 @@
 use std::collections::HashMap;
 
 use crate::store::market::MarketStore;
-use crate::store::orderbook::helpers::denormalize_price;
-use crate::types::orderbook_types::{Level, OrderbookData, OrderbookSnapshot};
+use crate::store::order_feed::public_order_id;
+use crate::store::orderbook::helpers::{denormalize_price, is_no_market};
+use crate::types::error_types::EngineError;
+use crate::types::orderbook_types::{
+    L3Level, L3Order, Level, OrderQueue, OrderbookData, OrderbookL3Snapshot, OrderbookSnapshot,
+    SnapshotOptions, TopOfBook,
+};
 
 pub fn build_orderbook_snapshot(
     market_id: u64,
+    options: &SnapshotOptions,
     alias_map: &HashMap<u64, u64>,
     orderbooks: &HashMap<u64, OrderbookData>,
     market_store: &MarketStore,
-) -> Result<OrderbookSnapshot, String> {
+) -> Result<OrderbookSnapshot, EngineError> {
+    if options.depth == Some(0) {
+        return Err(EngineError::InvalidRequest(
+            "Depth must be at least 1".into(),
+        ));
+    }
+    if options.group.is_some_and(|group| group == 0 || group > 100) {
+        return Err(EngineError::InvalidRequest(
+            "Group must be between 1 and 100".into(),
+        ));
+    }
+
     let canonical_id = alias_map.get(&market_id).copied().unwrap_or(market_id);
     let book = orderbooks
         .get(&canonical_id)
-        .ok_or_else(|| "Market not found".to_string())?;
-
-    let bids: Vec<Level> = book
-        .bids
-        .iter()
-        .rev()
-        .map(|(price, quantity)| Level {
-            price: denormalize_price(market_id, *price, market_store),
-            quantity: *quantity,
-        })
-        .collect();
+        .ok_or(EngineError::MarketNotFound)?;
 
-    let asks: Vec<Level> = book
-        .asks
-        .iter()
-        .map(|(price, quantity)| Level {
-            price: denormalize_price(market_id, *price, market_store),
-            quantity: *quantity,
-        })
-        .collect();
+    // Both sides are walked best first; for a NO market that means the YES asks become its
+    // bids and the YES bids its asks
+    let group = options.group.unwrap_or(1);
+    let yes_bids: Box<dyn Iterator<Item = (&u64, &u64)>> = Box::new(book.bids.iter().rev());
+    let yes_asks: Box<dyn Iterator<Item = (&u64, &u64)>> = Box::new(book.asks.iter());
+    let (bid_levels, ask_levels) = if is_no_market(market_id, market_store) {
+        (yes_asks, yes_bids)
+    } else {
+        (yes_bids, yes_asks)
+    };
+    let bids = collect_levels(
+        bid_levels,
+        |price| bid_bucket(price, group),
+        market_id,
+        options,
+        market_store,
+    );
+    let asks = collect_levels(
+        ask_levels,
+        |price| ask_bucket(price, group),
+        market_id,
+        options,
+        market_store,
+    );
 
     let last_price = book
         .last_price
@@ -43,5 +66,313 @@ pub fn build_orderbook_snapshot(
         bids,
         asks,
         last_price,
+        sequence: book.sequence,
+        depth_sequence: book.depth.sequence,
+    })
+}
+
+pub fn build_top_of_book(
+    market_id: u64,
+    alias_map: &HashMap<u64, u64>,
+    orderbooks: &HashMap<u64, OrderbookData>,
+    market_store: &MarketStore,
+) -> Option<TopOfBook> {
+    let options = SnapshotOptions {
+        depth: Some(1),
+        ..SnapshotOptions::default()
+    };
+    let snapshot =
+        build_orderbook_snapshot(market_id, &options, alias_map, orderbooks, market_store).ok()?;
+
+    Some(TopOfBook {
+        market_id: snapshot.market_id,
+        best_bid: snapshot.bids.into_iter().next(),
+        best_ask: snapshot.asks.into_iter().next(),
+        last_price: snapshot.last_price,
+    })
+}
+
+fn bid_bucket(price: u64, group: u64) -> u64 {
+    price - price % group
+}
+
+// Rounding up can pass 100 when `group` does not divide it, so the top band is capped there.
+fn ask_bucket(price: u64, group: u64) -> u64 {
+    (price.div_ceil(group) * group).min(100)
+}
+
+// Levels arrive best first, so grouped prices stay contiguous and the walk can stop as soon
+// as `depth` levels are filled.
+fn collect_levels<'a>(
+    levels: impl Iterator<Item = (&'a u64, &'a u64)>,
+    bucket: impl Fn(u64) -> u64,
+    market_id: u64,
+    options: &SnapshotOptions,
+    market_store: &MarketStore,
+) -> Vec<Level> {
+    let depth = options.depth.unwrap_or(usize::MAX);
+    let mut result: Vec<Level> = Vec::new();
+
+    for (price, quantity) in levels {
+        let price = bucket(denormalize_price(market_id, *price, market_store));
+        if let Some(level) = result.last_mut()
+            && level.price == price
+        {
+            level.quantity += quantity;
+            continue;
+        }
+        if result.len() == depth {
+            break;
+        }
+        result.push(Level {
+            price,
+            quantity: *quantity,
+            cumulative_quantity: None,
+        });
+    }
+
+    if options.cumulative {
+        let mut total = 0;
+        for level in &mut result {
+            total += level.quantity;
+            level.cumulative_quantity = Some(total);
+        }
+    }
+
+    result
+}
+
+pub fn build_l3_snapshot(
+    market_id: u64,
+    alias_map: &HashMap<u64, u64>,
+    orderbooks: &HashMap<u64, OrderbookData>,
+    market_store: &MarketStore,
+) -> Result<OrderbookL3Snapshot, EngineError> {
+    let canonical_id = alias_map.get(&market_id).copied().unwrap_or(market_id);
+    let book = orderbooks
+        .get(&canonical_id)
+        .ok_or(EngineError::MarketNotFound)?;
+
+    let level = |price: u64, queue: Option<&OrderQueue>| L3Level {
+        price: denormalize_price(market_id, price, market_store),
+        orders: queue
+            .into_iter()
+            .flat_map(|queue| queue.iter())
+            .filter_map(|order_id| book.orders.get(&order_id))
+            .map(|order| L3Order {
+                order_id: public_order_id(order.order_id.unwrap_or_default()),
+                quantity: order.remaining_qty,
+            })
+            .collect(),
+    };
+
+    let yes_bids = book
+        .bids
+        .keys()
+        .rev()
+        .map(|price| level(*price, book.bid_queue.get(price)))
+        .collect();
+    let yes_asks = book
+        .asks
+        .keys()
+        .map(|price| level(*price, book.ask_queue.get(price)))
+        .collect();
+    let (bids, asks) = if is_no_market(market_id, market_store) {
+        (yes_asks, yes_bids)
+    } else {
+        (yes_bids, yes_asks)
+    };
+
+    Ok(OrderbookL3Snapshot {
+        market_id,
+        bids,
+        asks,
+        sequence: book.order_feed.sequence,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::orderbook_actions::add_order_to_book;
+    use crate::types::market_types::MarketMeta;
+    use crate::types::orderbook_types::{Order, OrderSide, OrderType};
+    use std::collections::BTreeMap;
+
+    const YES: u64 = 100;
+    const NO: u64 = 101;
+
+    struct Fixture {
+        market_store: MarketStore,
+        alias_map: HashMap<u64, u64>,
+        orderbooks: HashMap<u64, OrderbookData>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let market_store = MarketStore::new();
+            market_store
+                .register_market_pair(MarketMeta {
+                    event_id: 1,
+                    outcome_id: 10,
+                    yes_market_id: YES,
+                    no_market_id: NO,
+                })
+                .unwrap();
+            Self {
+                market_store,
+                alias_map: HashMap::from([(YES, YES), (NO, YES)]),
+                orderbooks: HashMap::from([(YES, OrderbookData::new(YES))]),
+            }
+        }
+
+        fn rest(&mut self, order_id: u64, side: OrderSide, price: u64, qty: u64, hidden: bool) {
+            let order = Order {
+                order_id: Some(order_id),
+                market_id: YES,
+                user_id: 1,
+                price,
+                original_qty: qty,
+                remaining_qty: qty,
+                side,
+                order_type: OrderType::Limit,
+                hidden,
+                client_order_id: None,
+            };
+            add_order_to_book(order_id, &order, self.orderbooks.get_mut(&YES).unwrap());
+        }
+
+        fn snapshot(&self, market_id: u64, options: SnapshotOptions) -> OrderbookSnapshot {
+            build_orderbook_snapshot(
+                market_id,
+                &options,
+                &self.alias_map,
+                &self.orderbooks,
+                &self.market_store,
+            )
+            .unwrap()
+        }
+    }
+
+    fn prices(levels: &[Level]) -> Vec<(u64, u64)> {
+        levels.iter().map(|l| (l.price, l.quantity)).collect()
+    }
+
+    #[test]
+    fn hidden_orders_stay_out_of_snapshots_and_top_of_book() {
+        let mut fixture = Fixture::new();
+        fixture.rest(1, OrderSide::Bid, 45, 5, false);
+        fixture.rest(2, OrderSide::Bid, 48, 7, true);
+        fixture.rest(3, OrderSide::Bid, 45, 2, true);
+        fixture.rest(4, OrderSide::Ask, 55, 5, false);
+        fixture.rest(5, OrderSide::Ask, 52, 7, true);
+
+        let snapshot = fixture.snapshot(YES, SnapshotOptions::default());
+        assert_eq!(prices(&snapshot.bids), vec![(45, 5)]);
+        assert_eq!(prices(&snapshot.asks), vec![(55, 5)]);
+
+        let top = build_top_of_book(
+            YES,
+            &fixture.alias_map,
+            &fixture.orderbooks,
+            &fixture.market_store,
+        )
+        .unwrap();
+        assert_eq!(top.best_bid.map(|l| l.price), Some(45));
+        assert_eq!(top.best_ask.map(|l| l.price), Some(55));
+
+        let l3 = build_l3_snapshot(
+            YES,
+            &fixture.alias_map,
+            &fixture.orderbooks,
+            &fixture.market_store,
+        )
+        .unwrap();
+        let l3_ids: Vec<String> = l3
+            .bids
+            .iter()
+            .chain(&l3.asks)
+            .flat_map(|level| level.orders.iter().map(|o| o.order_id.clone()))
+            .collect();
+        assert_eq!(l3_ids, vec![public_order_id(1), public_order_id(4)]);
+    }
+
+    #[test]
+    fn collect_levels_groups_trims_and_totals_levels() {
+        let fixture = Fixture::new();
+        let asks = BTreeMap::from([(91, 1), (93, 2), (97, 3), (99, 4)]);
+        let options = SnapshotOptions {
+            depth: Some(2),
+            group: Some(7),
+            cumulative: true,
+        };
+
+        let levels = collect_levels(
+            asks.iter(),
+            |price| ask_bucket(price, 7),
+            YES,
+            &options,
+            &fixture.market_store,
+        );
+
+        // 93 and 97 share the 98 band, and 99 is capped at 100 instead of rounding to 105
+        assert_eq!(prices(&levels), vec![(91, 1), (98, 5)]);
+        let totals: Vec<Option<u64>> = levels.iter().map(|l| l.cumulative_quantity).collect();
+        assert_eq!(totals, vec![Some(1), Some(6)]);
+    }
+
+    #[test]
+    fn grouped_asks_never_go_above_100() {
+        let mut fixture = Fixture::new();
+        fixture.rest(1, OrderSide::Ask, 99, 4, false);
+        fixture.rest(2, OrderSide::Bid, 3, 2, false);
+        let options = SnapshotOptions {
+            group: Some(7),
+            ..SnapshotOptions::default()
+        };
+
+        let snapshot = fixture.snapshot(YES, options);
+
+        assert_eq!(prices(&snapshot.asks), vec![(100, 4)]);
+        assert_eq!(prices(&snapshot.bids), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn a_no_market_shows_mirrored_yes_asks_as_its_bids_everywhere() {
+        let mut fixture = Fixture::new();
+        fixture.rest(1, OrderSide::Bid, 40, 5, false);
+        fixture.rest(2, OrderSide::Bid, 45, 2, false);
+        fixture.rest(3, OrderSide::Ask, 55, 3, false);
+        fixture.rest(4, OrderSide::Ask, 58, 4, false);
+
+        let snapshot = fixture.snapshot(NO, SnapshotOptions::default());
+        assert_eq!(prices(&snapshot.bids), vec![(45, 3), (42, 4)]);
+        assert_eq!(prices(&snapshot.asks), vec![(55, 2), (60, 5)]);
+
+        let top = build_top_of_book(
+            NO,
+            &fixture.alias_map,
+            &fixture.orderbooks,
+            &fixture.market_store,
+        )
+        .unwrap();
+        assert_eq!(top.best_bid.map(|l| l.price), Some(45));
+        assert_eq!(top.best_ask.map(|l| l.price), Some(55));
+
+        let l3 = build_l3_snapshot(
+            NO,
+            &fixture.alias_map,
+            &fixture.orderbooks,
+            &fixture.market_store,
+        )
+        .unwrap();
+        let ids = |levels: &[L3Level]| -> Vec<String> {
+            levels
+                .iter()
+                .flat_map(|level| level.orders.iter().map(|o| o.order_id.clone()))
+                .collect()
+        };
+        assert_eq!(ids(&l3.bids), vec![public_order_id(3), public_order_id(4)]);
+        assert_eq!(ids(&l3.asks), vec![public_order_id(2), public_order_id(1)]);
+    }
+}
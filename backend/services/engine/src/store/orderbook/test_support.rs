@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::store::context::{EngineContext, ManualClock, SequentialIdGenerator};
+use crate::store::market::MarketStore;
+use crate::store::orderbook::{Orderbook, Persistence, spawn_orderbook_actor};
+use crate::types::market_types::MarketMeta;
+use crate::types::orderbook_types::{Order, OrderSide, OrderType};
+use crate::types::user_types::User;
+
+pub const YES: u64 = 100;
+pub const NO: u64 = 101;
+pub const STARTING_BALANCE: i64 = 10_000;
+
+// Same clock and ids on every run, like a replay.
+pub fn spawn_engine(persistence: Option<Persistence>) -> Orderbook {
+    let context = EngineContext::new(
+        Arc::new(ManualClock::default()),
+        Arc::new(SequentialIdGenerator::new(1)),
+    );
+    spawn_orderbook_actor(MarketStore::new(), persistence, context)
+}
+
+/// Opens one YES/NO pair and funds `user_ids` with `STARTING_BALANCE` each.
+pub async fn open_market(orderbook: &Orderbook, user_ids: &[u64]) {
+    orderbook
+        .init_markets(vec![MarketMeta {
+            event_id: 1,
+            outcome_id: 10,
+            yes_market_id: YES,
+            no_market_id: NO,
+        }])
+        .await
+        .unwrap();
+
+    for &id in user_ids {
+        orderbook
+            .add_user(User {
+                id,
+                name: format!("user{}", id),
+                email: format!("user{}@example.com", id),
+                balance: STARTING_BALANCE,
+                positions: HashMap::new(),
+                locked_balance: 0,
+                locked_positions: HashMap::new(),
+                costs: HashMap::new(),
+                pending_withdrawals: HashMap::new(),
+                deposits: HashMap::new(),
+            })
+            .await
+            .unwrap();
+    }
+}
+
+pub fn limit(user_id: u64, market_id: u64, side: OrderSide, price: u64, qty: u64) -> Order {
+    Order {
+        order_id: None,
+        market_id,
+        user_id,
+        price,
+        original_qty: qty,
+        remaining_qty: qty,
+        side,
+        order_type: OrderType::Limit,
+        hidden: false,
+        client_order_id: None,
+    }
+}
@@ -4,28 +4,36 @@ pub fn remove_order_from_book(order_id: u64, order: &Order, book: &mut Orderbook
     let price = order.price;
     let qty = order.remaining_qty;
 
-    let queue_map = match order.side {
-        OrderSide::Ask => &mut book.ask_queue,
-        OrderSide::Bid => &mut book.bid_queue,
+    let queue_map = match (&order.side, order.hidden) {
+        (OrderSide::Ask, false) => &mut book.ask_queue,
+        (OrderSide::Bid, false) => &mut book.bid_queue,
+        (OrderSide::Ask, true) => &mut book.hidden_ask_queue,
+        (OrderSide::Bid, true) => &mut book.hidden_bid_queue,
     };
 
     if let Some(queue) = queue_map.get_mut(&price) {
-        queue.retain(|&id| id != order_id);
+        queue.remove(order_id);
         if queue.is_empty() {
             queue_map.remove(&price);
         }
     }
 
-    let map = match order.side {
-        OrderSide::Ask => &mut book.asks,
-        OrderSide::Bid => &mut book.bids,
+    let map = match (&order.side, order.hidden) {
+        (OrderSide::Ask, false) => &mut book.asks,
+        (OrderSide::Bid, false) => &mut book.bids,
+        (OrderSide::Ask, true) => &mut book.hidden_asks,
+        (OrderSide::Bid, true) => &mut book.hidden_bids,
     };
 
     if let Some(level_qty) = map.get_mut(&price) {
         *level_qty -= qty;
-        if *level_qty == 0 {
+        let remaining = *level_qty;
+        if remaining == 0 {
             map.remove(&price);
         }
+        if !order.hidden {
+            book.depth.record(order.side.clone(), price, remaining);
+        }
     }
 
     book.orders.remove(&order_id);
@@ -34,20 +42,21 @@ pub fn remove_order_from_book(order_id: u64, order: &Order, book: &mut Orderbook
 pub fn add_order_to_book(order_id: u64, order: &Order, book: &mut OrderbookData) {
     book.orders.insert(order_id, order.clone());
 
-    match order.side {
-        OrderSide::Bid => {
-            book.bid_queue
-                .entry(order.price)
-                .or_default()
-                .push(order_id);
-            *book.bids.entry(order.price).or_default() += order.remaining_qty;
-        }
-        OrderSide::Ask => {
-            book.ask_queue
-                .entry(order.price)
-                .or_default()
-                .push(order_id);
-            *book.asks.entry(order.price).or_default() += order.remaining_qty;
-        }
+    let (queue_map, map) = match (&order.side, order.hidden) {
+        (OrderSide::Bid, false) => (&mut book.bid_queue, &mut book.bids),
+        (OrderSide::Ask, false) => (&mut book.ask_queue, &mut book.asks),
+        (OrderSide::Bid, true) => (&mut book.hidden_bid_queue, &mut book.hidden_bids),
+        (OrderSide::Ask, true) => (&mut book.hidden_ask_queue, &mut book.hidden_asks),
+    };
+
+    queue_map
+        .entry(order.price)
+        .or_default()
+        .push_back(order_id);
+    let level_qty = map.entry(order.price).or_default();
+    *level_qty += order.remaining_qty;
+    if !order.hidden {
+        book.depth
+            .record(order.side.clone(), order.price, *level_qty);
     }
 }
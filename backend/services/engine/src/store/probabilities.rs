@@ -0,0 +1,231 @@
+use crate::types::market_types::MarketSide;
+use crate::types::orderbook_types::{
+    Arbitrage, EventOrderbookSnapshot, EventProbabilities, Level, OutcomeProbability,
+};
+
+const FULL_SET_PAYOUT: u64 = 100;
+
+// Outcomes of an event are mutually exclusive, so exactly one YES pays out. The sums only
+// mean something once every outcome is quoted, and a lone outcome can't be arbitraged.
+pub fn event_probabilities(snapshot: &EventOrderbookSnapshot) -> EventProbabilities {
+    let yes_books: Vec<_> = snapshot
+        .outcomes
+        .iter()
+        .map(|outcome| {
+            let yes = outcome
+                .markets
+                .iter()
+                .find(|market| market.side == Some(MarketSide::Yes));
+            (outcome.outcome_id, yes)
+        })
+        .collect();
+
+    let bids: Vec<Option<&Level>> = yes_books
+        .iter()
+        .map(|(_, yes)| yes.and_then(|market| market.snapshot.bids.first()))
+        .collect();
+    let asks: Vec<Option<&Level>> = yes_books
+        .iter()
+        .map(|(_, yes)| yes.and_then(|market| market.snapshot.asks.first()))
+        .collect();
+
+    let mut outcomes: Vec<OutcomeProbability> = yes_books
+        .iter()
+        .zip(bids.iter().zip(&asks))
+        .map(|((outcome_id, yes), (bid, ask))| {
+            let best_bid = bid.map(|level| level.price);
+            let best_ask = ask.map(|level| level.price);
+            let mid_price = match (best_bid, best_ask) {
+                (Some(bid), Some(ask)) => Some((bid + ask) as f64 / 2.0),
+                _ => yes
+                    .and_then(|market| market.snapshot.last_price)
+                    .map(|price| price as f64),
+            };
+            OutcomeProbability {
+                outcome_id: *outcome_id,
+                market_id: yes.map(|market| market.market_id),
+                best_bid,
+                best_ask,
+                mid_price,
+                probability: None,
+            }
+        })
+        .collect();
+
+    let mid_sum: f64 = outcomes.iter().filter_map(|o| o.mid_price).sum();
+    if mid_sum > 0.0 {
+        for outcome in &mut outcomes {
+            outcome.probability = outcome.mid_price.map(|mid| mid / mid_sum);
+        }
+    }
+
+    let yes_ask_sum = full_set(&asks).map(|(sum, _)| sum);
+    let yes_bid_sum = full_set(&bids).map(|(sum, _)| sum);
+    let arbitrage_possible = outcomes.len() > 1;
+
+    let buy_arbitrage = full_set(&asks)
+        .filter(|(sum, _)| arbitrage_possible && *sum < FULL_SET_PAYOUT)
+        .map(|(sum, quantity)| Arbitrage {
+            edge: FULL_SET_PAYOUT - sum,
+            quantity,
+        });
+    let sell_arbitrage = full_set(&bids)
+        .filter(|(sum, _)| arbitrage_possible && *sum > FULL_SET_PAYOUT)
+        .map(|(sum, quantity)| Arbitrage {
+            edge: sum - FULL_SET_PAYOUT,
+            quantity,
+        });
+
+    EventProbabilities {
+        event_id: snapshot.event_id,
+        outcomes,
+        yes_ask_sum,
+        yes_bid_sum,
+        overround: yes_ask_sum.map(|sum| sum as f64 / FULL_SET_PAYOUT as f64),
+        buy_arbitrage,
+        sell_arbitrage,
+    }
+}
+
+// Total price and tradable size of one level per outcome, or None if any outcome is missing.
+fn full_set(levels: &[Option<&Level>]) -> Option<(u64, u64)> {
+    if levels.is_empty() {
+        return None;
+    }
+    levels
+        .iter()
+        .try_fold((0, u64::MAX), |(sum, quantity), level| {
+            let level = (*level)?;
+            Some((sum + level.price, quantity.min(level.quantity)))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::orderbook_types::{
+        MarketOrderbookSnapshot, OrderbookSnapshot, OutcomeOrderbookSnapshot,
+    };
+
+    fn level(price: u64, quantity: u64) -> Level {
+        Level {
+            price,
+            quantity,
+            cumulative_quantity: None,
+        }
+    }
+
+    // Best YES level as (price, quantity), if any
+    type Quote = Option<(u64, u64)>;
+
+    // One outcome per entry, with its best YES bid and ask
+    fn event(quotes: &[(Quote, Quote)]) -> EventOrderbookSnapshot {
+        let outcomes = quotes
+            .iter()
+            .enumerate()
+            .map(|(i, (bid, ask))| {
+                let outcome_id = i as u64 + 1;
+                OutcomeOrderbookSnapshot {
+                    outcome_id,
+                    event_id: Some(1),
+                    markets: vec![MarketOrderbookSnapshot {
+                        market_id: outcome_id * 100,
+                        side: Some(MarketSide::Yes),
+                        snapshot: OrderbookSnapshot {
+                            market_id: outcome_id * 100,
+                            bids: bid.map(|(p, q)| level(p, q)).into_iter().collect(),
+                            asks: ask.map(|(p, q)| level(p, q)).into_iter().collect(),
+                            last_price: None,
+                            sequence: 0,
+                            depth_sequence: 0,
+                        },
+                    }],
+                }
+            })
+            .collect();
+        EventOrderbookSnapshot {
+            event_id: 1,
+            outcomes,
+        }
+    }
+
+    #[test]
+    fn probabilities_are_mids_normalized_to_one() {
+        let snapshot = event(&[
+            (Some((58, 10)), Some((62, 10))),
+            (Some((28, 10)), Some((32, 10))),
+            (Some((18, 10)), Some((22, 10))),
+        ]);
+
+        let result = event_probabilities(&snapshot);
+
+        let mids: Vec<Option<f64>> = result.outcomes.iter().map(|o| o.mid_price).collect();
+        assert_eq!(mids, vec![Some(60.0), Some(30.0), Some(20.0)]);
+        let probabilities: Vec<f64> = result
+            .outcomes
+            .iter()
+            .map(|o| o.probability.unwrap())
+            .collect();
+        assert_eq!(
+            probabilities,
+            vec![60.0 / 110.0, 30.0 / 110.0, 20.0 / 110.0]
+        );
+        assert_eq!(result.yes_ask_sum, Some(116));
+        assert_eq!(result.yes_bid_sum, Some(104));
+    }
+
+    #[test]
+    fn an_outcome_without_quotes_leaves_the_sums_empty() {
+        let snapshot = event(&[(Some((40, 5)), Some((45, 5))), (None, None)]);
+
+        let result = event_probabilities(&snapshot);
+
+        assert_eq!(result.outcomes[1].mid_price, None);
+        assert_eq!(result.outcomes[1].probability, None);
+        assert_eq!(result.outcomes[0].probability, Some(1.0));
+        assert_eq!(result.yes_ask_sum, None);
+        assert_eq!(result.yes_bid_sum, None);
+        assert_eq!(result.overround, None);
+        assert!(result.buy_arbitrage.is_none());
+        assert!(result.sell_arbitrage.is_none());
+    }
+
+    #[test]
+    fn a_single_outcome_has_no_arbitrage() {
+        let snapshot = event(&[(Some((90, 5)), Some((95, 5)))]);
+
+        let result = event_probabilities(&snapshot);
+
+        assert_eq!(result.yes_ask_sum, Some(95));
+        assert!(result.buy_arbitrage.is_none());
+        assert!(result.sell_arbitrage.is_none());
+    }
+
+    #[test]
+    fn buying_a_cheap_full_set_is_an_arbitrage() {
+        let snapshot = event(&[
+            (Some((40, 9)), Some((45, 7))),
+            (Some((40, 3)), Some((50, 12))),
+        ]);
+
+        let result = event_probabilities(&snapshot);
+
+        let buy = result.buy_arbitrage.unwrap();
+        assert_eq!((buy.edge, buy.quantity), (5, 7));
+        assert!(result.sell_arbitrage.is_none());
+    }
+
+    #[test]
+    fn selling_a_rich_full_set_is_an_arbitrage() {
+        let snapshot = event(&[
+            (Some((55, 9)), Some((60, 7))),
+            (Some((48, 4)), Some((50, 12))),
+        ]);
+
+        let result = event_probabilities(&snapshot);
+
+        let sell = result.sell_arbitrage.unwrap();
+        assert_eq!((sell.edge, sell.quantity), (3, 4));
+        assert!(result.buy_arbitrage.is_none());
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::services::db_event_publisher::publish_db_event;
+use crate::store::balance::publish_balance;
+use crate::store::context::EngineContext;
+use crate::types::db_event_types::{DbEvent, WithdrawalUpdatedEvent};
+use crate::types::error_types::EngineError;
+use crate::types::ledger_types::{LedgerAccount, LedgerEntry, LedgerKind};
+use crate::types::user_types::User;
+use crate::types::withdrawal_types::{Withdrawal, WithdrawalDecision, WithdrawalStatus};
+
+// Locks the amount until an admin decides; the id is assigned before the command is journaled.
+pub async fn request_withdrawal(
+    mut withdrawal: Withdrawal,
+    users: &mut HashMap<u64, User>,
+    owners: &mut HashMap<u64, u64>,
+    context: &EngineContext,
+) -> Result<Withdrawal, EngineError> {
+    let Some(user) = users.get_mut(&withdrawal.user_id) else {
+        return Err(EngineError::UserNotFound);
+    };
+    let Some(id) = withdrawal.withdrawal_id else {
+        return Err(EngineError::InvalidRequest(
+            "Withdrawal id is required".into(),
+        ));
+    };
+    if withdrawal.amount <= 0 {
+        return Err(EngineError::InvalidRequest(
+            "Amount must be greater than 0".into(),
+        ));
+    }
+    if user.balance < withdrawal.amount {
+        return Err(EngineError::InsufficientBalance);
+    }
+
+    user.balance -= withdrawal.amount;
+    user.locked_balance += withdrawal.amount;
+    user.pending_withdrawals.insert(id, withdrawal.amount);
+    owners.insert(id, user.id);
+    withdrawal.status = WithdrawalStatus::Pending;
+
+    let ledger = LedgerEntry::new(LedgerKind::Reservation)
+        .transfer(
+            LedgerAccount::Available,
+            LedgerAccount::Locked,
+            withdrawal.amount,
+        )
+        .withdrawal(id);
+    publish_balance(user, ledger, context).await;
+    publish_withdrawal(&withdrawal, None, context).await;
+
+    Ok(withdrawal)
+}
+
+// Approving pays the locked amount out; rejecting hands it back to the available balance.
+// `owners` maps each pending withdrawal to its user.
+pub async fn decide_withdrawal(
+    decision: WithdrawalDecision,
+    approve: bool,
+    users: &mut HashMap<u64, User>,
+    owners: &mut HashMap<u64, u64>,
+    context: &EngineContext,
+) -> Result<Withdrawal, EngineError> {
+    let id = decision.withdrawal_id;
+    let Some(user) = owners.get(&id).and_then(|user_id| users.get_mut(user_id)) else {
+        return Err(EngineError::WithdrawalNotFound);
+    };
+    let Some(amount) = user.pending_withdrawals.remove(&id) else {
+        return Err(EngineError::WithdrawalNotFound);
+    };
+    owners.remove(&id);
+    user.locked_balance -= amount;
+    let (status, ledger) = if approve {
+        let ledger = LedgerEntry::new(LedgerKind::Withdrawal).transfer(
+            LedgerAccount::Locked,
+            LedgerAccount::External,
+            amount,
+        );
+        (WithdrawalStatus::Approved, ledger)
+    } else {
+        user.balance += amount;
+        let ledger = LedgerEntry::new(LedgerKind::Release).transfer(
+            LedgerAccount::Locked,
+            LedgerAccount::Available,
+            amount,
+        );
+        (WithdrawalStatus::Rejected, ledger)
+    };
+    publish_balance(user, ledger.withdrawal(id), context).await;
+
+    let withdrawal = Withdrawal {
+        withdrawal_id: Some(id),
+        user_id: user.id,
+        amount,
+        status,
+    };
+    publish_withdrawal(&withdrawal, Some(&decision), context).await;
+
+    Ok(withdrawal)
+}
+
+/// Pending withdrawal ids of `user`, paired with the user's id.
+pub fn pending_withdrawal_owners(user: &User) -> impl Iterator<Item = (u64, u64)> + '_ {
+    user.pending_withdrawals.keys().map(|id| (*id, user.id))
+}
+
+async fn publish_withdrawal(
+    withdrawal: &Withdrawal,
+    decision: Option<&WithdrawalDecision>,
+    context: &EngineContext,
+) {
+    let _ = publish_db_event(DbEvent::WithdrawalUpdated(WithdrawalUpdatedEvent {
+        withdrawal_id: withdrawal.withdrawal_id.unwrap_or_default(),
+        user_id: withdrawal.user_id,
+        amount: withdrawal.amount,
+        status: withdrawal.status,
+        admin_id: decision.map(|d| d.admin_id),
+        reason: decision.and_then(|d| d.reason.clone()),
+        sequence: context.next_sequence(),
+        timestamp: context.now(),
+    }))
+    .await;
+}
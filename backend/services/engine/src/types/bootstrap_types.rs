@@ -0,0 +1,74 @@
+use serde::Deserialize;
+
+use crate::types::deposit_types::Deposit;
+use crate::types::market_types::MarketMeta;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BootstrapData {
+    pub users: Vec<BootstrapUser>,
+    pub positions: Vec<BootstrapPosition>,
+    pub markets: Vec<MarketMeta>,
+    pub orders: Vec<BootstrapOrder>,
+    /// Filled and cancelled orders placed with a client order id.
+    #[serde(default)]
+    pub client_orders: Vec<BootstrapClientOrder>,
+    #[serde(default)]
+    pub withdrawals: Vec<BootstrapWithdrawal>,
+    #[serde(default)]
+    pub deposits: Vec<Deposit>,
+    /// Sequence of the last DB event db_worker applied.
+    #[serde(default)]
+    pub last_sequence: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BootstrapUser {
+    pub id: u64,
+    pub name: String,
+    pub email: String,
+    pub balance: i64,
+    #[serde(default)]
+    pub locked_balance: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BootstrapPosition {
+    pub user_id: u64,
+    pub market_id: u64,
+    pub quantity: u64,
+    #[serde(default)]
+    pub locked_quantity: u64,
+    #[serde(default)]
+    pub cost_basis: i64,
+    #[serde(default)]
+    pub realized_pnl: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BootstrapOrder {
+    pub order_id: i64,
+    pub user_id: u64,
+    pub market_id: u64,
+    pub side: String,
+    pub price: u64,
+    pub original_qty: u64,
+    pub remaining_qty: u64,
+    pub hidden: bool,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BootstrapClientOrder {
+    pub order_id: i64,
+    pub user_id: u64,
+    pub client_order_id: String,
+    pub status: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BootstrapWithdrawal {
+    pub withdrawal_id: u64,
+    pub user_id: u64,
+    pub amount: i64,
+}
@@ -1,6 +1,13 @@
+use crate::types::deposit_types::DepositStatus;
+use crate::types::ledger_types::LedgerEntry;
+use crate::types::withdrawal_types::WithdrawalStatus;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Every event the engine publishes carries the engine-wide `sequence`, which db_worker uses
+/// to skip events it already applied. Order and trade events also carry `market_sequence`,
+/// numbered without gaps per order book (shared by a YES/NO pair), so a consumer can spot a
+/// missed event for that market.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "event_type")]
 pub enum DbEvent {
@@ -28,6 +35,10 @@ pub enum DbEvent {
     EventUpdated(EventUpdatedEvent),
     #[serde(rename = "event_deleted")]
     EventDeleted(EventDeletedEvent),
+    #[serde(rename = "withdrawal_updated")]
+    WithdrawalUpdated(WithdrawalUpdatedEvent),
+    #[serde(rename = "deposit_updated")]
+    DepositUpdated(DepositUpdatedEvent),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +50,14 @@ pub struct OrderPlacedEvent {
     pub price: u64,
     pub original_qty: u64,
     pub remaining_qty: u64,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub market_sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -47,6 +66,10 @@ pub struct OrderCancelledEvent {
     pub order_id: u64,
     pub user_id: u64,
     pub market_id: u64,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub market_sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -58,6 +81,10 @@ pub struct OrderModifiedEvent {
     pub price: u64,
     pub original_qty: u64,
     pub remaining_qty: u64,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub market_sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -69,6 +96,10 @@ pub struct OrderFilledEvent {
     pub filled_qty: u64,
     pub remaining_qty: u64,
     pub status: String,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub market_sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -83,6 +114,10 @@ pub struct TradeExecutedEvent {
     pub price: u64,
     pub quantity: u64,
     pub taker_side: String,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub market_sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -91,6 +126,14 @@ pub struct PositionUpdatedEvent {
     pub user_id: u64,
     pub market_id: u64,
     pub quantity: u64,
+    #[serde(default)]
+    pub locked_quantity: u64,
+    #[serde(default)]
+    pub cost_basis: i64,
+    #[serde(default)]
+    pub realized_pnl: i64,
+    #[serde(default)]
+    pub sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -98,6 +141,12 @@ pub struct PositionUpdatedEvent {
 pub struct BalanceUpdatedEvent {
     pub user_id: u64,
     pub balance: i64,
+    #[serde(default)]
+    pub locked_balance: i64,
+    #[serde(default)]
+    pub ledger: Option<LedgerEntry>,
+    #[serde(default)]
+    pub sequence: u64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -159,3 +208,28 @@ pub struct EventDeletedEvent {
     pub event_id: u64,
     pub timestamp: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawalUpdatedEvent {
+    pub withdrawal_id: u64,
+    pub user_id: u64,
+    pub amount: i64,
+    pub status: WithdrawalStatus,
+    pub admin_id: Option<u64>,
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositUpdatedEvent {
+    pub deposit_id: String,
+    pub user_id: u64,
+    pub amount: i64,
+    pub provider: String,
+    pub status: DepositStatus,
+    #[serde(default)]
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+/// A deposit intent created with a payment provider. `deposit_id` is the provider's
+/// reference, and the balance is only credited once the provider confirms it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deposit {
+    pub deposit_id: String,
+    pub user_id: u64,
+    pub amount: i64,
+    pub provider: String,
+    pub status: DepositStatus,
+}
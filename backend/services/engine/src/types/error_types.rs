@@ -0,0 +1,87 @@
+use std::fmt;
+
+/// Failure returned by the engine. The string from `code()` is what clients match on, so
+/// existing codes must keep their spelling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    InvalidRequest(String),
+    InvalidPrice,
+    MarketNotFound,
+    MarketInactive,
+    EventNotFound,
+    OutcomeNotFound,
+    OrderNotFound,
+    UserNotFound,
+    WithdrawalNotFound,
+    DepositNotFound,
+    InsufficientBalance,
+    InsufficientPosition,
+    NoLiquidity,
+    DuplicateClientOrderId(u64),
+    Internal(String),
+}
+
+impl EngineError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EngineError::InvalidRequest(_) => "INVALID_REQUEST",
+            EngineError::InvalidPrice => "INVALID_PRICE",
+            EngineError::MarketNotFound => "MARKET_NOT_FOUND",
+            EngineError::MarketInactive => "MARKET_INACTIVE",
+            EngineError::EventNotFound => "EVENT_NOT_FOUND",
+            EngineError::OutcomeNotFound => "OUTCOME_NOT_FOUND",
+            EngineError::OrderNotFound => "ORDER_NOT_FOUND",
+            EngineError::UserNotFound => "USER_NOT_FOUND",
+            EngineError::WithdrawalNotFound => "WITHDRAWAL_NOT_FOUND",
+            EngineError::DepositNotFound => "DEPOSIT_NOT_FOUND",
+            EngineError::InsufficientBalance => "INSUFFICIENT_BALANCE",
+            EngineError::InsufficientPosition => "INSUFFICIENT_POSITION",
+            EngineError::NoLiquidity => "NO_LIQUIDITY",
+            EngineError::DuplicateClientOrderId(_) => "DUPLICATE_CLIENT_ORDER_ID",
+            EngineError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn status_code(&self) -> i32 {
+        match self {
+            EngineError::InvalidRequest(_) | EngineError::InvalidPrice => 400,
+            EngineError::MarketNotFound
+            | EngineError::EventNotFound
+            | EngineError::OutcomeNotFound
+            | EngineError::OrderNotFound
+            | EngineError::UserNotFound
+            | EngineError::WithdrawalNotFound
+            | EngineError::DepositNotFound => 404,
+            EngineError::MarketInactive | EngineError::DuplicateClientOrderId(_) => 409,
+            EngineError::InsufficientBalance
+            | EngineError::InsufficientPosition
+            | EngineError::NoLiquidity => 422,
+            EngineError::Internal(_) => 500,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::InvalidRequest(message) | EngineError::Internal(message) => {
+                write!(f, "{}", message)
+            }
+            EngineError::InvalidPrice => write!(f, "Price must be between 0 and 100"),
+            EngineError::MarketNotFound => write!(f, "Market not found"),
+            EngineError::MarketInactive => write!(f, "Market not active to trade"),
+            EngineError::EventNotFound => write!(f, "No markets found for event"),
+            EngineError::OutcomeNotFound => write!(f, "No markets found for outcome"),
+            EngineError::OrderNotFound => write!(f, "Order not found"),
+            EngineError::UserNotFound => write!(f, "User not found"),
+            EngineError::WithdrawalNotFound => write!(f, "Pending withdrawal not found"),
+            EngineError::DepositNotFound => write!(f, "Deposit not found"),
+            EngineError::InsufficientBalance => write!(f, "Insufficient balance"),
+            EngineError::InsufficientPosition => write!(f, "Insufficient position"),
+            EngineError::NoLiquidity => write!(f, "No liquidity available"),
+            EngineError::DuplicateClientOrderId(order_id) => {
+                write!(f, "client_order_id is already used by order {}", order_id)
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerKind {
+    Onramp,
+    Deposit,
+    Reservation,
+    Release,
+    Trade,
+    Split,
+    Settlement,
+    Withdrawal,
+}
+
+/// `Available` and `Locked` are the user's own cash. The others stand for where money
+/// comes from or goes to outside the user's account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    Available,
+    Locked,
+    External,
+    Trading,
+    Collateral,
+    Settlement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerPosting {
+    pub account: LedgerAccount,
+    pub amount: i64,
+}
+
+/// Why a user's balance moved. Postings are only added in pairs through `transfer`, so
+/// they always sum to zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub kind: LedgerKind,
+    pub postings: Vec<LedgerPosting>,
+    #[serde(default)]
+    pub order_id: Option<u64>,
+    #[serde(default)]
+    pub trade_id: Option<String>,
+    #[serde(default)]
+    pub event_id: Option<u64>,
+    #[serde(default)]
+    pub withdrawal_id: Option<u64>,
+    #[serde(default)]
+    pub deposit_id: Option<String>,
+}
+
+impl LedgerEntry {
+    pub fn new(kind: LedgerKind) -> Self {
+        Self {
+            kind,
+            postings: Vec::new(),
+            order_id: None,
+            trade_id: None,
+            event_id: None,
+            withdrawal_id: None,
+            deposit_id: None,
+        }
+    }
+
+    pub fn transfer(mut self, from: LedgerAccount, to: LedgerAccount, amount: i64) -> Self {
+        if amount != 0 {
+            self.postings.push(LedgerPosting {
+                account: from,
+                amount: -amount,
+            });
+            self.postings.push(LedgerPosting {
+                account: to,
+                amount,
+            });
+        }
+        self
+    }
+
+    pub fn order(mut self, order_id: Option<u64>) -> Self {
+        self.order_id = order_id;
+        self
+    }
+
+    pub fn trade(mut self, trade_id: &str) -> Self {
+        self.trade_id = Some(trade_id.to_string());
+        self
+    }
+
+    pub fn event(mut self, event_id: u64) -> Self {
+        self.event_id = Some(event_id);
+        self
+    }
+
+    pub fn withdrawal(mut self, withdrawal_id: u64) -> Self {
+        self.withdrawal_id = Some(withdrawal_id);
+        self
+    }
+
+    pub fn deposit(mut self, deposit_id: &str) -> Self {
+        self.deposit_id = Some(deposit_id.to_string());
+        self
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::market_types::MarketStatus;
+use crate::types::orderbook_types::{LevelDelta, OrderFeedEntry};
+
+/// Public market data published on the `market_data` stream. Prices and sides are in the
+/// market's own terms, so a NO market gets its own messages mirrored from the YES book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketDataEvent {
+    Delta(BookDelta),
+    Orders(OrderFeedUpdate),
+    Trade(TradePrint),
+    Status(MarketStatusUpdate),
+}
+
+/// Level changes from one engine command, in depth-sequence order. Apply them on top of a
+/// snapshot, skipping any with `sequence <= snapshot.depth_sequence`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookDelta {
+    pub market_id: u64,
+    pub outcome_id: Option<u64>,
+    pub event_id: Option<u64>,
+    pub deltas: Vec<LevelDelta>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Order-by-order (L3) changes from one engine command, applied on top of an L3 snapshot the
+/// same way deltas are applied to an L2 one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFeedUpdate {
+    pub market_id: u64,
+    pub outcome_id: Option<u64>,
+    pub event_id: Option<u64>,
+    pub entries: Vec<OrderFeedEntry>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradePrint {
+    pub trade_id: String,
+    pub market_id: u64,
+    pub outcome_id: Option<u64>,
+    pub event_id: Option<u64>,
+    pub price: u64,
+    pub quantity: u64,
+    pub taker_side: String,
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStatusUpdate {
+    pub market_id: u64,
+    pub outcome_id: Option<u64>,
+    pub event_id: Option<u64>,
+    pub status: MarketStatus,
+    pub timestamp: DateTime<Utc>,
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::types::orderbook_types::Level;
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub enum MarketSide {
     Yes,
@@ -24,7 +26,24 @@ pub enum MarketStatus {
     Cancelled,
 }
 
-#[derive(Debug, Clone)]
+/// A market's registry entry with its live top of book. Prices are quoted for the market's own
+/// side, so a NO market's best bid is 100 minus the best YES ask. `spread` is best ask minus
+/// best bid and goes negative if the book is ever crossed.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MarketSummary {
+    pub market_id: u64,
+    pub side: Option<MarketSide>,
+    pub paired_market_id: Option<u64>,
+    pub event_id: Option<u64>,
+    pub outcome_id: Option<u64>,
+    pub status: MarketStatus,
+    pub best_bid: Option<Level>,
+    pub best_ask: Option<Level>,
+    pub last_price: Option<u64>,
+    pub spread: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct MarketMeta {
     pub event_id: u64,
     pub outcome_id: u64,
@@ -1,5 +1,11 @@
+pub mod bootstrap_types;
 pub mod db_event_types;
+pub mod deposit_types;
+pub mod error_types;
+pub mod ledger_types;
+pub mod market_data_types;
 pub mod market_types;
 pub mod orderbook_types;
 pub mod request_types;
 pub mod user_types;
+pub mod withdrawal_types;
@@ -1,4 +1,5 @@
 use crate::types::market_types::MarketSide;
+use crate::types::user_types::User;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::collections::HashMap;
@@ -8,10 +9,231 @@ pub struct OrderbookData {
     pub market_id: u64,
     pub asks: BTreeMap<u64, u64>,
     pub bids: BTreeMap<u64, u64>,
-    pub ask_queue: HashMap<u64, Vec<u64>>,
-    pub bid_queue: HashMap<u64, Vec<u64>>,
+    pub ask_queue: HashMap<u64, OrderQueue>,
+    pub bid_queue: HashMap<u64, OrderQueue>,
+    pub hidden_asks: BTreeMap<u64, u64>,
+    pub hidden_bids: BTreeMap<u64, u64>,
+    pub hidden_ask_queue: HashMap<u64, OrderQueue>,
+    pub hidden_bid_queue: HashMap<u64, OrderQueue>,
     pub orders: HashMap<u64, Order>,
     pub last_price: Option<u64>,
+    /// Market sequence of the last order or trade event the book emitted. It counts without
+    /// gaps per book, which the YES and NO side of a pair share.
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub depth: DepthDeltas,
+    #[serde(default)]
+    pub order_feed: OrderFeed,
+}
+
+impl OrderbookData {
+    pub fn new(market_id: u64) -> Self {
+        Self {
+            market_id,
+            asks: BTreeMap::new(),
+            bids: BTreeMap::new(),
+            ask_queue: HashMap::new(),
+            bid_queue: HashMap::new(),
+            hidden_asks: BTreeMap::new(),
+            hidden_bids: BTreeMap::new(),
+            hidden_ask_queue: HashMap::new(),
+            hidden_bid_queue: HashMap::new(),
+            orders: HashMap::new(),
+            last_price: None,
+            sequence: 0,
+            depth: DepthDeltas::default(),
+            order_feed: OrderFeed::default(),
+        }
+    }
+
+    pub fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+}
+
+/// Changes to displayed price levels since they were last published. Every change gets the
+/// next depth sequence, so a gap tells a consumer it has to resync from a snapshot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DepthDeltas {
+    pub sequence: u64,
+    #[serde(skip)]
+    pending: Vec<LevelDelta>,
+}
+
+impl DepthDeltas {
+    pub fn record(&mut self, side: OrderSide, price: u64, quantity: u64) {
+        self.sequence += 1;
+        self.pending.push(LevelDelta {
+            side,
+            price,
+            quantity,
+            sequence: self.sequence,
+        });
+    }
+
+    pub fn take(&mut self) -> Vec<LevelDelta> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// New aggregate quantity at a displayed level; zero means the level is gone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelDelta {
+    pub side: OrderSide,
+    pub price: u64,
+    pub quantity: u64,
+    pub sequence: u64,
+}
+
+/// Per-order changes to displayed resting orders since they were last published, numbered
+/// like `DepthDeltas` but on their own sequence.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderFeed {
+    pub sequence: u64,
+    #[serde(skip)]
+    pending: Vec<OrderFeedEntry>,
+}
+
+impl OrderFeed {
+    pub fn record(
+        &mut self,
+        kind: OrderFeedKind,
+        public_id: String,
+        order: &Order,
+        executed_qty: Option<u64>,
+    ) {
+        self.sequence += 1;
+        let quantity = match kind {
+            OrderFeedKind::Cancel => 0,
+            _ => order.remaining_qty,
+        };
+        self.pending.push(OrderFeedEntry {
+            kind,
+            order_id: public_id,
+            side: order.side.clone(),
+            price: order.price,
+            quantity,
+            executed_qty,
+            sequence: self.sequence,
+        });
+    }
+
+    pub fn take(&mut self) -> Vec<OrderFeedEntry> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderFeedKind {
+    Add,
+    Modify,
+    Cancel,
+    Execute,
+}
+
+/// `order_id` is the anonymized public id and `quantity` what is left resting after the
+/// change. A modify moves the order to the back of its new level's queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFeedEntry {
+    pub kind: OrderFeedKind,
+    pub order_id: String,
+    pub side: OrderSide,
+    pub price: u64,
+    pub quantity: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_qty: Option<u64>,
+    pub sequence: u64,
+}
+
+/// FIFO of order ids at a single price level. Each id is linked to its
+/// neighbours so push, pop and removal from the middle are all O(1).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderQueue {
+    head: Option<u64>,
+    tail: Option<u64>,
+    links: HashMap<u64, QueueLink>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct QueueLink {
+    prev: Option<u64>,
+    next: Option<u64>,
+}
+
+impl OrderQueue {
+    pub fn push_back(&mut self, order_id: u64) {
+        if self.links.contains_key(&order_id) {
+            return;
+        }
+
+        match self.tail {
+            Some(tail) => {
+                if let Some(link) = self.links.get_mut(&tail) {
+                    link.next = Some(order_id);
+                }
+            }
+            None => self.head = Some(order_id),
+        }
+
+        self.links.insert(
+            order_id,
+            QueueLink {
+                prev: self.tail,
+                next: None,
+            },
+        );
+        self.tail = Some(order_id);
+    }
+
+    pub fn front(&self) -> Option<u64> {
+        self.head
+    }
+
+    pub fn pop_front(&mut self) -> Option<u64> {
+        let order_id = self.head?;
+        self.remove(order_id);
+        Some(order_id)
+    }
+
+    pub fn remove(&mut self, order_id: u64) -> bool {
+        let Some(link) = self.links.remove(&order_id) else {
+            return false;
+        };
+
+        match link.prev {
+            Some(prev) => {
+                if let Some(prev_link) = self.links.get_mut(&prev) {
+                    prev_link.next = link.next;
+                }
+            }
+            None => self.head = link.next,
+        }
+
+        match link.next {
+            Some(next) => {
+                if let Some(next_link) = self.links.get_mut(&next) {
+                    next_link.prev = link.prev;
+                }
+            }
+            None => self.tail = link.prev,
+        }
+
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Order ids in time priority, front first.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        std::iter::successors(self.head, |id| {
+            self.links.get(id).and_then(|link| link.next)
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +252,10 @@ pub struct Order {
     pub remaining_qty: u64,
     pub side: OrderSide,
     pub order_type: OrderType,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,12 +270,65 @@ pub struct OrderbookSnapshot {
     pub bids: Vec<Level>,
     pub asks: Vec<Level>,
     pub last_price: Option<u64>,
+    #[serde(default)]
+    pub sequence: u64,
+    /// Depth deltas with a sequence up to this one are already reflected in the snapshot.
+    #[serde(default)]
+    pub depth_sequence: u64,
+}
+
+/// Every displayed resting order, level by level in time priority. Feed entries with a
+/// sequence up to `sequence` are already reflected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderbookL3Snapshot {
+    pub market_id: u64,
+    pub bids: Vec<L3Level>,
+    pub asks: Vec<L3Level>,
+    pub sequence: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L3Level {
+    pub price: u64,
+    pub orders: Vec<L3Order>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L3Order {
+    pub order_id: String,
+    pub quantity: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Level {
     pub price: u64,
     pub quantity: u64,
+    /// Running total from the top of the book, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cumulative_quantity: Option<u64>,
+}
+
+/// Shapes an L2 snapshot: `group` buckets prices into bands of that many cents (bids round
+/// down, asks up to at most 100), `depth` keeps the best N levels per side after grouping,
+/// and `cumulative` adds running totals. The default is the full book.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SnapshotOptions {
+    #[serde(default)]
+    pub depth: Option<usize>,
+    #[serde(default)]
+    pub group: Option<u64>,
+    #[serde(default)]
+    pub cumulative: bool,
+}
+
+/// Best level on each side quoted for the market's own side (a NO market's best bid mirrors
+/// the best YES ask), for tickers and market lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopOfBook {
+    pub market_id: u64,
+    pub best_bid: Option<Level>,
+    pub best_ask: Option<Level>,
+    pub last_price: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,3 +350,92 @@ pub struct EventOrderbookSnapshot {
     pub event_id: u64,
     pub outcomes: Vec<OutcomeOrderbookSnapshot>,
 }
+
+/// Implied probabilities for an event's outcomes, read off each outcome's YES book. Prices
+/// are in cents, so a full set of YES shares pays out 100.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventProbabilities {
+    pub event_id: u64,
+    pub outcomes: Vec<OutcomeProbability>,
+    /// Sum of best YES asks, when every outcome has one.
+    pub yes_ask_sum: Option<u64>,
+    /// Sum of best YES bids, when every outcome has one.
+    pub yes_bid_sum: Option<u64>,
+    /// `yes_ask_sum / 100`; above 1 is the book's margin.
+    pub overround: Option<f64>,
+    /// Buying every YES at the ask costs less than the 100 one of them pays.
+    pub buy_arbitrage: Option<Arbitrage>,
+    /// Selling every YES at the bid brings in more than the 100 one of them pays.
+    pub sell_arbitrage: Option<Arbitrage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutcomeProbability {
+    pub outcome_id: u64,
+    pub market_id: Option<u64>,
+    pub best_bid: Option<u64>,
+    pub best_ask: Option<u64>,
+    /// Bid/ask midpoint, or the last trade when either side is empty.
+    pub mid_price: Option<f64>,
+    /// `mid_price` scaled so the priced outcomes sum to 1.
+    pub probability: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arbitrage {
+    /// Cents per full set.
+    pub edge: u64,
+    /// Full sets available at the best levels.
+    pub quantity: u64,
+}
+
+/// Full engine state, hidden orders included, used to inspect a replayed run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineState {
+    pub orderbooks: HashMap<u64, OrderbookData>,
+    pub users: HashMap<u64, User>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_queue_is_fifo() {
+        let mut queue = OrderQueue::default();
+        for id in [3, 1, 2] {
+            queue.push_back(id);
+        }
+        queue.push_back(1);
+
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![3, 1, 2]);
+        assert_eq!(queue.pop_front(), Some(3));
+        assert_eq!(queue.pop_front(), Some(1));
+        assert_eq!(queue.pop_front(), Some(2));
+        assert_eq!(queue.pop_front(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn order_queue_removes_from_any_position() {
+        let mut queue = OrderQueue::default();
+        for id in 1..=5 {
+            queue.push_back(id);
+        }
+
+        assert!(queue.remove(3));
+        assert!(queue.remove(1));
+        assert!(queue.remove(5));
+        assert!(!queue.remove(3));
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(queue.front(), Some(2));
+
+        queue.push_back(6);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![2, 4, 6]);
+        assert!(queue.remove(2));
+        assert!(queue.remove(4));
+        assert!(queue.remove(6));
+        assert!(queue.is_empty());
+        assert_eq!(queue.front(), None);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::orderbook_types::{OrderSide, OrderType};
+use crate::types::orderbook_types::{OrderSide, OrderType, SnapshotOptions};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -14,6 +14,10 @@ pub struct PlaceOrderRequest {
     pub side: OrderSide,
     #[serde(with = "order_type_string", default = "default_order_type")]
     pub order_type: OrderType,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
 }
 
 fn default_order_type() -> OrderType {
@@ -58,17 +62,27 @@ mod order_side_string {
 
 #[derive(Debug, Deserialize)]
 pub struct CancelOrderRequest {
-    pub order_id: u64,
+    #[serde(default)]
+    pub order_id: Option<u64>,
     #[serde(default)]
     pub market_id: u64,
+    #[serde(default)]
+    pub user_id: u64,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ModifyOrderRequest {
-    pub order_id: u64,
+    #[serde(default)]
+    pub order_id: Option<u64>,
     pub price: Option<u64>,
     #[serde(rename = "original_qty")]
     pub original_qty: Option<u64>,
+    #[serde(default)]
+    pub user_id: u64,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -81,6 +95,12 @@ pub struct GetOrderStatusRequest {
     pub order_id: u64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GetClientOrderRequest {
+    pub user_id: u64,
+    pub client_order_id: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetOrderHistoryRequest {
     #[allow(dead_code)]
@@ -107,6 +127,25 @@ pub struct OnrampRequest {
     pub amount: i64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalRequest {
+    pub user_id: u64,
+    pub amount: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDepositRequest {
+    pub deposit_id: String,
+    pub user_id: u64,
+    pub amount: i64,
+    pub provider: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettleDepositRequest {
+    pub deposit_id: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SplitOrderRequest {
     pub user_id: u64,
@@ -122,19 +161,44 @@ pub struct MergeOrderRequest {
     pub market2_id: u64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GetTopOfBookRequest {
+    #[serde(default)]
+    pub market_ids: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListMarketsRequest {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub event_id: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetOrderbookByMarketRequest {
     pub market_id: u64,
+    #[serde(flatten)]
+    pub options: SnapshotOptions,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GetOrderbooksByEventRequest {
     pub event_id: u64,
+    #[serde(flatten)]
+    pub options: SnapshotOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetEventProbabilitiesRequest {
+    pub event_id: u64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GetOrderbooksByOutcomeRequest {
     pub outcome_id: u64,
+    #[serde(flatten)]
+    pub options: SnapshotOptions,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,3 +1,4 @@
+use crate::types::deposit_types::Deposit;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -6,6 +7,76 @@ pub struct User {
     pub id: u64,
     pub name: String,
     pub email: String,
+    /// Cash free to use; `locked_balance` is what open bids have reserved.
     pub balance: i64,
     pub positions: HashMap<u64, u64>,
+    #[serde(default)]
+    pub locked_balance: i64,
+    /// Shares reserved by open asks, per market. Not included in `positions`.
+    #[serde(default)]
+    pub locked_positions: HashMap<u64, u64>,
+    /// Cost basis and realized PnL per market, kept after a position closes.
+    #[serde(default)]
+    pub costs: HashMap<u64, PositionCost>,
+    /// Pending withdrawals by id; their amounts are part of `locked_balance`.
+    #[serde(default)]
+    pub pending_withdrawals: HashMap<u64, i64>,
+    /// Deposits by provider reference, kept after they settle so a repeated webhook is a no-op.
+    #[serde(default)]
+    pub deposits: HashMap<String, Deposit>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PositionCost {
+    /// What the shares still held, free or locked, cost in total.
+    pub cost_basis: i64,
+    pub realized_pnl: i64,
+}
+
+impl User {
+    pub fn total_balance(&self) -> i64 {
+        self.balance + self.locked_balance
+    }
+
+    pub fn position(&self, market_id: u64) -> u64 {
+        self.positions.get(&market_id).copied().unwrap_or(0)
+    }
+
+    pub fn locked_position(&self, market_id: u64) -> u64 {
+        self.locked_positions.get(&market_id).copied().unwrap_or(0)
+    }
+
+    pub fn held_position(&self, market_id: u64) -> u64 {
+        self.position(market_id) + self.locked_position(market_id)
+    }
+
+    pub fn cost(&self, market_id: u64) -> PositionCost {
+        self.costs.get(&market_id).cloned().unwrap_or_default()
+    }
+
+    pub fn average_entry_price(&self, market_id: u64) -> f64 {
+        let held = self.held_position(market_id);
+        if held == 0 {
+            return 0.0;
+        }
+        self.cost(market_id).cost_basis as f64 / held as f64
+    }
+
+    pub fn record_buy(&mut self, market_id: u64, cost: i64) {
+        self.costs.entry(market_id).or_default().cost_basis += cost;
+    }
+
+    /// Realizes `proceeds` against the average cost of `quantity` shares. Must be called
+    /// before the shares are taken out of `positions` or `locked_positions`.
+    pub fn record_sell(&mut self, market_id: u64, quantity: u64, proceeds: i64) {
+        let held = self.held_position(market_id) as i128;
+        let entry = self.costs.entry(market_id).or_default();
+        let released = if held == 0 {
+            0
+        } else {
+            (entry.cost_basis as i128 * quantity.min(held as u64) as i128 / held) as i64
+        };
+        entry.cost_basis -= released;
+        entry.realized_pnl += proceeds - released;
+    }
 }
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A request to take cash out. The amount stays locked while it is pending.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Withdrawal {
+    pub withdrawal_id: Option<u64>,
+    pub user_id: u64,
+    pub amount: i64,
+    pub status: WithdrawalStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawalDecision {
+    pub withdrawal_id: u64,
+    pub admin_id: u64,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,177 @@
+use crate::services::payment_provider::{DepositOutcome, PaymentProvider};
+use crate::types::deposit_types::{DepositInput, DepositQuery};
+use crate::utils::jwt::extract_user_id;
+use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::{build_json_from_redis_response, error_status};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use log::warn;
+use redis_client::RedisRequest;
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+#[post("/deposits")]
+pub async fn create_deposit(
+    req: HttpRequest,
+    body: web::Json<DepositInput>,
+    provider: web::Data<dyn PaymentProvider>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": e.to_string()
+        }));
+    }
+
+    let intent = match provider.create_intent(user_id as u64, body.amount).await {
+        Ok(intent) => intent,
+        Err(e) => {
+            return HttpResponse::BadGateway().json(json!({
+                "status": "error",
+                "message": "Failed to create deposit with payment provider",
+                "error": e
+            }))
+        }
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let deposit_request = RedisRequest::new(
+        "engine",
+        "create-deposit",
+        "Create a deposit",
+        json!({
+            "deposit_id": intent.deposit_id,
+            "user_id": user_id as u64,
+            "amount": body.amount,
+            "provider": provider.name(),
+        }),
+    );
+
+    match send_request_and_wait(request_id, deposit_request, 10).await {
+        Ok(response) => {
+            if response.status_code >= 400 {
+                return build_json_from_redis_response(&response);
+            }
+            HttpResponse::Ok().json(json!({
+                "deposit": response.data["deposit"],
+                "checkout_url": intent.checkout_url
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to create deposit",
+            "error": e
+        })),
+    }
+}
+
+#[get("/deposits")]
+pub async fn get_deposits(req: HttpRequest, query: web::Query<DepositQuery>) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let read_request = RedisRequest::new(
+        "db_worker",
+        "get_deposits_by_user",
+        "Get user deposits",
+        json!({
+            "user_id": user_id as u64,
+            "limit": query.limit,
+            "offset": query.offset,
+        }),
+    );
+
+    match send_request_and_wait(request_id, read_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch deposits",
+            "error": e
+        })),
+    }
+}
+
+// Called by the payment provider, not by users, so it sits outside the auth scope and
+// trusts nothing until the signature checks out.
+#[post("/deposits/webhook")]
+pub async fn deposit_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    provider: web::Data<dyn PaymentProvider>,
+) -> impl Responder {
+    let signature = match req
+        .headers()
+        .get(provider.signature_header())
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(signature) => signature,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "status": "error",
+                "message": "Missing webhook signature"
+            }))
+        }
+    };
+
+    let webhook = match provider.verify_webhook(&body, signature) {
+        Ok(webhook) => webhook,
+        Err(e) => {
+            warn!("Rejected {} webhook: {}", provider.name(), e);
+            return HttpResponse::Unauthorized().json(json!({
+                "status": "error",
+                "message": e
+            }));
+        }
+    };
+
+    let action = match webhook.status {
+        DepositOutcome::Succeeded => "confirm-deposit",
+        DepositOutcome::Failed => "fail-deposit",
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let settle_request = RedisRequest::new(
+        "engine",
+        action,
+        "Settle a deposit",
+        json!({ "deposit_id": webhook.deposit_id }),
+    );
+
+    match send_request_and_wait(request_id, settle_request, 10).await {
+        Ok(response) => {
+            // The engine only keeps pending deposits, so a retried webhook for one that already
+            // settled lands here and is acknowledged to stop the retries
+            if response.error_code.as_deref() == Some("DEPOSIT_NOT_FOUND") {
+                return HttpResponse::Ok().json(json!({
+                    "status": "success",
+                    "message": "No pending deposit with this id"
+                }));
+            }
+            if response.status_code >= 400 {
+                return HttpResponse::build(error_status(&response)).json(json!({
+                    "status": "error",
+                    "message": response.message,
+                    "code": response.error_code
+                }));
+            }
+            HttpResponse::Ok().json(json!({
+                "status": "success",
+                "message": response.message,
+                "data": response.data
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to settle deposit",
+            "error": e
+        })),
+    }
+}
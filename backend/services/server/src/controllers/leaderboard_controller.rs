@@ -0,0 +1,86 @@
+use crate::utils::jwt::extract_user_id;
+use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::build_json_from_redis_response;
+use actix_web::{get, put, web, HttpRequest, HttpResponse, Responder};
+use redis_client::RedisRequest;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+/// `window` is one of daily, weekly, monthly or all_time (the default).
+#[derive(Deserialize, Default)]
+pub struct LeaderboardQuery {
+    pub window: Option<String>,
+    pub category: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Accuracy board only: resolved predictions a user needs to be ranked.
+    pub min_predictions: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct LeaderboardPrivacyPayload {
+    pub hide_from_leaderboards: bool,
+}
+
+// `metric` is pnl, volume or accuracy; db_worker rejects anything else.
+#[get("/leaderboards/{metric}")]
+pub async fn get_leaderboard(
+    path: web::Path<String>,
+    query: web::Query<LeaderboardQuery>,
+) -> impl Responder {
+    let request_id = Uuid::new_v4().to_string();
+    let read_request = RedisRequest::new(
+        "db_worker",
+        "get_leaderboard",
+        "Get leaderboard",
+        json!({
+            "metric": path.into_inner(),
+            "window": query.window,
+            "category": query.category,
+            "limit": query.limit,
+            "offset": query.offset,
+            "min_predictions": query.min_predictions,
+        }),
+    );
+
+    match send_request_and_wait(request_id, read_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch leaderboard",
+            "error": e
+        })),
+    }
+}
+
+#[put("/user/leaderboard-privacy")]
+pub async fn update_leaderboard_privacy(
+    req: HttpRequest,
+    payload: web::Json<LeaderboardPrivacyPayload>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let redis_request = RedisRequest::new(
+        "db_worker",
+        "update_leaderboard_privacy",
+        "Update leaderboard privacy",
+        json!({
+            "user_id": user_id as u64,
+            "hide_from_leaderboards": payload.hide_from_leaderboards,
+        }),
+    );
+
+    match send_request_and_wait(request_id, redis_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to update leaderboard privacy",
+            "error": e
+        })),
+    }
+}
@@ -0,0 +1,73 @@
+use crate::utils::jwt::extract_user_id;
+use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::build_json_from_redis_response;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use redis_client::RedisRequest;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+#[derive(Deserialize, Default)]
+pub struct LedgerQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct LedgerCheckQuery {
+    pub user_id: Option<u64>,
+}
+
+#[get("/ledger")]
+pub async fn get_ledger(req: HttpRequest, query: web::Query<LedgerQuery>) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let request_id = Uuid::new_v4().to_string();
+    let ledger_request = RedisRequest::new(
+        "db_worker",
+        "get_ledger_by_user",
+        "Get user ledger",
+        json!({
+            "user_id": user_id as u64,
+            "limit": limit,
+            "offset": offset,
+        }),
+    );
+
+    match send_request_and_wait(request_id, ledger_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch ledger",
+            "error": e
+        })),
+    }
+}
+
+#[get("/ledger/check")]
+pub async fn check_ledger(query: web::Query<LedgerCheckQuery>) -> impl Responder {
+    let request_id = Uuid::new_v4().to_string();
+    let check_request = RedisRequest::new(
+        "db_worker",
+        "check_ledger_consistency",
+        "Check ledger against balances",
+        json!({
+            "user_id": query.user_id,
+        }),
+    );
+
+    match send_request_and_wait(request_id, check_request, 30).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to check ledger",
+            "error": e
+        })),
+    }
+}
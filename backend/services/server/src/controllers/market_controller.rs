@@ -0,0 +1,188 @@
+use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::build_json_from_redis_response;
+use actix_web::{get, web, HttpResponse, Responder};
+use redis_client::RedisRequest;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Deserialize, Default)]
+pub struct CandleQuery {
+    pub interval: Option<String>,
+    /// Unix seconds.
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct MarketListQuery {
+    pub status: Option<String>,
+    pub event_id: Option<u64>,
+}
+
+#[get("/markets")]
+pub async fn list_markets(query: web::Query<MarketListQuery>) -> impl Responder {
+    let request_id = Uuid::new_v4().to_string();
+    let request = RedisRequest::new(
+        "engine",
+        "list-markets",
+        "List markets",
+        json!({
+            "status": query.status,
+            "event_id": query.event_id,
+        }),
+    );
+
+    match send_request_and_wait(request_id, request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch markets",
+            "error": e
+        })),
+    }
+}
+
+#[get("/markets/{market_id}/candles")]
+pub async fn get_market_candles(
+    path: web::Path<u64>,
+    query: web::Query<CandleQuery>,
+) -> impl Responder {
+    let request_id = Uuid::new_v4().to_string();
+    let read_request = RedisRequest::new(
+        "db_worker",
+        "get_candles_by_market",
+        "Get market candles",
+        json!({
+            "market_id": path.into_inner(),
+            "interval": query.interval,
+            "from": query.from,
+            "to": query.to,
+        }),
+    );
+
+    match send_request_and_wait(request_id, read_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch candles",
+            "error": e
+        })),
+    }
+}
+
+#[get("/markets/tickers")]
+pub async fn get_market_tickers() -> impl Responder {
+    match fetch_tickers(None).await {
+        Ok((window_start, tickers)) => HttpResponse::Ok().json(json!({
+            "window_start": window_start,
+            "count": tickers.len(),
+            "tickers": tickers
+        })),
+        Err(response) => response,
+    }
+}
+
+#[get("/events/{event_id}/ticker")]
+pub async fn get_event_ticker(path: web::Path<u64>) -> impl Responder {
+    let event_id = path.into_inner();
+    let (window_start, tickers) = match fetch_tickers(Some(event_id)).await {
+        Ok(result) => result,
+        Err(response) => return response,
+    };
+
+    if tickers.is_empty() {
+        return HttpResponse::NotFound().json(json!({
+            "status": "error",
+            "message": "Event not found"
+        }));
+    }
+
+    // A NO market's stats mirror its YES sibling's trades, so only YES markets are summed
+    let yes_markets = || tickers.iter().filter(|t| t["side"] == "YES");
+    let volume: i64 = yes_markets().filter_map(|t| t["volume"].as_i64()).sum();
+    let trade_count: i64 = yes_markets()
+        .filter_map(|t| t["trade_count"].as_i64())
+        .sum();
+
+    HttpResponse::Ok().json(json!({
+        "event_id": event_id,
+        "window_start": window_start,
+        "volume": volume,
+        "trade_count": trade_count,
+        "markets": tickers
+    }))
+}
+
+// Normalized outcome probabilities from each outcome's YES book, with the overround and any
+// buy-all or sell-all arbitrage across the event's outcomes.
+#[get("/events/{event_id}/probabilities")]
+pub async fn get_event_probabilities(path: web::Path<u64>) -> impl Responder {
+    let request_id = Uuid::new_v4().to_string();
+    let request = RedisRequest::new(
+        "engine",
+        "get-event-probabilities",
+        "Get event probabilities",
+        json!({ "event_id": path.into_inner() }),
+    );
+
+    match send_request_and_wait(request_id, request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch event probabilities",
+            "error": e
+        })),
+    }
+}
+
+// 24h stats come from db_worker's candles, best bid/ask from the engine's live books.
+async fn fetch_tickers(event_id: Option<u64>) -> Result<(Value, Vec<Value>), HttpResponse> {
+    let stats_request = RedisRequest::new(
+        "db_worker",
+        "get_market_tickers",
+        "Get market tickers",
+        json!({ "event_id": event_id }),
+    );
+    let book_request = RedisRequest::new("engine", "get-top-of-book", "Get top of book", json!({}));
+
+    let (stats, books) = tokio::join!(
+        send_request_and_wait(Uuid::new_v4().to_string(), stats_request, 10),
+        send_request_and_wait(Uuid::new_v4().to_string(), book_request, 10),
+    );
+
+    let stats = match stats {
+        Ok(response) if response.status_code < 400 => response,
+        Ok(response) => return Err(build_json_from_redis_response(&response)),
+        Err(e) => {
+            return Err(HttpResponse::InternalServerError().json(json!({
+                "status": "error",
+                "message": "Failed to fetch tickers",
+                "error": e
+            })))
+        }
+    };
+
+    // Without the engine the tickers still carry their 24h stats, just no bid/ask
+    let tops: HashMap<u64, Value> = books
+        .ok()
+        .filter(|response| response.status_code < 400)
+        .and_then(|response| response.data.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|top| Some((top["market_id"].as_u64()?, top)))
+        .collect();
+
+    let mut tickers = stats.data["tickers"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    for ticker in &mut tickers {
+        let top = ticker["market_id"].as_u64().and_then(|id| tops.get(&id));
+        ticker["best_bid"] = top.map_or(Value::Null, |top| top["best_bid"].clone());
+        ticker["best_ask"] = top.map_or(Value::Null, |top| top["best_ask"].clone());
+    }
+
+    Ok((stats.data["window_start"].clone(), tickers))
+}
@@ -0,0 +1,211 @@
+use crate::services::market_data_consumer::MarketDataHub;
+use crate::types::market_data_types::{BookLevel, MarketDataChannel, MarketDataClientMessage};
+use crate::utils::redis_stream::send_request_and_wait;
+use actix_web::{get, rt, web, Error, HttpRequest, HttpResponse};
+use actix_ws::{Closed, Message, MessageStream, Session};
+use engine::types::market_data_types::MarketDataEvent;
+use redis_client::RedisRequest;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+const MAX_SUBSCRIPTIONS: usize = 100;
+
+type Subscriptions = HashSet<(MarketDataChannel, u64, BookLevel)>;
+
+// Public feed. Clients send `{"op": "subscribe", "channel": "market" | "outcome" | "event",
+// "id": ..., "level": "l2" | "l3"}`, get the current orderbook snapshot back, then every
+// level delta (or order feed entry for l3), trade print and status change for that scope.
+#[get("/ws/market-data")]
+pub async fn market_data_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    hub: web::Data<MarketDataHub>,
+) -> Result<HttpResponse, Error> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+    rt::spawn(run_session(session, stream, hub.subscribe()));
+    Ok(response)
+}
+
+async fn run_session(
+    mut session: Session,
+    mut stream: MessageStream,
+    mut updates: broadcast::Receiver<Arc<MarketDataEvent>>,
+) {
+    let mut subscriptions = Subscriptions::new();
+
+    loop {
+        let sent = tokio::select! {
+            message = stream.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_client_message(&text, &mut session, &mut subscriptions).await
+                }
+                Some(Ok(Message::Ping(bytes))) => session.pong(&bytes).await,
+                Some(Ok(Message::Close(reason))) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                Some(Ok(_)) => Ok(()),
+                Some(Err(_)) | None => break,
+            },
+            update = updates.recv() => match update {
+                Ok(event) if is_subscribed(&subscriptions, &event) => {
+                    match serde_json::to_string(&*event) {
+                        Ok(json) => session.text(json).await,
+                        Err(_) => Ok(()),
+                    }
+                }
+                Ok(_) => Ok(()),
+                // The client fell behind and missed updates; fresh snapshots resync it
+                Err(RecvError::Lagged(_)) => resync(&mut session, &subscriptions).await,
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if sent.is_err() {
+            return;
+        }
+    }
+
+    let _ = session.close(None).await;
+}
+
+async fn handle_client_message(
+    text: &str,
+    session: &mut Session,
+    subscriptions: &mut Subscriptions,
+) -> Result<(), Closed> {
+    match serde_json::from_str::<MarketDataClientMessage>(text) {
+        Ok(MarketDataClientMessage::Subscribe { channel, id, level }) => {
+            let subscription = (channel, id, level);
+            if level == BookLevel::L3 && channel != MarketDataChannel::Market {
+                return send_json(
+                    session,
+                    json!({ "type": "error", "message": "L3 is only available per market" }),
+                )
+                .await;
+            }
+            if subscriptions.len() >= MAX_SUBSCRIPTIONS && !subscriptions.contains(&subscription) {
+                return send_json(
+                    session,
+                    json!({ "type": "error", "message": "Too many subscriptions" }),
+                )
+                .await;
+            }
+            subscriptions.insert(subscription);
+            send_json(
+                session,
+                json!({ "type": "subscribed", "channel": channel, "id": id, "level": level }),
+            )
+            .await?;
+            send_snapshot(session, subscription).await
+        }
+        Ok(MarketDataClientMessage::Unsubscribe { channel, id, level }) => {
+            subscriptions.remove(&(channel, id, level));
+            send_json(
+                session,
+                json!({ "type": "unsubscribed", "channel": channel, "id": id, "level": level }),
+            )
+            .await
+        }
+        Err(e) => {
+            send_json(
+                session,
+                json!({ "type": "error", "message": format!("Invalid message: {}", e) }),
+            )
+            .await
+        }
+    }
+}
+
+fn is_subscribed(subscriptions: &Subscriptions, event: &MarketDataEvent) -> bool {
+    let (market_id, outcome_id, event_id, levels) = match event {
+        MarketDataEvent::Delta(update) => (
+            update.market_id,
+            update.outcome_id,
+            update.event_id,
+            &[BookLevel::L2][..],
+        ),
+        MarketDataEvent::Orders(update) => (
+            update.market_id,
+            update.outcome_id,
+            update.event_id,
+            &[BookLevel::L3][..],
+        ),
+        MarketDataEvent::Trade(print) => (
+            print.market_id,
+            print.outcome_id,
+            print.event_id,
+            &[BookLevel::L2, BookLevel::L3][..],
+        ),
+        MarketDataEvent::Status(update) => (
+            update.market_id,
+            update.outcome_id,
+            update.event_id,
+            &[BookLevel::L2, BookLevel::L3][..],
+        ),
+    };
+    levels.iter().any(|&level| {
+        subscriptions.contains(&(MarketDataChannel::Market, market_id, level))
+            || outcome_id
+                .is_some_and(|id| subscriptions.contains(&(MarketDataChannel::Outcome, id, level)))
+            || event_id
+                .is_some_and(|id| subscriptions.contains(&(MarketDataChannel::Event, id, level)))
+    })
+}
+
+async fn resync(session: &mut Session, subscriptions: &Subscriptions) -> Result<(), Closed> {
+    for &subscription in subscriptions {
+        send_snapshot(session, subscription).await?;
+    }
+    Ok(())
+}
+
+async fn send_snapshot(
+    session: &mut Session,
+    (channel, id, level): (MarketDataChannel, u64, BookLevel),
+) -> Result<(), Closed> {
+    let (action, data) = match (channel, level) {
+        (MarketDataChannel::Market, BookLevel::L3) => {
+            ("get-orderbook-l3", json!({ "market_id": id }))
+        }
+        (MarketDataChannel::Market, _) => ("get-orderbook", json!({ "market_id": id })),
+        (MarketDataChannel::Outcome, _) => {
+            ("get-orderbook-by-outcome", json!({ "outcome_id": id }))
+        }
+        (MarketDataChannel::Event, _) => ("get-orderbook-by-event", json!({ "event_id": id })),
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let request = RedisRequest::new("engine", action, "Get orderbook snapshot", data);
+
+    let message = match send_request_and_wait(request_id, request, 10).await {
+        Ok(response) if response.status_code < 400 => json!({
+            "type": "snapshot",
+            "channel": channel,
+            "id": id,
+            "level": level,
+            "data": response.data
+        }),
+        Ok(response) => json!({
+            "type": "error",
+            "channel": channel,
+            "id": id,
+            "message": response.message,
+            "code": response.error_code
+        }),
+        Err(e) => json!({
+            "type": "error",
+            "channel": channel,
+            "id": id,
+            "message": format!("Failed to fetch snapshot: {}", e)
+        }),
+    };
+    send_json(session, message).await
+}
+
+async fn send_json(session: &mut Session, message: Value) -> Result<(), Closed> {
+    session.text(message.to_string()).await
+}
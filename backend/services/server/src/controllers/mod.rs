@@ -1,5 +1,11 @@
 pub mod admin_auth_controller;
 pub mod admin_event_controller;
+pub mod deposit_controller;
+pub mod leaderboard_controller;
+pub mod ledger_controller;
+pub mod market_controller;
+pub mod market_data_controller;
+pub mod notification_controller;
 pub mod order_controller;
 pub mod orderbook_controller;
 pub mod position_controller;
@@ -8,3 +14,5 @@ pub mod user_bookmark_controller;
 pub mod user_controller;
 pub mod user_event_controller;
 pub mod user_profile_controller;
+pub mod user_update_controller;
+pub mod withdrawal_controller;
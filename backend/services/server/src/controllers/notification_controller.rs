@@ -0,0 +1,126 @@
+use crate::utils::jwt::extract_user_id;
+use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::build_json_from_redis_response;
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use redis_client::RedisRequest;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+#[derive(Deserialize, Default)]
+pub struct NotificationQuery {
+    pub unread_only: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Without `ids`, every unread notification is marked read.
+#[derive(Deserialize, Default)]
+pub struct MarkReadPayload {
+    pub ids: Option<Vec<u64>>,
+}
+
+#[get("/user/notifications")]
+pub async fn get_notifications(
+    req: HttpRequest,
+    query: web::Query<NotificationQuery>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    send_notification_request(
+        "get_notifications",
+        "Get notifications",
+        json!({
+            "user_id": user_id as u64,
+            "unread_only": query.unread_only.unwrap_or(false),
+            "limit": query.limit,
+            "offset": query.offset,
+        }),
+        "Failed to fetch notifications",
+    )
+    .await
+}
+
+#[post("/user/notifications/read")]
+pub async fn mark_notifications_read(
+    req: HttpRequest,
+    payload: Option<web::Json<MarkReadPayload>>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let ids = payload.and_then(|p| p.into_inner().ids);
+
+    send_notification_request(
+        "mark_notifications_read",
+        "Mark notifications read",
+        json!({
+            "user_id": user_id as u64,
+            "ids": ids,
+        }),
+        "Failed to mark notifications read",
+    )
+    .await
+}
+
+#[get("/user/notification-preferences")]
+pub async fn get_notification_preferences(req: HttpRequest) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    send_notification_request(
+        "get_notification_preferences",
+        "Get notification preferences",
+        json!({ "user_id": user_id as u64 }),
+        "Failed to fetch notification preferences",
+    )
+    .await
+}
+
+// Body maps kinds (order_filled, event_resolved, payout) to whether they are enabled.
+#[put("/user/notification-preferences")]
+pub async fn update_notification_preferences(
+    req: HttpRequest,
+    payload: web::Json<Map<String, Value>>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    send_notification_request(
+        "update_notification_preferences",
+        "Update notification preferences",
+        json!({
+            "user_id": user_id as u64,
+            "preferences": payload.into_inner(),
+        }),
+        "Failed to update notification preferences",
+    )
+    .await
+}
+
+async fn send_notification_request(
+    action: &str,
+    message: &str,
+    data: Value,
+    failure: &str,
+) -> HttpResponse {
+    let request_id = Uuid::new_v4().to_string();
+    let request = RedisRequest::new("db_worker", action, message, data);
+
+    match send_request_and_wait(request_id, request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": failure,
+            "error": e
+        })),
+    }
+}
@@ -4,6 +4,7 @@ use crate::types::order_types::{
 };
 use crate::utils::jwt::extract_user_id;
 use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::error_status;
 use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
 use redis_client::RedisRequest;
 use serde_json::json;
@@ -41,6 +42,13 @@ pub async fn place_order(req: HttpRequest, body: web::Json<PlaceOrderInput>) ->
         }));
     }
 
+    if body.order_type == OrderTypeInput::Market && body.hidden {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": "Hidden orders must be limit orders"
+        }));
+    }
+
     let order_data = json!({
         "market_id": body.market_id,
         "user_id": user_id as u64,
@@ -49,6 +57,8 @@ pub async fn place_order(req: HttpRequest, body: web::Json<PlaceOrderInput>) ->
         "remaining_qty": body.quantity,
         "side": order_side,
         "order_type": order_type_str,
+        "hidden": body.hidden,
+        "client_order_id": body.client_order_id,
     });
 
     let request_id = Uuid::new_v4().to_string();
@@ -58,11 +68,11 @@ pub async fn place_order(req: HttpRequest, body: web::Json<PlaceOrderInput>) ->
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -101,11 +111,11 @@ pub async fn cancel_order(req: HttpRequest, path: web::Path<u64>) -> impl Respon
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -163,11 +173,11 @@ pub async fn modify_order(
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -185,6 +195,157 @@ pub async fn modify_order(
     }
 }
 
+#[post("/orders/client/{client_order_id}/cancel")]
+pub async fn cancel_order_by_client_id(
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    let client_order_id = path.into_inner();
+
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let order_data = json!({
+        "client_order_id": client_order_id,
+        "user_id": user_id as u64,
+    });
+
+    let request_id = Uuid::new_v4().to_string();
+    let redis_request =
+        RedisRequest::new("engine", "cancel-order", "Cancel order request", order_data);
+
+    match send_request_and_wait(request_id, redis_request, 10).await {
+        Ok(response) => {
+            if response.status_code >= 400 {
+                let status = error_status(&response);
+                return HttpResponse::build(status).json(json!({
+                    "status": if response.success { "success" } else { "error" },
+                    "message": response.message,
+                    "code": response.error_code,
+                    "data": response.data
+                }));
+            }
+            HttpResponse::Ok().json(json!({
+                "status": "success",
+                "message": response.message,
+                "data": response.data
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to cancel order",
+            "error": e
+        })),
+    }
+}
+
+#[put("/orders/client/{client_order_id}")]
+pub async fn modify_order_by_client_id(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<ModifyOrderInput>,
+) -> impl Responder {
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": e.to_string()
+        }));
+    }
+
+    let client_order_id = path.into_inner();
+
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let mut order_data = json!({
+        "client_order_id": client_order_id,
+        "user_id": user_id as u64,
+    });
+
+    if let Some(price) = body.price {
+        order_data["price"] = json!(price);
+    }
+    if let Some(quantity) = body.quantity {
+        order_data["original_qty"] = json!(quantity);
+        order_data["remaining_qty"] = json!(quantity);
+    }
+
+    let request_id = Uuid::new_v4().to_string();
+    let redis_request =
+        RedisRequest::new("engine", "modify-order", "Modify order request", order_data);
+
+    match send_request_and_wait(request_id, redis_request, 10).await {
+        Ok(response) => {
+            if response.status_code >= 400 {
+                let status = error_status(&response);
+                return HttpResponse::build(status).json(json!({
+                    "status": if response.success { "success" } else { "error" },
+                    "message": response.message,
+                    "code": response.error_code,
+                    "data": response.data
+                }));
+            }
+            HttpResponse::Ok().json(json!({
+                "status": "success",
+                "message": response.message,
+                "data": response.data
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to modify order",
+            "error": e
+        })),
+    }
+}
+
+#[get("/orders/client/{client_order_id}")]
+pub async fn get_order_by_client_id(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let client_order_id = path.into_inner();
+
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let order_data = json!({
+        "client_order_id": client_order_id,
+        "user_id": user_id as u64,
+    });
+
+    let request_id = Uuid::new_v4().to_string();
+    let redis_request = RedisRequest::new(
+        "engine",
+        "get-client-order",
+        "Get order by client order ID",
+        order_data,
+    );
+
+    match send_request_and_wait(request_id, redis_request, 10).await {
+        Ok(response) => {
+            if response.status_code >= 400 {
+                let status = error_status(&response);
+                return HttpResponse::build(status).json(json!({
+                    "status": "error",
+                    "message": response.message,
+                    "code": response.error_code,
+                    "data": response.data
+                }));
+            }
+            HttpResponse::Ok().json(response.data)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch order",
+            "error": e
+        })),
+    }
+}
+
 #[post("/orders/split")]
 pub async fn split_order(req: HttpRequest, body: web::Json<SplitOrderInput>) -> impl Responder {
     if let Err(e) = body.validate() {
@@ -213,11 +374,11 @@ pub async fn split_order(req: HttpRequest, body: web::Json<SplitOrderInput>) ->
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -262,11 +423,11 @@ pub async fn merge_order(req: HttpRequest, body: web::Json<MergeOrderInput>) ->
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -306,11 +467,11 @@ pub async fn get_open_orders(req: HttpRequest) -> impl Responder {
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -367,11 +528,11 @@ pub async fn get_order_status(req: HttpRequest, path: web::Path<u64>) -> impl Re
                         "data": response.data
                     }));
                 }
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -407,11 +568,11 @@ pub async fn get_order_history(req: HttpRequest) -> impl Responder {
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -442,11 +603,11 @@ pub async fn get_orders_by_user(req: HttpRequest, path: web::Path<u64>) -> impl
     match send_request_and_wait(request_id, read_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -477,11 +638,11 @@ pub async fn get_orders_by_market(req: HttpRequest, path: web::Path<u64>) -> imp
     match send_request_and_wait(request_id, read_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -1,28 +1,79 @@
 use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::error_status;
 use actix_web::{get, web, HttpResponse, Responder};
 use redis_client::RedisRequest;
+use serde::Deserialize;
 use serde_json::json;
 use uuid::Uuid;
 
+// `depth` keeps the best N levels per side, `group` buckets prices into bands of that many
+// cents and `cumulative` adds running totals. The engine applies them before replying.
+#[derive(Deserialize)]
+pub struct OrderbookQuery {
+    pub depth: Option<usize>,
+    pub group: Option<u64>,
+    pub cumulative: Option<bool>,
+}
+
 #[get("/orderbooks/market/{market_id}")]
-pub async fn get_orderbook_by_market(path: web::Path<u64>) -> impl Responder {
+pub async fn get_orderbook_by_market(
+    path: web::Path<u64>,
+    query: web::Query<OrderbookQuery>,
+) -> impl Responder {
     let market_id = path.into_inner();
     let request_id = Uuid::new_v4().to_string();
     let redis_request = RedisRequest::new(
         "engine",
         "get-orderbook",
         "Get orderbook snapshot",
+        json!({
+            "market_id": market_id,
+            "depth": query.depth,
+            "group": query.group,
+            "cumulative": query.cumulative.unwrap_or(false)
+        }),
+    );
+
+    match send_request_and_wait(request_id, redis_request, 10).await {
+        Ok(response) => {
+            if response.status_code >= 400 {
+                let status = error_status(&response);
+                return HttpResponse::build(status).json(json!({
+                    "status": if response.success { "success" } else { "error" },
+                    "message": response.message,
+                    "code": response.error_code,
+                    "data": response.data
+                }));
+            }
+            HttpResponse::Ok().json(response.data)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch orderbook",
+            "error": e
+        })),
+    }
+}
+
+#[get("/orderbooks/market/{market_id}/l3")]
+pub async fn get_orderbook_l3_by_market(path: web::Path<u64>) -> impl Responder {
+    let market_id = path.into_inner();
+    let request_id = Uuid::new_v4().to_string();
+    let redis_request = RedisRequest::new(
+        "engine",
+        "get-orderbook-l3",
+        "Get L3 orderbook snapshot",
         json!({ "market_id": market_id }),
     );
 
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -37,24 +88,32 @@ pub async fn get_orderbook_by_market(path: web::Path<u64>) -> impl Responder {
 }
 
 #[get("/orderbooks/event/{event_id}")]
-pub async fn get_orderbooks_by_event(path: web::Path<u64>) -> impl Responder {
+pub async fn get_orderbooks_by_event(
+    path: web::Path<u64>,
+    query: web::Query<OrderbookQuery>,
+) -> impl Responder {
     let event_id = path.into_inner();
     let request_id = Uuid::new_v4().to_string();
     let redis_request = RedisRequest::new(
         "engine",
         "get-orderbook-by-event",
         "Get event orderbooks",
-        json!({ "event_id": event_id }),
+        json!({
+            "event_id": event_id,
+            "depth": query.depth,
+            "group": query.group,
+            "cumulative": query.cumulative.unwrap_or(false)
+        }),
     );
 
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -69,24 +128,32 @@ pub async fn get_orderbooks_by_event(path: web::Path<u64>) -> impl Responder {
 }
 
 #[get("/orderbooks/outcome/{outcome_id}")]
-pub async fn get_orderbooks_by_outcome(path: web::Path<u64>) -> impl Responder {
+pub async fn get_orderbooks_by_outcome(
+    path: web::Path<u64>,
+    query: web::Query<OrderbookQuery>,
+) -> impl Responder {
     let outcome_id = path.into_inner();
     let request_id = Uuid::new_v4().to_string();
     let redis_request = RedisRequest::new(
         "engine",
         "get-orderbook-by-outcome",
         "Get outcome orderbooks",
-        json!({ "outcome_id": outcome_id }),
+        json!({
+            "outcome_id": outcome_id,
+            "depth": query.depth,
+            "group": query.group,
+            "cumulative": query.cumulative.unwrap_or(false)
+        }),
     );
 
     match send_request_and_wait(request_id, redis_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
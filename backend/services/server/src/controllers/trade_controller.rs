@@ -1,5 +1,6 @@
 use crate::utils::jwt::extract_user_id;
 use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::error_status;
 use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
 use redis_client::RedisRequest;
 use serde_json::json;
@@ -32,11 +33,11 @@ pub async fn get_trades(req: HttpRequest) -> impl Responder {
                         "data": response.data
                     }));
                 }
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -74,11 +75,11 @@ pub async fn get_trade_by_id(req: HttpRequest, path: web::Path<String>) -> impl
                         "data": response.data
                     }));
                 }
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -109,11 +110,11 @@ pub async fn get_trades_by_market(req: HttpRequest, path: web::Path<u64>) -> imp
     match send_request_and_wait(request_id, read_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -156,11 +157,11 @@ pub async fn get_trades_by_user_id(req: HttpRequest, path: web::Path<u64>) -> im
                         "data": response.data
                     }));
                 }
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -1,7 +1,9 @@
 use crate::services::db_event_publisher::publish_db_event;
+use crate::services::payment_provider::dev_onramp_enabled;
 use crate::types::auth_types::{LoginUserInput, SignUpUserInput};
 use crate::utils::jwt::{create_jwt, extract_user_id};
 use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::error_status;
 use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::Utc;
@@ -284,30 +286,20 @@ pub async fn get_balance(req: HttpRequest) -> impl Responder {
     match send_request_and_wait(request_id, balance_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                if response.status_code == 404 {
-                    return HttpResponse::NotFound().json(json!({
-                        "status": "error",
-                        "message": response.message,
-                        "data": response.data
-                    }));
-                } else if response.status_code == 400 {
-                    return HttpResponse::BadRequest().json(json!({
-                        "status": "error",
-                        "message": response.message,
-                        "data": response.data
-                    }));
-                } else {
-                    return HttpResponse::InternalServerError().json(json!({
-                        "status": "error",
-                        "message": response.message,
-                        "data": response.data
-                    }));
-                }
+                return HttpResponse::build(error_status(&response)).json(json!({
+                    "status": "error",
+                    "message": response.message,
+                    "code": response.error_code,
+                    "data": response.data
+                }));
             }
             HttpResponse::Ok().json(json!({
                 "status": "success",
                 "message": response.message,
-                "balance": response.data.get("balance").and_then(|v| v.as_i64()).unwrap_or(0)
+                "balance": response.data.get("balance").and_then(|v| v.as_i64()).unwrap_or(0),
+                "available": response.data.get("available").and_then(|v| v.as_i64()).unwrap_or(0),
+                "locked": response.data.get("locked").and_then(|v| v.as_i64()).unwrap_or(0),
+                "total": response.data.get("total").and_then(|v| v.as_i64()).unwrap_or(0)
             }))
         }
         Err(e) => HttpResponse::InternalServerError().json(json!({
@@ -320,6 +312,13 @@ pub async fn get_balance(req: HttpRequest) -> impl Responder {
 
 #[post("/onramp")]
 pub async fn onramp(req: HttpRequest, body: web::Json<serde_json::Value>) -> impl Responder {
+    if !dev_onramp_enabled() {
+        return HttpResponse::Forbidden().json(json!({
+            "status": "error",
+            "message": "Onramp is disabled, deposit through /deposits instead"
+        }));
+    }
+
     let user_id = match extract_user_id(&req) {
         Ok(id) => id,
         Err(resp) => return resp,
@@ -358,25 +357,12 @@ pub async fn onramp(req: HttpRequest, body: web::Json<serde_json::Value>) -> imp
     match send_request_and_wait(request_id, onramp_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                if response.status_code == 404 {
-                    return HttpResponse::NotFound().json(json!({
-                        "status": "error",
-                        "message": response.message,
-                        "data": response.data
-                    }));
-                } else if response.status_code == 400 {
-                    return HttpResponse::BadRequest().json(json!({
-                        "status": "error",
-                        "message": response.message,
-                        "data": response.data
-                    }));
-                } else {
-                    return HttpResponse::InternalServerError().json(json!({
-                        "status": "error",
-                        "message": response.message,
-                        "data": response.data
-                    }));
-                }
+                return HttpResponse::build(error_status(&response)).json(json!({
+                    "status": "error",
+                    "message": response.message,
+                    "code": response.error_code,
+                    "data": response.data
+                }));
             }
             HttpResponse::Ok().json(json!({
                 "status": "success",
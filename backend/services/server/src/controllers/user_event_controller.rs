@@ -1,5 +1,6 @@
 use crate::types::event_types::EventSearchQueryRequest;
 use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::error_status;
 use actix_web::{get, web, HttpResponse, Responder};
 use log::warn;
 use redis_client::{RedisManager, RedisRequest};
@@ -33,11 +34,11 @@ pub async fn get_all_events() -> impl Responder {
     match send_request_and_wait(request_id, read_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -92,11 +93,11 @@ pub async fn get_event_by_id(path: web::Path<u64>) -> impl Responder {
                         "data": response.data
                     }));
                 }
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -150,11 +151,11 @@ pub async fn search_events(query: web::Query<EventSearchQueryRequest>) -> impl R
     match send_request_and_wait(request_id, read_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -1,5 +1,6 @@
 use crate::utils::jwt::extract_user_id;
 use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::error_status;
 use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
 use redis_client::RedisRequest;
 use serde_json::json;
@@ -48,11 +49,11 @@ pub async fn get_user_by_id(req: HttpRequest, path: web::Path<u64>) -> impl Resp
                         "data": response.data
                     }));
                 }
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
@@ -130,11 +131,11 @@ pub async fn get_all_users(req: HttpRequest) -> impl Responder {
     match send_request_and_wait(request_id, read_request, 10).await {
         Ok(response) => {
             if response.status_code >= 400 {
-                let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+                let status = error_status(&response);
                 return HttpResponse::build(status).json(json!({
                     "status": if response.success { "success" } else { "error" },
                     "message": response.message,
+                    "code": response.error_code,
                     "data": response.data
                 }));
             }
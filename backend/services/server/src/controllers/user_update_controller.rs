@@ -0,0 +1,93 @@
+use crate::services::user_update_consumer::UserUpdateHub;
+use crate::types::user_update_types::UserUpdate;
+use crate::utils::jwt::{extract_user_id, verify_jwt};
+use actix_web::{get, rt, web, Error, HttpRequest, HttpResponse};
+use actix_ws::{Message, MessageStream, Session};
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+#[derive(Deserialize)]
+pub struct WsAuthQuery {
+    pub token: Option<String>,
+}
+
+// Private feed of the caller's order, fill, balance and position updates. Browsers cannot
+// set headers on a WebSocket handshake, so the JWT may also be passed as `?token=`.
+#[get("/ws/user")]
+pub async fn user_updates_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<WsAuthQuery>,
+    hub: web::Data<UserUpdateHub>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match authenticate(&req, query.token.as_deref()) {
+        Ok(id) => id as u64,
+        Err(response) => return Ok(response),
+    };
+
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+    rt::spawn(run_session(session, stream, hub.subscribe(user_id)));
+    Ok(response)
+}
+
+fn authenticate(req: &HttpRequest, token: Option<&str>) -> Result<i64, HttpResponse> {
+    let Some(token) = token else {
+        return extract_user_id(req);
+    };
+
+    let jwt_secret = env::var("JWT_SECRET").map_err(|_| {
+        HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "JWT secret not configured"
+        }))
+    })?;
+
+    verify_jwt(token, &jwt_secret).map_err(|_| {
+        HttpResponse::Unauthorized().json(json!({
+            "status": "error",
+            "message": "Invalid or expired token"
+        }))
+    })
+}
+
+async fn run_session(
+    mut session: Session,
+    mut stream: MessageStream,
+    mut updates: broadcast::Receiver<Arc<UserUpdate>>,
+) {
+    loop {
+        let sent = tokio::select! {
+            message = stream.recv() => match message {
+                Some(Ok(Message::Ping(bytes))) => session.pong(&bytes).await,
+                Some(Ok(Message::Close(reason))) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                Some(Ok(_)) => Ok(()),
+                Some(Err(_)) | None => break,
+            },
+            update = updates.recv() => match update {
+                Ok(update) => match serde_json::to_string(&*update) {
+                    Ok(json) => session.text(json).await,
+                    Err(_) => Ok(()),
+                },
+                // Updates were dropped; the client has to refetch its state over REST
+                Err(RecvError::Lagged(_)) => {
+                    session
+                        .text(json!({ "type": "resync_required" }).to_string())
+                        .await
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if sent.is_err() {
+            return;
+        }
+    }
+
+    let _ = session.close(None).await;
+}
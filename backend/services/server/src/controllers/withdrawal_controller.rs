@@ -0,0 +1,172 @@
+use crate::types::withdrawal_types::{RejectWithdrawalInput, WithdrawalInput, WithdrawalQuery};
+use crate::utils::jwt::extract_user_id;
+use crate::utils::redis_stream::send_request_and_wait;
+use crate::utils::responses::build_json_from_redis_response;
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use redis_client::RedisRequest;
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+#[post("/withdrawals")]
+pub async fn request_withdrawal(
+    req: HttpRequest,
+    body: web::Json<WithdrawalInput>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": e.to_string()
+        }));
+    }
+
+    let request_id = Uuid::new_v4().to_string();
+    let withdrawal_request = RedisRequest::new(
+        "engine",
+        "request-withdrawal",
+        "Request a withdrawal",
+        json!({
+            "user_id": user_id as u64,
+            "amount": body.amount,
+        }),
+    );
+
+    match send_request_and_wait(request_id, withdrawal_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to request withdrawal",
+            "error": e
+        })),
+    }
+}
+
+#[get("/withdrawals")]
+pub async fn get_withdrawals(
+    req: HttpRequest,
+    query: web::Query<WithdrawalQuery>,
+) -> impl Responder {
+    let user_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let read_request = RedisRequest::new(
+        "db_worker",
+        "get_withdrawals_by_user",
+        "Get user withdrawals",
+        json!({
+            "user_id": user_id as u64,
+            "limit": query.limit,
+            "offset": query.offset,
+        }),
+    );
+
+    match send_request_and_wait(request_id, read_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch withdrawals",
+            "error": e
+        })),
+    }
+}
+
+#[get("/withdrawals/all")]
+pub async fn get_all_withdrawals(query: web::Query<WithdrawalQuery>) -> impl Responder {
+    let request_id = Uuid::new_v4().to_string();
+    let read_request = RedisRequest::new(
+        "db_worker",
+        "get_withdrawals",
+        "Get withdrawals",
+        json!({
+            "status": query.status,
+            "limit": query.limit,
+            "offset": query.offset,
+        }),
+    );
+
+    match send_request_and_wait(request_id, read_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to fetch withdrawals",
+            "error": e
+        })),
+    }
+}
+
+#[post("/withdrawals/{withdrawal_id}/approve")]
+pub async fn approve_withdrawal(req: HttpRequest, path: web::Path<u64>) -> impl Responder {
+    let admin_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let approve_request = RedisRequest::new(
+        "engine",
+        "approve-withdrawal",
+        "Approve a withdrawal",
+        json!({
+            "withdrawal_id": path.into_inner(),
+            "admin_id": admin_id as u64,
+        }),
+    );
+
+    match send_request_and_wait(request_id, approve_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to approve withdrawal",
+            "error": e
+        })),
+    }
+}
+
+#[post("/withdrawals/{withdrawal_id}/reject")]
+pub async fn reject_withdrawal(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    body: Option<web::Json<RejectWithdrawalInput>>,
+) -> impl Responder {
+    let admin_id = match extract_user_id(&req) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": e.to_string()
+        }));
+    }
+
+    let request_id = Uuid::new_v4().to_string();
+    let reject_request = RedisRequest::new(
+        "engine",
+        "reject-withdrawal",
+        "Reject a withdrawal",
+        json!({
+            "withdrawal_id": path.into_inner(),
+            "admin_id": admin_id as u64,
+            "reason": body.reason,
+        }),
+    );
+
+    match send_request_and_wait(request_id, reject_request, 10).await {
+        Ok(response) => build_json_from_redis_response(&response),
+        Err(e) => HttpResponse::InternalServerError().json(json!({
+            "status": "error",
+            "message": "Failed to reject withdrawal",
+            "error": e
+        })),
+    }
+}
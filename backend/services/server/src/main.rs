@@ -8,12 +8,27 @@ use crate::controllers::admin_auth_controller::signin_admin;
 use crate::controllers::admin_event_controller::{
     create_event, delete_event, resolve_event, update_event,
 };
+use crate::controllers::deposit_controller::{create_deposit, deposit_webhook, get_deposits};
+use crate::controllers::leaderboard_controller::{get_leaderboard, update_leaderboard_privacy};
+use crate::controllers::ledger_controller::{check_ledger, get_ledger};
+use crate::controllers::market_controller::{
+    get_event_probabilities, get_event_ticker, get_market_candles, get_market_tickers,
+    list_markets,
+};
+use crate::controllers::market_data_controller::market_data_ws;
+use crate::controllers::user_update_controller::user_updates_ws;
+use crate::controllers::notification_controller::{
+    get_notification_preferences, get_notifications, mark_notifications_read,
+    update_notification_preferences,
+};
 use crate::controllers::order_controller::{
-    cancel_order, get_open_orders, get_order_history, get_order_status, get_orders_by_market,
-    get_orders_by_user, merge_order, modify_order, place_order, split_order,
+    cancel_order, cancel_order_by_client_id, get_open_orders, get_order_by_client_id,
+    get_order_history, get_order_status, get_orders_by_market, get_orders_by_user, merge_order,
+    modify_order, modify_order_by_client_id, place_order, split_order,
 };
 use crate::controllers::orderbook_controller::{
-    get_orderbook_by_market, get_orderbooks_by_event, get_orderbooks_by_outcome,
+    get_orderbook_by_market, get_orderbook_l3_by_market, get_orderbooks_by_event,
+    get_orderbooks_by_outcome,
 };
 use crate::controllers::position_controller::{
     get_portfolio, get_position_by_market, get_positions, get_positions_history,
@@ -27,10 +42,17 @@ use crate::controllers::user_bookmark_controller::{
 use crate::controllers::user_controller::{get_balance, onramp, signin_user, signup_user};
 use crate::controllers::user_event_controller::{get_all_events, get_event_by_id, search_events};
 use crate::controllers::user_profile_controller::{get_all_users, get_user_by_id};
+use crate::controllers::withdrawal_controller::{
+    approve_withdrawal, get_all_withdrawals, get_withdrawals, reject_withdrawal,
+    request_withdrawal,
+};
 use crate::middleware::admin::AdminMiddleware;
 use crate::middleware::auth::AuthMiddleware;
 use crate::services::db_read_response_consumer::start_db_read_response_consumer;
+use crate::services::market_data_consumer::start_market_data_consumer;
+use crate::services::payment_provider::payment_provider_from_env;
 use crate::services::response_consumer::start_response_consumer;
+use crate::services::user_update_consumer::start_user_update_consumer;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use dotenvy::dotenv;
 use redis_client::RedisManager;
@@ -61,8 +83,18 @@ async fn run() -> std::io::Result<()> {
     start_response_consumer().await;
     start_db_read_response_consumer().await;
 
+    let market_data_hub = web::Data::new(start_market_data_consumer());
+    let user_update_hub = web::Data::new(start_user_update_consumer());
+    let payment_provider = web::Data::from(
+        payment_provider_from_env()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+    );
+
     HttpServer::new(move || {
         App::new()
+            .app_data(payment_provider.clone())
+            .app_data(market_data_hub.clone())
+            .app_data(user_update_hub.clone())
             .service(signup_user)
             .service(signin_user)
             .service(signin_admin)
@@ -70,17 +102,34 @@ async fn run() -> std::io::Result<()> {
             .service(search_events)
             .service(get_event_by_id)
             .service(get_orderbook_by_market)
+            .service(get_orderbook_l3_by_market)
             .service(get_orderbooks_by_event)
             .service(get_orderbooks_by_outcome)
+            .service(list_markets)
+            .service(get_market_candles)
+            .service(get_market_tickers)
+            .service(get_event_ticker)
+            .service(get_event_probabilities)
+            .service(get_leaderboard)
+            .service(market_data_ws)
+            .service(user_updates_ws)
+            .service(deposit_webhook)
             .route("/health", web::get().to(health))
             .service(
                 web::scope("")
                     .wrap(AuthMiddleware)
                     .service(get_balance)
                     .service(onramp)
+                    .service(create_deposit)
+                    .service(get_deposits)
+                    .service(request_withdrawal)
+                    .service(get_withdrawals)
                     .service(place_order)
                     .service(cancel_order)
                     .service(modify_order)
+                    .service(cancel_order_by_client_id)
+                    .service(modify_order_by_client_id)
+                    .service(get_order_by_client_id)
                     .service(split_order)
                     .service(merge_order)
                     .service(get_open_orders)
@@ -88,6 +137,7 @@ async fn run() -> std::io::Result<()> {
                     .service(get_order_status)   
                     .service(get_orders_by_user)
                     .service(get_orders_by_market)
+                    .service(get_ledger)
                     .service(get_positions)
                     .service(get_portfolio)
                     .service(get_position_by_market)
@@ -95,6 +145,11 @@ async fn run() -> std::io::Result<()> {
                     .service(add_market_bookmark)
                     .service(remove_market_bookmark)
                     .service(get_user_bookmarks)
+                    .service(update_leaderboard_privacy)
+                    .service(get_notifications)
+                    .service(mark_notifications_read)
+                    .service(get_notification_preferences)
+                    .service(update_notification_preferences)
                     .service(get_for_you_markets)
                     .service(get_trades)
                     .service(get_trade_by_id)
@@ -108,6 +163,10 @@ async fn run() -> std::io::Result<()> {
                             .service(update_event)
                             .service(resolve_event)
                             .service(delete_event)
+                            .service(check_ledger)
+                            .service(get_all_withdrawals)
+                            .service(approve_withdrawal)
+                            .service(reject_withdrawal)
                             .service(get_all_users),
                     ),
             )
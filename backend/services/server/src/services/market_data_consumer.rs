@@ -0,0 +1,41 @@
+use crate::utils::redis_stream::spawn_stream_tail;
+use engine::types::market_data_types::MarketDataEvent;
+use log::warn;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+const MARKET_DATA_STREAM: &str = "market_data";
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// Fans the engine's `market_data` stream out to every WebSocket session.
+#[derive(Clone)]
+pub struct MarketDataHub {
+    sender: broadcast::Sender<Arc<MarketDataEvent>>,
+}
+
+impl MarketDataHub {
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<MarketDataEvent>> {
+        self.sender.subscribe()
+    }
+}
+
+// Subscribers get their starting state from a snapshot, so reading starts at the current
+// tail of the stream.
+pub fn start_market_data_consumer() -> MarketDataHub {
+    let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+    let hub = MarketDataHub {
+        sender: sender.clone(),
+    };
+
+    spawn_stream_tail(MARKET_DATA_STREAM, move |data| {
+        match serde_json::from_str::<MarketDataEvent>(data) {
+            // Nobody listening is fine; the event is simply not needed
+            Ok(event) => {
+                let _ = sender.send(Arc::new(event));
+            }
+            Err(e) => warn!("Skipping malformed market data event: {}", e),
+        }
+    });
+
+    hub
+}
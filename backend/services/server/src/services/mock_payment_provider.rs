@@ -0,0 +1,115 @@
+use crate::services::payment_provider::{DepositIntent, DepositWebhook, PaymentProvider};
+use futures_util::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Local stand-in for a real provider. Intents are confirmed by posting
+/// `{"deposit_id": "...", "status": "succeeded"}` to the webhook, signed with the hex
+/// HMAC-SHA256 of the body under `PAYMENT_WEBHOOK_SECRET`.
+pub struct MockPaymentProvider {
+    webhook_secret: String,
+}
+
+impl MockPaymentProvider {
+    pub fn new(webhook_secret: String) -> Self {
+        Self { webhook_secret }
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(self.webhook_secret.as_bytes())
+            .expect("HMAC accepts keys of any length")
+    }
+}
+
+impl PaymentProvider for MockPaymentProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn signature_header(&self) -> &'static str {
+        "X-Mock-Signature"
+    }
+
+    fn create_intent(
+        &self,
+        _user_id: u64,
+        _amount: i64,
+    ) -> BoxFuture<'_, Result<DepositIntent, String>> {
+        Box::pin(async move {
+            Ok(DepositIntent {
+                deposit_id: format!("mock_{}", Uuid::new_v4().simple()),
+                checkout_url: None,
+            })
+        })
+    }
+
+    fn verify_webhook(&self, payload: &[u8], signature: &str) -> Result<DepositWebhook, String> {
+        let signature = hex::decode(signature.trim()).map_err(|_| "Malformed signature")?;
+        let mut mac = self.mac();
+        mac.update(payload);
+        mac.verify_slice(&signature)
+            .map_err(|_| "Invalid signature".to_string())?;
+
+        serde_json::from_slice(payload).map_err(|e| format!("Invalid webhook payload: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::payment_provider::DepositOutcome;
+
+    const SECRET: &str = "webhook-secret";
+    const BODY: &[u8] = br#"{"deposit_id": "mock_1", "status": "succeeded"}"#;
+
+    fn sign(body: &[u8]) -> String {
+        let mut mac = HmacSha256::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn accepts_a_body_signed_with_the_secret() {
+        let provider = MockPaymentProvider::new(SECRET.to_string());
+
+        let webhook = provider.verify_webhook(BODY, &sign(BODY)).unwrap();
+
+        assert_eq!(webhook.deposit_id, "mock_1");
+        assert!(matches!(webhook.status, DepositOutcome::Succeeded));
+    }
+
+    #[test]
+    fn rejects_a_signature_made_with_another_secret() {
+        let provider = MockPaymentProvider::new("other-secret".to_string());
+
+        let err = provider.verify_webhook(BODY, &sign(BODY)).unwrap_err();
+
+        assert_eq!(err, "Invalid signature");
+    }
+
+    #[test]
+    fn rejects_a_signature_that_is_not_hex() {
+        let provider = MockPaymentProvider::new(SECRET.to_string());
+
+        for signature in ["not-hex", "abc"] {
+            let err = provider.verify_webhook(BODY, signature).unwrap_err();
+            assert_eq!(err, "Malformed signature");
+        }
+        let err = provider.verify_webhook(BODY, "").unwrap_err();
+        assert_eq!(err, "Invalid signature");
+    }
+
+    #[test]
+    fn rejects_a_body_changed_after_signing() {
+        let provider = MockPaymentProvider::new(SECRET.to_string());
+        let signature = sign(BODY);
+        let changed = br#"{"deposit_id": "mock_2", "status": "succeeded"}"#;
+
+        let err = provider.verify_webhook(changed, &signature).unwrap_err();
+
+        assert_eq!(err, "Invalid signature");
+    }
+}
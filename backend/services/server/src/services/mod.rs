@@ -1,3 +1,7 @@
 pub mod db_event_publisher;
 pub mod db_read_response_consumer;
+pub mod market_data_consumer;
+pub mod mock_payment_provider;
+pub mod payment_provider;
 pub mod response_consumer;
+pub mod user_update_consumer;
@@ -0,0 +1,63 @@
+use crate::services::mock_payment_provider::MockPaymentProvider;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::env;
+use std::sync::Arc;
+
+/// What the provider hands back when a deposit is started. `deposit_id` is the provider's
+/// own reference and is what its webhooks later refer to.
+#[derive(Debug, Clone)]
+pub struct DepositIntent {
+    pub deposit_id: String,
+    pub checkout_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositOutcome {
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepositWebhook {
+    pub deposit_id: String,
+    pub status: DepositOutcome,
+}
+
+pub trait PaymentProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Header the provider puts the webhook signature in.
+    fn signature_header(&self) -> &'static str;
+
+    fn create_intent(
+        &self,
+        user_id: u64,
+        amount: i64,
+    ) -> BoxFuture<'_, Result<DepositIntent, String>>;
+
+    /// Checks the signature against the raw body before anything in it is trusted.
+    fn verify_webhook(&self, payload: &[u8], signature: &str) -> Result<DepositWebhook, String>;
+}
+
+/// Both settings are required, so a deployment never falls back to the mock provider by
+/// accident.
+pub fn payment_provider_from_env() -> Result<Arc<dyn PaymentProvider>, String> {
+    let provider =
+        env::var("PAYMENT_PROVIDER").map_err(|_| "PAYMENT_PROVIDER must be set".to_string())?;
+    let webhook_secret = env::var("PAYMENT_WEBHOOK_SECRET")
+        .map_err(|_| "PAYMENT_WEBHOOK_SECRET must be set".to_string())?;
+
+    match provider.as_str() {
+        "mock" => Ok(Arc::new(MockPaymentProvider::new(webhook_secret))),
+        other => Err(format!("Unsupported PAYMENT_PROVIDER: {}", other)),
+    }
+}
+
+/// The free `/onramp` credit is only for local development.
+pub fn dev_onramp_enabled() -> bool {
+    env::var("DEV_ONRAMP_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
@@ -0,0 +1,62 @@
+use crate::types::user_update_types::UserUpdate;
+use crate::utils::redis_stream::spawn_stream_tail;
+use engine::types::db_event_types::DbEvent;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+const DB_EVENTS_STREAM: &str = "db_events";
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Turns the engine's `db_events` into per-user updates for the private WebSocket. Each user
+/// with an open session has a channel of their own, so a session only ever sees its own
+/// updates.
+#[derive(Clone)]
+pub struct UserUpdateHub {
+    senders: Arc<Mutex<HashMap<u64, broadcast::Sender<Arc<UserUpdate>>>>>,
+}
+
+impl UserUpdateHub {
+    pub fn subscribe(&self, user_id: u64) -> broadcast::Receiver<Arc<UserUpdate>> {
+        let mut senders = self.senders.lock().unwrap_or_else(|e| e.into_inner());
+        senders
+            .entry(user_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    // A user whose sessions have all closed is dropped on their next update
+    fn route(&self, update: UserUpdate) {
+        let mut senders = self.senders.lock().unwrap_or_else(|e| e.into_inner());
+        let user_id = update.user_id();
+        let delivered = senders
+            .get(&user_id)
+            .map(|sender| sender.send(Arc::new(update)).is_ok());
+        if delivered == Some(false) {
+            senders.remove(&user_id);
+        }
+    }
+}
+
+// Follows `db_events` from its tail on its own; db_worker reads the stream with its own
+// position, so nothing here affects persistence.
+pub fn start_user_update_consumer() -> UserUpdateHub {
+    let hub = UserUpdateHub {
+        senders: Arc::new(Mutex::new(HashMap::new())),
+    };
+
+    let router = hub.clone();
+    spawn_stream_tail(DB_EVENTS_STREAM, move |data| {
+        match serde_json::from_str::<DbEvent>(data) {
+            Ok(event) => {
+                if let Some(update) = UserUpdate::from_db_event(event) {
+                    router.route(update);
+                }
+            }
+            Err(e) => warn!("Skipping malformed DB event: {}", e),
+        }
+    });
+
+    hub
+}
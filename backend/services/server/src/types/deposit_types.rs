@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct DepositInput {
+    #[validate(range(min = 1, message = "Amount must be greater than 0"))]
+    pub amount: i64,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct DepositQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketDataChannel {
+    Market,
+    Outcome,
+    Event,
+}
+
+/// Aggregated levels (`l2`) or individual orders (`l3`, markets only).
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BookLevel {
+    #[default]
+    L2,
+    L3,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum MarketDataClientMessage {
+    Subscribe {
+        channel: MarketDataChannel,
+        id: u64,
+        #[serde(default)]
+        level: BookLevel,
+    },
+    Unsubscribe {
+        channel: MarketDataChannel,
+        id: u64,
+        #[serde(default)]
+        level: BookLevel,
+    },
+}
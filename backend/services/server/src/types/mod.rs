@@ -1,5 +1,9 @@
 pub mod admin_types;
 pub mod auth_types;
+pub mod deposit_types;
 pub mod event_types;
+pub mod market_data_types;
 pub mod order_types;
 pub mod user_types;
+pub mod user_update_types;
+pub mod withdrawal_types;
@@ -17,6 +17,15 @@ pub struct PlaceOrderInput {
     pub price: Option<u64>,
     #[validate(range(min = 1, message = "Quantity must be greater than 0"))]
     pub quantity: u64,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Client order ID must be between 1 and 64 characters"
+    ))]
+    pub client_order_id: Option<String>,
 }
 
 fn default_order_type() -> OrderTypeInput {
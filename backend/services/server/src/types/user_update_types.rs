@@ -0,0 +1,43 @@
+use engine::types::db_event_types::{
+    BalanceUpdatedEvent, DbEvent, OrderCancelledEvent, OrderFilledEvent, OrderModifiedEvent,
+    OrderPlacedEvent, PositionUpdatedEvent,
+};
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserUpdate {
+    OrderAccepted(OrderPlacedEvent),
+    OrderModified(OrderModifiedEvent),
+    OrderCancelled(OrderCancelledEvent),
+    PartialFill(OrderFilledEvent),
+    Fill(OrderFilledEvent),
+    Balance(BalanceUpdatedEvent),
+    Position(PositionUpdatedEvent),
+}
+
+impl UserUpdate {
+    pub fn from_db_event(event: DbEvent) -> Option<Self> {
+        match event {
+            DbEvent::OrderPlaced(e) => Some(UserUpdate::OrderAccepted(e)),
+            DbEvent::OrderModified(e) => Some(UserUpdate::OrderModified(e)),
+            DbEvent::OrderCancelled(e) => Some(UserUpdate::OrderCancelled(e)),
+            DbEvent::OrderFilled(e) if e.status == "filled" => Some(UserUpdate::Fill(e)),
+            DbEvent::OrderFilled(e) => Some(UserUpdate::PartialFill(e)),
+            DbEvent::BalanceUpdated(e) => Some(UserUpdate::Balance(e)),
+            DbEvent::PositionUpdated(e) => Some(UserUpdate::Position(e)),
+            _ => None,
+        }
+    }
+
+    pub fn user_id(&self) -> u64 {
+        match self {
+            UserUpdate::OrderAccepted(e) => e.user_id,
+            UserUpdate::OrderModified(e) => e.user_id,
+            UserUpdate::OrderCancelled(e) => e.user_id,
+            UserUpdate::PartialFill(e) | UserUpdate::Fill(e) => e.user_id,
+            UserUpdate::Balance(e) => e.user_id,
+            UserUpdate::Position(e) => e.user_id,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct WithdrawalInput {
+    #[validate(range(min = 1, message = "Amount must be greater than 0"))]
+    pub amount: i64,
+}
+
+#[derive(Deserialize, Validate, Debug, Default)]
+pub struct RejectWithdrawalInput {
+    #[serde(default)]
+    #[validate(length(max = 500, message = "Reason must be at most 500 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct WithdrawalQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
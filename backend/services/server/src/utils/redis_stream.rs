@@ -1,3 +1,4 @@
+use fred::prelude::*;
 use redis_client::{RedisManager, RedisRequest, RedisResponse};
 use serde::Serialize;
 use std::collections::HashMap;
@@ -75,3 +76,70 @@ pub fn resolve_pending_request(request_id: String, response: RedisResponse<serde
         }
     });
 }
+
+const TAIL_READ_BATCH_SIZE: u64 = 500;
+
+// Follows a stream from its current tail without a consumer group, handing each `data`
+// payload to `on_message`. Used for fan-out feeds where history is served separately.
+pub fn spawn_stream_tail<F>(stream: &'static str, mut on_message: F)
+where
+    F: FnMut(&str) + Send + 'static,
+{
+    let Some(redis_manager) = RedisManager::global() else {
+        log::error!("Redis manager not initialized, cannot follow {}", stream);
+        return;
+    };
+    let client = redis_manager.client();
+
+    tokio::spawn(async move {
+        let mut last_id = match client
+            .xrevrange_values::<String, String, String, _, _, _>(stream, "+", "-", Some(1))
+            .await
+        {
+            Ok(latest) => latest
+                .into_iter()
+                .next()
+                .map(|(id, _)| id)
+                .unwrap_or_else(|| "0".to_string()),
+            Err(e) => {
+                log::warn!("Could not read {} tail, starting from 0: {}", stream, e);
+                "0".to_string()
+            }
+        };
+        log::info!("Following {} after {}", stream, last_id);
+
+        loop {
+            let streams = match client
+                .xread_map::<String, String, String, String, _, _>(
+                    Some(TAIL_READ_BATCH_SIZE),
+                    None,
+                    stream,
+                    last_id.as_str(),
+                )
+                .await
+            {
+                Ok(streams) => streams,
+                Err(e) => {
+                    log::error!("Error reading from stream {}: {}", stream, e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            let mut read = 0;
+            for (_, messages) in streams {
+                for (id, fields) in messages {
+                    read += 1;
+                    last_id = id;
+                    if let Some(data) = fields.get("data") {
+                        on_message(data);
+                    }
+                }
+            }
+
+            if read == 0 {
+                tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+            }
+        }
+    });
+}
@@ -1,3 +1,4 @@
+use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
 use redis_client::RedisResponse;
 use serde::de::DeserializeOwned;
@@ -9,15 +10,24 @@ where
     T: Serialize + DeserializeOwned + Clone,
 {
     if response.status_code >= 400 {
-        let status = actix_web::http::StatusCode::from_u16(response.status_code as u16)
-            .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+        let status = error_status(response);
 
         return HttpResponse::build(status).json(json!({
             "status": if response.success { "success" } else { "error" },
             "message": response.message,
+            "code": response.error_code,
             "data": response.data
         }));
     }
 
     HttpResponse::Ok().json(response.data.clone())
 }
+
+/// HTTP status for a failed service response. The engine already picks the status from its
+/// error code, so the status code the service sent is used as is.
+pub fn error_status<T>(response: &RedisResponse<T>) -> StatusCode
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    StatusCode::from_u16(response.status_code as u16).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}